@@ -1,8 +1,9 @@
 use minepack::api::curseforge::CurseforgeClient;
+use minepack::utils::{Env, RealEnv};
 
 #[tokio::main]
 async fn main() {
-    let client = CurseforgeClient::new().unwrap();
+    let client = CurseforgeClient::new(&RealEnv::new()).unwrap();
     let data = client.download_mod_file(1030830, 6332315).await.unwrap();
     assert!(!data.is_empty());
     println!("Downloaded mod file successfully");
@@ -1,8 +1,9 @@
 use minepack::api::curseforge::CurseforgeClient;
+use minepack::utils::{Env, RealEnv};
 
 #[tokio::main]
 async fn main() {
-    let client = CurseforgeClient::new().unwrap();
+    let client = CurseforgeClient::new(&RealEnv::new()).unwrap();
     let data = client.get_mod_info(238222).await.unwrap();
     dbg!(data);
 }
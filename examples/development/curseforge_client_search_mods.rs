@@ -1,9 +1,10 @@
 use minepack::api::curseforge::schema::SearchModsRequestQuery;
 use minepack::api::curseforge::CurseforgeClient;
+use minepack::utils::{Env, RealEnv};
 
 #[tokio::main]
 async fn main() {
-    let client = CurseforgeClient::new().unwrap();
+    let client = CurseforgeClient::new(&RealEnv::new()).unwrap();
     let mods = client
         .search_mods(&SearchModsRequestQuery {
             search_filter: Some("oritech".to_string()),
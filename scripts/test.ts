@@ -9,7 +9,8 @@ import $ from "jsr:@david/dax";
  */
 enum Host {
   Curseforge = "api.curseforge.com",
-  Modrinth = "api.modrinth.com", // Not implemented yet
+  CurseforgeUpload = "minecraft.curseforge.com",
+  Modrinth = "api.modrinth.com",
 }
 
 /**
@@ -38,6 +39,8 @@ async function setupMockServer(): Promise<
 
   // Process Curseforge mocks
   await processMockFiles(Host.Curseforge, mocksDir, endpoints);
+  await processMockFiles(Host.CurseforgeUpload, mocksDir, endpoints);
+  await processMockFiles(Host.Modrinth, mocksDir, endpoints);
 
   // Set up server
   const server = Deno.serve({ port, signal }, async (request) => {
@@ -59,6 +62,17 @@ async function setupMockServer(): Promise<
       return curseforgeGetModFiles(body);
     }
 
+    const upload = path.match(
+      /^\/minecraft\.curseforge\.com\/api\/projects\/(\d+)\/upload-file$/,
+    );
+    if (request.method === "POST" && upload) {
+      return curseforgeUploadFile(request);
+    }
+
+    if (request.method === "POST" && path === "/api.modrinth.com/v2/version") {
+      return modrinthCreateVersion(request);
+    }
+
     // まずパスが完全に一致するエンドポイントを探す
     let mockData = endpoints.get(path);
 
@@ -108,26 +122,7 @@ type CurseforgeGetModsRequest = {
 };
 
 async function curseforgeGetMods(body: CurseforgeGetModsRequest) {
-  if (body.modIds.length === 1 && body.modIds[0] === 1030830) {
-    const content = await Deno.readTextFile(
-      join(
-        Deno.cwd(),
-        "tests",
-        "fixtures",
-        "api.curseforge.com",
-        "mods",
-        "post_1030830.json",
-      ),
-    );
-    return new Response(content, {
-      status: 200,
-      headers: {
-        "Content-Type": "application/json; charset=utf-8",
-      },
-    });
-  }
-
-  return new Response("Not Found", { status: 404 });
+  return await curseforgeGetByIds(body.modIds, ["mods"]);
 }
 
 type CurseforgeGetModFilesRequest = {
@@ -135,27 +130,131 @@ type CurseforgeGetModFilesRequest = {
 };
 
 async function curseforgeGetModFiles(body: CurseforgeGetModFilesRequest) {
-  if (body.fileIds.length === 1 && body.fileIds[0] === 6332315) {
-    const content = await Deno.readTextFile(
-      join(
-        Deno.cwd(),
-        "tests",
-        "fixtures",
-        "api.curseforge.com",
-        "mods",
-        "files",
-        "post_6332315.json",
-      ),
+  return await curseforgeGetByIds(body.fileIds, ["mods", "files"]);
+}
+
+/**
+ * Answers a request for several projects or files from the fixtures `post_<id>.json` in
+ * `dir`. Like the real API, unknown IDs are left out and the results are sorted by ID
+ * instead of following the order of the request.
+ */
+async function curseforgeGetByIds(ids: number[], dir: string[]) {
+  const data: { id: number }[] = [];
+  for (const id of new Set(ids)) {
+    const path = join(
+      Deno.cwd(),
+      "tests",
+      "fixtures",
+      "api.curseforge.com",
+      ...dir,
+      `post_${id}.json`,
     );
-    return new Response(content, {
+    if (await exists(path)) {
+      data.push(...JSON.parse(await Deno.readTextFile(path)).data);
+    }
+  }
+
+  if (data.length === 0) {
+    return new Response("Not Found", { status: 404 });
+  }
+
+  data.sort((a, b) => a.id - b.id);
+  return new Response(JSON.stringify({ data }), {
+    status: 200,
+    headers: {
+      "Content-Type": "application/json; charset=utf-8",
+    },
+  });
+}
+
+type CurseforgeUploadMetadata = {
+  changelog: string;
+  changelogType: string;
+  displayName?: string;
+  gameVersions: number[];
+  releaseType: string;
+};
+
+/**
+ * Stand-in for the upload API, checks the request and returns the ID of a new file.
+ */
+async function curseforgeUploadFile(request: Request) {
+  if (!request.headers.get("X-Api-Token")) {
+    return new Response("Unauthorized", { status: 401 });
+  }
+
+  const form = await request.formData();
+  const metadata = form.get("metadata");
+  const file = form.get("file");
+  if (typeof metadata !== "string" || !(file instanceof File)) {
+    return new Response("Missing metadata or file", { status: 400 });
+  }
+
+  const parsed: CurseforgeUploadMetadata = JSON.parse(metadata);
+  if (
+    !parsed.changelog || parsed.gameVersions.length === 0 ||
+    !["alpha", "beta", "release"].includes(parsed.releaseType)
+  ) {
+    return new Response("Invalid metadata", { status: 400 });
+  }
+
+  return new Response(JSON.stringify({ id: 7000001 }), {
+    status: 200,
+    headers: {
+      "Content-Type": "application/json; charset=utf-8",
+    },
+  });
+}
+
+type ModrinthCreateVersionRequest = {
+  project_id: string;
+  version_number: string;
+  game_versions: string[];
+  loaders: string[];
+  file_parts: string[];
+};
+
+async function modrinthCreateVersion(request: Request) {
+  if (!request.headers.get("Authorization")) {
+    return new Response("Unauthorized", { status: 401 });
+  }
+
+  const form = await request.formData();
+  const data = form.get("data");
+  if (typeof data !== "string") {
+    return new Response("Missing data", { status: 400 });
+  }
+
+  const parsed: ModrinthCreateVersionRequest = JSON.parse(data);
+  // Like the real API, versions are only created with the base62 project ID, not a slug
+  if (!/^[0-9A-Za-z]{8}$/.test(parsed.project_id)) {
+    return new Response("Invalid project_id", { status: 400 });
+  }
+  // A project the token isn't allowed to publish to
+  if (parsed.project_id === "LoCkEd01") {
+    return new Response("Forbidden", { status: 403 });
+  }
+  if (
+    !parsed.version_number || parsed.game_versions.length === 0 ||
+    parsed.loaders.length === 0 ||
+    !parsed.file_parts.every((part) => form.get(part) instanceof File)
+  ) {
+    return new Response("Invalid data", { status: 400 });
+  }
+
+  return new Response(
+    JSON.stringify({
+      id: "MOCKVER1",
+      project_id: parsed.project_id,
+      version_number: parsed.version_number,
+    }),
+    {
       status: 200,
       headers: {
         "Content-Type": "application/json; charset=utf-8",
       },
-    });
-  }
-
-  return new Response("Not Found", { status: 404 });
+    },
+  );
 }
 
 /**
@@ -0,0 +1,64 @@
+//! The fingerprint Curseforge identifies files by (`fileFingerprint`).
+//!
+//! It is a 32-bit MurmurHash2 with seed 1 over the file content with all whitespace bytes
+//! (tab, line feed, carriage return and space) removed.
+
+const SEED: u32 = 1;
+const M: u32 = 0x5bd1e995;
+const R: u32 = 24;
+
+/// Computes the Curseforge fingerprint of a file's content
+pub fn fingerprint(content: &[u8]) -> u32 {
+    let normalized: Vec<u8> = content
+        .iter()
+        .copied()
+        .filter(|byte| !matches!(byte, 9 | 10 | 13 | 32))
+        .collect();
+    murmur2(&normalized, SEED)
+}
+
+fn murmur2(data: &[u8], seed: u32) -> u32 {
+    let mut hash = seed ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M);
+        hash ^= k;
+    }
+
+    let tail = chunks.remainder();
+    if tail.len() >= 3 {
+        hash ^= (tail[2] as u32) << 16;
+    }
+    if tail.len() >= 2 {
+        hash ^= (tail[1] as u32) << 8;
+    }
+    if !tail.is_empty() {
+        hash ^= tail[0] as u32;
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        // Whitespace doesn't change the fingerprint
+        assert_eq!(fingerprint(b"hello world\r\n"), fingerprint(b"helloworld"));
+        assert_ne!(fingerprint(b"helloworld"), fingerprint(b"helloworle"));
+
+        assert_eq!(murmur2(b"", 1), 0x5bd15e36);
+        assert_eq!(fingerprint(b"helloworld"), murmur2(b"helloworld", 1));
+    }
+}
@@ -1,57 +1,53 @@
+pub mod fingerprint;
 pub mod schema;
+pub mod upload;
 
 use anyhow::{anyhow, Context, Result};
 use reqwest::header::{HeaderMap, HeaderValue};
 use schema::GetDownloadUrlResponse;
-use std::env;
-use std::fs;
 use url::Url;
 
+use crate::models::config::ManualDownload;
+use crate::utils;
 use crate::utils::errors::MinepackError;
 
-// テスト環境でない場合は本番のAPIを使用
-const CURSEFORGE_API_URL_PROD: &str = "https://api.curseforge.com/v1";
+/// The official Curseforge API, mock servers are supplied through `utils::Env`
+pub const CURSEFORGE_API_URL_PROD: &str = "https://api.curseforge.com/v1";
 const MINECRAFT_GAME_ID: u32 = 432;
-const CONFIG_FILE_NAME: &str = ".minepack-config";
+/// IDs sent in a single request to the endpoints that look up several projects or files
+const MAX_IDS_PER_REQUEST: usize = 500;
 
+/// CDN hosts serving Curseforge files, tried when the download URL from the API fails
+pub const DEFAULT_CDN_HOSTS: [&str; 2] = ["edge.forgecdn.net", "mediafilez.forgecdn.net"];
+
+#[derive(Clone)]
 pub struct CurseforgeClient {
     client: reqwest::Client,
     base_url: String,
+    /// Configured CDN hosts, replacing the download URLs returned by the API
+    cdn_hosts: Vec<String>,
 }
 
 impl CurseforgeClient {
-    pub fn new() -> Result<Self> {
+    pub fn new<E: utils::Env>(env: &E) -> Result<Self> {
         // Try to get API key from different sources
-        let api_key = Self::get_api_key()?;
-
-        let mut headers = HeaderMap::new();
-        headers.insert("x-api-key", HeaderValue::from_str(&api_key)?);
+        let api_key = Self::get_api_key(env)?;
+        Self::with_api_key(env, &api_key)
+    }
 
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
+    /// Creates a new client using the given API key. The API URL and CDN hosts come from
+    /// the project or user configuration, otherwise from the env.
+    pub fn with_api_key<E: utils::Env>(env: &E, api_key: &str) -> Result<Self> {
+        let settings = utils::load_curseforge_settings(env)?;
+        let base_url = settings.api_url.unwrap_or_else(|| env.curseforge_api_url());
 
-        // テスト環境の場合はモックサーバーのURLを使用
-        let base_url = if cfg!(any(test, feature = "mock")) {
-            match env::var("MOCK_SERVER_URL") {
-                Ok(url) => format!("{}/api.curseforge.com/v1", url),
-                Err(_) => "http://127.0.0.1:25569/api.curseforge.com/v1".to_string(),
-            }
-        } else {
-            CURSEFORGE_API_URL_PROD.to_string()
-        };
-
-        Ok(Self { client, base_url })
+        Ok(Self::with_base_url(api_key, &base_url)?.with_cdn_hosts(settings.cdn_hosts))
     }
 
-    /// Creates a new client with a custom base URL (useful for testing with mock server)
-    #[allow(dead_code)]
-    #[cfg(test)]
-    pub fn new_with_base_url(base_url: &str) -> Result<Self> {
-        let api_key = Self::get_api_key()?;
-
+    /// Creates a new client for the API at `base_url`
+    pub fn with_base_url(api_key: &str, base_url: &str) -> Result<Self> {
         let mut headers = HeaderMap::new();
-        headers.insert("x-api-key", HeaderValue::from_str(&api_key)?);
+        headers.insert("x-api-key", HeaderValue::from_str(api_key)?);
 
         let client = reqwest::Client::builder()
             .default_headers(headers)
@@ -59,45 +55,59 @@ impl CurseforgeClient {
 
         Ok(Self {
             client,
-            base_url: base_url.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            cdn_hosts: Vec::new(),
         })
     }
 
-    fn get_api_key() -> Result<String> {
+    /// Downloads files from the given CDN hosts (or base URLs) instead of the API's download URLs
+    pub fn with_cdn_hosts(mut self, cdn_hosts: Vec<String>) -> Self {
+        self.cdn_hosts = cdn_hosts;
+        self
+    }
+
+    fn get_api_key<E: utils::Env>(env: &E) -> Result<String> {
         // First try environment variable
-        if let Ok(key) = env::var("CURSEFORGE_API_KEY") {
+        if let Some(key) = env.var("CURSEFORGE_API_KEY") {
             return Ok(key);
         }
 
-        // Then try config file in home directory
-        if let Some(mut home_dir) = dirs::home_dir() {
-            home_dir.push(CONFIG_FILE_NAME);
-            if home_dir.exists() {
-                let content = fs::read_to_string(home_dir)?;
-                for line in content.lines() {
-                    if let Some(key) = line.strip_prefix("api_key=") {
-                        return Ok(key.trim().to_string());
-                    }
-                }
-            }
-        }
-
-        // For development/demo, provide a placeholder key
-        #[cfg(debug_assertions)]
-        {
-            eprintln!("WARNING: Using placeholder API key for development. This will not work with the real API.");
-            Ok("$2a$10$This.Is.A.Development.Key.For.Local.Testing.Only".to_string())
+        // Then try the user configuration
+        if let Some(key) = utils::load_user_config(env)?.curseforge.credentials.api_key {
+            return Ok(key);
         }
 
-        // In release mode, we require a real API key
-        #[cfg(not(debug_assertions))]
         Err(anyhow!(MinepackError::ApiKeyNotFound))
     }
 
+    /// Checks whether the API accepts the client's API key
+    pub async fn validate_api_key(&self) -> Result<bool> {
+        let url = format!("{}/games/{}", self.base_url, MINECRAFT_GAME_ID);
+        let response = self.client.get(&url).send().await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => Ok(false),
+            status => Err(anyhow!(MinepackError::CurseforgeApiError(format!(
+                "Failed to validate API key: {}",
+                status
+            )))),
+        }
+    }
+
     pub async fn search_mods(
         &self,
         query: &schema::SearchModsRequestQuery,
     ) -> Result<Vec<schema::Mod>> {
+        let result = self.search_mods_paginated(query).await?;
+        Ok(result.data)
+    }
+
+    /// Searches mods and returns the results together with the pagination info
+    pub async fn search_mods_paginated(
+        &self,
+        query: &schema::SearchModsRequestQuery,
+    ) -> Result<schema::SearchModsResponse> {
         let mut url = Url::parse(&self.base_url)?;
         url.path_segments_mut()
             .map_err(|_| anyhow!(MinepackError::Unknown("Cannot modify URL path".to_string())))?
@@ -138,8 +148,10 @@ impl CurseforgeClient {
                 .append_pair("searchFilter", search_filter);
         }
         if let Some(sort_field) = &query.sort_field {
-            url.query_pairs_mut()
-                .append_pair("sort", (sort_field.clone() as u32).to_string().as_str());
+            url.query_pairs_mut().append_pair(
+                "sortField",
+                (sort_field.clone() as u32).to_string().as_str(),
+            );
         }
         if let Some(sort_order) = &query.sort_order {
             url.query_pairs_mut()
@@ -196,17 +208,27 @@ impl CurseforgeClient {
             .await
             .with_context(|| "Failed to parse search results from Curseforge API")?;
 
-        Ok(result.data)
+        Ok(result)
     }
 
+    /// Looks up several projects. Unavailable and deleted projects are left out and the
+    /// results aren't in the order of `mods_ids`, so match them by ID.
     pub async fn get_mod_infos(&self, mods_ids: Vec<u32>) -> Result<Vec<schema::Mod>> {
+        let mut mods = Vec::with_capacity(mods_ids.len());
+        for chunk in mods_ids.chunks(MAX_IDS_PER_REQUEST) {
+            mods.extend(self.get_mod_infos_chunk(chunk).await?);
+        }
+        Ok(mods)
+    }
+
+    async fn get_mod_infos_chunk(&self, mods_ids: &[u32]) -> Result<Vec<schema::Mod>> {
         let mut url = Url::parse(&self.base_url)?;
         url.path_segments_mut()
             .map_err(|_| anyhow!(MinepackError::Unknown("Cannot modify URL path".to_string())))?
             .push("mods");
 
         let parameters = schema::GetModsByIdsListRequestBody {
-            mod_ids: mods_ids.clone(),
+            mod_ids: mods_ids.to_vec(),
             filter_pc_only: true,
         };
         let response = self
@@ -358,7 +380,17 @@ impl CurseforgeClient {
         Ok(result.data)
     }
 
+    /// Looks up several files. Deleted files are left out and the results aren't in the
+    /// order of `file_ids`, so match them by ID.
     pub async fn get_file_infos(&self, file_ids: Vec<u32>) -> Result<Vec<schema::File>> {
+        let mut files = Vec::with_capacity(file_ids.len());
+        for chunk in file_ids.chunks(MAX_IDS_PER_REQUEST) {
+            files.extend(self.get_file_infos_chunk(chunk).await?);
+        }
+        Ok(files)
+    }
+
+    async fn get_file_infos_chunk(&self, file_ids: &[u32]) -> Result<Vec<schema::File>> {
         let mut url = Url::parse(&self.base_url)?;
         url.path_segments_mut()
             .map_err(|_| anyhow!(MinepackError::Unknown("Cannot modify URL path".to_string())))?
@@ -366,7 +398,7 @@ impl CurseforgeClient {
             .push("files");
 
         let parameters = schema::GetModFilesRequestBody {
-            file_ids: file_ids.clone(),
+            file_ids: file_ids.to_vec(),
         };
         let response = self
             .client
@@ -396,6 +428,42 @@ impl CurseforgeClient {
         Ok(result.data)
     }
 
+    /// The changelog of a file as HTML
+    pub async fn get_mod_file_changelog(&self, mod_id: u32, file_id: u32) -> Result<String> {
+        let mut url = Url::parse(&self.base_url)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!(MinepackError::Unknown("Cannot modify URL path".to_string())))?
+            .push("mods")
+            .push(&mod_id.to_string())
+            .push("files")
+            .push(&file_id.to_string())
+            .push("changelog");
+
+        let response = self.client.get(url).send().await.with_context(|| {
+            format!(
+                "Failed to send request to Curseforge API for the changelog of mod ID {} file ID {}",
+                mod_id, file_id
+            )
+        })?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(MinepackError::CurseforgeApiError(format!(
+                "API request failed with status: {}",
+                response.status()
+            ))));
+        }
+
+        let changelog_response: schema::GetModFileChangelogResponse =
+            response.json().await.with_context(|| {
+                format!(
+                    "Failed to parse changelog for mod ID {} file ID {}",
+                    mod_id, file_id
+                )
+            })?;
+
+        Ok(changelog_response.data)
+    }
+
     pub async fn download_mod_file(&self, mod_id: u32, file_id: u32) -> Result<Vec<u8>> {
         let mut url = Url::parse(&self.base_url)?;
         url.path_segments_mut()
@@ -424,38 +492,141 @@ impl CurseforgeClient {
             .json()
             .await
             .with_context(|| "Failed to parse download URL")?;
-        let download_url = download_url_response
-            .data
-            .context("Failed to get download URL from response")?;
+        // Files whose author disallows third-party distribution have no download URL
+        let download_url = download_url_response.data.ok_or_else(|| {
+            anyhow!(MinepackError::ManualDownloadRequired(format!(
+                "mod ID {} file ID {}",
+                mod_id, file_id
+            )))
+        })?;
 
-        // Download the actual file
-        let mod_file = reqwest::get(&download_url)
-            .await
-            .with_context(|| format!("Failed to download mod file from {}", download_url))?;
+        // Download the actual file, trying the next CDN host when one fails
+        let mut last_error = None;
+        for url in self.download_urls(&download_url)? {
+            match download_file(&url).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(err) => last_error = Some(err),
+            }
+        }
 
-        if !mod_file.status().is_success() {
-            return Err(anyhow!(MinepackError::ModDownloadError(format!(
-                "Failed to download mod file with status: {}",
-                mod_file.status()
-            ))));
+        Err(last_error.unwrap_or_else(|| {
+            anyhow!(MinepackError::ModDownloadError(
+                "No download URL available".to_string()
+            ))
+        }))
+    }
+
+    /// The URLs to try for a download URL returned by the API. Configured CDN hosts replace
+    /// its host, otherwise the default CDN hosts are tried after it.
+    fn download_urls(&self, download_url: &str) -> Result<Vec<String>> {
+        let url = Url::parse(download_url)
+            .with_context(|| format!("Invalid download URL: {}", download_url))?;
+        let path = url.path();
+
+        let mut urls = Vec::new();
+        if self.cdn_hosts.is_empty() {
+            urls.push(download_url.to_string());
+            urls.extend(
+                DEFAULT_CDN_HOSTS
+                    .iter()
+                    .map(|host| format!("{}{}", cdn_base_url(host), path)),
+            );
+        } else {
+            urls.extend(
+                self.cdn_hosts
+                    .iter()
+                    .map(|host| format!("{}{}", cdn_base_url(host), path)),
+            );
         }
 
-        let bytes = mod_file
-            .bytes()
-            .await
-            .with_context(|| "Failed to read mod file bytes")?;
+        let mut seen = std::collections::HashSet::new();
+        urls.retain(|url| seen.insert(url.clone()));
+        Ok(urls)
+    }
+}
+
+/// Turns a configured CDN host into a base URL, full URLs (e.g. of a proxy) are kept as they are
+fn cdn_base_url(host: &str) -> String {
+    if host.contains("://") {
+        host.trim_end_matches('/').to_string()
+    } else {
+        format!("https://{}", host.trim_end_matches('/'))
+    }
+}
+
+/// Where to download a file by hand, if its author disallows third-party distribution
+pub fn manual_download(mod_info: &schema::Mod, file: &schema::File) -> Option<ManualDownload> {
+    if mod_info.allow_mod_distribution != Some(false) && file.download_url.is_some() {
+        return None;
+    }
 
-        Ok(bytes.to_vec())
+    let page_url = match &mod_info.links.website_url {
+        Some(url) => format!("{}/files/{}", url.trim_end_matches('/'), file.id),
+        None => project_page_url(mod_info.id),
+    };
+    Some(ManualDownload {
+        page_url,
+        fingerprint: file.file_fingerprint,
+    })
+}
+
+/// The Curseforge page of a project, redirects to its slug
+pub fn project_page_url(project_id: u32) -> String {
+    format!("https://www.curseforge.com/projects/{}", project_id)
+}
+
+/// The URL of a file on a Curseforge CDN host
+pub fn cdn_file_url(host: &str, file_id: u32, filename: &str) -> String {
+    format!(
+        "{}/files/{}/{}/{}",
+        cdn_base_url(host),
+        file_id / 1000,
+        file_id % 1000,
+        filename
+    )
+}
+
+async fn download_file(url: &str) -> Result<Vec<u8>> {
+    let response = reqwest::get(url)
+        .await
+        .with_context(|| format!("Failed to download mod file from {}", url))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(MinepackError::ModDownloadError(format!(
+            "Failed to download mod file from {} with status: {}",
+            url,
+            response.status()
+        ))));
     }
+
+    let bytes = response
+        .bytes()
+        .await
+        .with_context(|| "Failed to read mod file bytes")?;
+
+    Ok(bytes.to_vec())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn mock_server_url() -> String {
+        std::env::var("MOCK_SERVER_URL").unwrap_or_else(|_| "http://127.0.0.1:25569".to_string())
+    }
+
+    /// A client for the mock server started by `scripts/test.ts`
+    fn mock_client() -> CurseforgeClient {
+        CurseforgeClient::with_base_url(
+            "mock-api-key",
+            &format!("{}/api.curseforge.com/v1", mock_server_url()),
+        )
+        .unwrap()
+    }
+
     #[tokio::test]
     async fn test_get_mod_info() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test search mods
         let mods = client.get_mod_info(1030830).await.unwrap();
@@ -466,7 +637,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_mod_infos() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test search mods
         let mods = client.get_mod_infos(vec![1030830]).await.unwrap();
@@ -476,7 +647,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_search_mods() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test search mods
         let mods = client
@@ -492,7 +663,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_mod_file_info() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test get file info
         let file = client.get_mod_file_info(1030830, 6332315).await.unwrap();
@@ -501,9 +672,20 @@ mod tests {
         assert_eq!(file.mod_id, 1030830);
     }
 
+    #[tokio::test]
+    async fn test_get_mod_file_changelog() {
+        let client = mock_client();
+
+        let changelog = client
+            .get_mod_file_changelog(1030830, 6332315)
+            .await
+            .unwrap();
+        assert!(changelog.contains("Fixed a crash"));
+    }
+
     #[tokio::test]
     async fn test_get_mod_file_infos() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test get file info
         let files = client
@@ -521,7 +703,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_file_infos() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test get file info
         let files = client.get_file_infos(vec![6332315]).await.unwrap();
@@ -531,10 +713,76 @@ mod tests {
 
     #[tokio::test]
     async fn test_download_mod_file() {
-        let client = CurseforgeClient::new().unwrap();
+        let client = mock_client();
 
         // Test download mod file
         let bytes = client.download_mod_file(1030830, 6332315).await.unwrap();
         assert!(!bytes.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_download_falls_back_to_next_cdn_host() {
+        let client = mock_client().with_cdn_hosts(vec![
+            format!("{}/unavailable-mirror", mock_server_url()),
+            mock_server_url(),
+        ]);
+
+        let bytes = client.download_mod_file(1030830, 6332315).await.unwrap();
+        assert!(!bytes.is_empty());
+    }
+
+    #[test]
+    fn test_manual_download() {
+        let response: serde_json::Value = serde_json::from_str(include_str!(
+            "../../../tests/fixtures/api.curseforge.com/mods/post_1030830.json"
+        ))
+        .unwrap();
+        let mut mod_info: schema::Mod =
+            serde_json::from_value(response["data"][0].clone()).unwrap();
+        let response: serde_json::Value = serde_json::from_str(include_str!(
+            "../../../tests/fixtures/api.curseforge.com/mods/files/post_6332315.json"
+        ))
+        .unwrap();
+        let mut file: schema::File = serde_json::from_value(response["data"][0].clone()).unwrap();
+        mod_info.allow_mod_distribution = Some(true);
+        assert_eq!(manual_download(&mod_info, &file), None);
+
+        mod_info.allow_mod_distribution = Some(false);
+        let manual = manual_download(&mod_info, &file).unwrap();
+        assert_eq!(manual.fingerprint, file.file_fingerprint);
+        assert!(manual.page_url.ends_with("/files/6332315"));
+
+        // Some files of distributable projects have no download URL either
+        mod_info.allow_mod_distribution = None;
+        file.download_url = None;
+        assert!(manual_download(&mod_info, &file).is_some());
+    }
+
+    #[test]
+    fn test_download_urls() {
+        let download_url = "https://edge.forgecdn.net/files/6332/315/oritech.jar";
+
+        let urls = mock_client().download_urls(download_url).unwrap();
+        assert_eq!(
+            urls,
+            [
+                download_url,
+                "https://mediafilez.forgecdn.net/files/6332/315/oritech.jar"
+            ]
+        );
+
+        let urls = mock_client()
+            .with_cdn_hosts(vec!["https://cache.example.com/forgecdn/".to_string()])
+            .download_urls(download_url)
+            .unwrap();
+        assert_eq!(
+            urls,
+            ["https://cache.example.com/forgecdn/files/6332/315/oritech.jar"]
+        );
+
+        assert_eq!(
+            cdn_file_url("mediafilez.forgecdn.net", 6332315, "oritech.jar"),
+            "https://mediafilez.forgecdn.net/files/6332/315/oritech.jar"
+        );
+    }
 }
@@ -77,7 +77,7 @@ pub struct SearchModsRequestQuery {
     pub game_versions: Option<Vec<String>>,
     #[serde(rename = "searchFilter")]
     pub search_filter: Option<String>,
-    #[serde(rename = "searchFilterType")]
+    #[serde(rename = "sortField")]
     pub sort_field: Option<ModsSearchSortField>,
     #[serde(rename = "sortOrder")]
     pub sort_order: Option<SortOrder>,
@@ -93,6 +93,7 @@ pub struct SearchModsRequestQuery {
     /// A zero based index of the first item to include in the response, the limit is: (index + pageSize <= 10,000).
     pub index: Option<u32>,
     /// The number of items to include in the response, the default/maximum value is 50.
+    #[serde(rename = "pageSize")]
     pub page_size: Option<u32>,
 }
 
@@ -139,6 +140,28 @@ impl From<ModsSearchSortField> for u32 {
     }
 }
 
+impl FromStr for ModsSearchSortField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "featured" => Ok(ModsSearchSortField::Featured),
+            "popularity" => Ok(ModsSearchSortField::Popularity),
+            "last-updated" => Ok(ModsSearchSortField::LastUpdated),
+            "name" => Ok(ModsSearchSortField::Name),
+            "author" => Ok(ModsSearchSortField::Author),
+            "downloads" => Ok(ModsSearchSortField::TotalDownloads),
+            "category" => Ok(ModsSearchSortField::Category),
+            "game-version" => Ok(ModsSearchSortField::GameVersion),
+            "early-access" => Ok(ModsSearchSortField::EarlyAccess),
+            "featured-released" => Ok(ModsSearchSortField::FeaturedReleased),
+            "released" => Ok(ModsSearchSortField::ReleasedDate),
+            "rating" => Ok(ModsSearchSortField::Rating),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum SortOrder {
     #[serde(rename = "asc")]
@@ -182,6 +205,13 @@ pub struct GetDownloadUrlResponse {
     pub data: Option<String>,
 }
 
+/// Response from GET /v1/mods/{modId}/files/{fileId}/changelog
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetModFileChangelogResponse {
+    /// The changelog as HTML
+    pub data: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Mod {
     pub id: u32,
@@ -0,0 +1,211 @@
+//! Client for the Curseforge upload API, which authors use to publish files to their projects.
+//! It lives on a different host than the core API and authenticates with an API token from
+//! the author's account settings instead of an API key.
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::models::config::ReleaseType;
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// The official upload API, mock servers are supplied through `utils::Env`
+pub const CURSEFORGE_UPLOAD_URL_PROD: &str = "https://minecraft.curseforge.com";
+
+/// Response item of GET /api/game/versions
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameVersion {
+    pub id: u32,
+    #[serde(rename = "gameVersionTypeID")]
+    pub game_version_type_id: u32,
+    pub name: String,
+    pub slug: String,
+}
+
+/// Response item of GET /api/game/version-types
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameVersionType {
+    pub id: u32,
+    pub name: String,
+    pub slug: String,
+}
+
+/// The `metadata` part of POST /api/projects/{projectId}/upload-file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadMetadata {
+    pub changelog: String,
+    /// text, html or markdown
+    #[serde(rename = "changelogType")]
+    pub changelog_type: String,
+    #[serde(rename = "displayName", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(rename = "gameVersions")]
+    pub game_versions: Vec<u32>,
+    #[serde(rename = "releaseType")]
+    pub release_type: ReleaseType,
+}
+
+/// Response from POST /api/projects/{projectId}/upload-file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadResponse {
+    /// ID of the uploaded file
+    pub id: u32,
+}
+
+#[derive(Clone)]
+pub struct CurseforgeUploadClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl CurseforgeUploadClient {
+    /// Creates a new client for the env's upload API, authenticated with the given API token
+    pub fn with_token<E: utils::Env>(env: &E, token: &str) -> Result<Self> {
+        Self::with_base_url(token, &env.curseforge_upload_url())
+    }
+
+    /// Creates a new client for the upload API at `base_url`
+    pub fn with_base_url(token: &str, base_url: &str) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Api-Token", HeaderValue::from_str(token)?);
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub async fn get_game_versions(&self) -> Result<Vec<GameVersion>> {
+        self.get_json("api/game/versions").await
+    }
+
+    pub async fn get_game_version_types(&self) -> Result<Vec<GameVersionType>> {
+        self.get_json("api/game/version-types").await
+    }
+
+    /// The IDs of the game versions matching a Minecraft version and a mod loader
+    pub async fn find_game_version_ids(
+        &self,
+        minecraft_version: &str,
+        loader: Option<&str>,
+    ) -> Result<Vec<u32>> {
+        let types = self.get_game_version_types().await?;
+        let versions = self.get_game_versions().await?;
+
+        // Minecraft versions are grouped into types per minor version, e.g. `minecraft-1-21`
+        let type_ids = |matches: &dyn Fn(&str) -> bool| -> Vec<u32> {
+            types
+                .iter()
+                .filter(|version_type| matches(&version_type.slug))
+                .map(|version_type| version_type.id)
+                .collect()
+        };
+        let minecraft_types = type_ids(&|slug| slug.starts_with("minecraft-"));
+        let loader_types = type_ids(&|slug| slug == "modloader");
+
+        let mut ids: Vec<u32> = versions
+            .iter()
+            .filter(|version| {
+                minecraft_types.contains(&version.game_version_type_id)
+                    && version.name == minecraft_version
+            })
+            .map(|version| version.id)
+            .collect();
+        if let Some(loader) = loader {
+            ids.extend(
+                versions
+                    .iter()
+                    .filter(|version| {
+                        loader_types.contains(&version.game_version_type_id)
+                            && version.name.eq_ignore_ascii_case(loader)
+                    })
+                    .map(|version| version.id),
+            );
+        }
+        Ok(ids)
+    }
+
+    /// Uploads a file to a project, returns the ID of the new file
+    pub async fn upload_file(
+        &self,
+        project_id: u32,
+        metadata: &UploadMetadata,
+        path: &Path,
+    ) -> Result<u32> {
+        let url = format!("{}/api/projects/{}/upload-file", self.base_url, project_id);
+
+        let content = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "modpack.zip".to_string());
+        let form = Form::new()
+            .text(
+                "metadata",
+                serde_json::to_string(metadata).context("Failed to serialize upload metadata")?,
+            )
+            .part(
+                "file",
+                Part::bytes(content)
+                    .file_name(file_name)
+                    .mime_str("application/zip")?,
+            );
+
+        let response = self
+            .client
+            .post(&url)
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| {
+                format!("Failed to upload file to Curseforge project {}", project_id)
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!(MinepackError::CurseforgeApiError(format!(
+                "Upload failed with status: {} {}",
+                status,
+                body.trim()
+            ))));
+        }
+
+        let result: UploadResponse = response
+            .json()
+            .await
+            .context("Failed to parse upload response from Curseforge")?;
+        Ok(result.id)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = format!("{}/{}", self.base_url, path);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", url))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(MinepackError::CurseforgeApiError(format!(
+                "API request failed with status: {}",
+                response.status()
+            ))));
+        }
+
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse response from {}", url))
+    }
+}
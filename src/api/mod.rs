@@ -1 +1,2 @@
 pub mod curseforge;
+pub mod modrinth;
@@ -0,0 +1,190 @@
+pub mod schema;
+
+use crate::utils;
+use crate::utils::errors::MinepackError;
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
+use reqwest::multipart::{Form, Part};
+use std::path::Path;
+
+/// The official Modrinth API, mock servers are supplied through `utils::Env`
+pub const MODRINTH_API_URL_PROD: &str = "https://api.modrinth.com/v2";
+
+/// Hosts Modrinth accepts in the `downloads` of the files of a published `.mrpack`
+pub const ACCEPTED_DOWNLOAD_HOSTS: [&str; 4] = [
+    "cdn.modrinth.com",
+    "github.com",
+    "raw.githubusercontent.com",
+    "gitlab.com",
+];
+
+/// Modrinth asks API clients to identify themselves with a user agent
+const MINEPACK_USER_AGENT: &str = concat!("minepack/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Clone)]
+pub struct ModrinthClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl ModrinthClient {
+    /// Creates a new client for the env's API, authenticated with the configured token
+    pub fn new<E: utils::Env>(env: &E) -> Result<Self> {
+        let token = Self::get_token(env)?;
+        Self::with_token(env, &token)
+    }
+
+    /// Creates a new client for the env's API, authenticated with the given personal access token
+    pub fn with_token<E: utils::Env>(env: &E, token: &str) -> Result<Self> {
+        Self::with_base_url(token, &env.modrinth_api_url())
+    }
+
+    /// Creates a new client for the API at `base_url`
+    pub fn with_base_url(token: &str, base_url: &str) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(token)?);
+        headers.insert(USER_AGENT, HeaderValue::from_static(MINEPACK_USER_AGENT));
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// The token from the `MODRINTH_TOKEN` environment variable or the user configuration
+    pub fn get_token<E: utils::Env>(env: &E) -> Result<String> {
+        // First try environment variable
+        if let Some(token) = env.var("MODRINTH_TOKEN") {
+            return Ok(token);
+        }
+
+        // Then try the user configuration
+        if let Some(token) = utils::load_user_config(env)?.modrinth.api_key {
+            return Ok(token);
+        }
+
+        Err(anyhow!(MinepackError::ModrinthTokenNotFound))
+    }
+
+    /// Checks whether the API accepts the client's token
+    pub async fn validate_token(&self) -> Result<bool> {
+        let url = format!("{}/user", self.base_url);
+        let response = self.client.get(&url).send().await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => Ok(false),
+            status => Err(anyhow!(MinepackError::Unknown(format!(
+                "Failed to validate Modrinth token: {}",
+                status
+            )))),
+        }
+    }
+
+    /// Looks up a project by its ID or slug. Projects the token can't see, e.g. drafts of
+    /// other users, aren't found.
+    pub async fn get_project(&self, id_or_slug: &str) -> Result<schema::Project> {
+        let url = format!("{}/project/{}", self.base_url, id_or_slug);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to look up Modrinth project {}", id_or_slug))?;
+
+        match response.status() {
+            status if status.is_success() => response
+                .json()
+                .await
+                .context("Failed to parse the Modrinth project"),
+            reqwest::StatusCode::NOT_FOUND => Err(anyhow!(MinepackError::ModrinthProjectNotFound(
+                id_or_slug.to_string()
+            ))),
+            reqwest::StatusCode::UNAUTHORIZED => Err(anyhow!(MinepackError::ModrinthApiError(
+                "The Modrinth token was rejected. Run 'minepack auth modrinth' to replace it"
+                    .to_string()
+            ))),
+            status => Err(anyhow!(MinepackError::ModrinthApiError(format!(
+                "Looking up project {} failed with status: {}",
+                id_or_slug, status
+            )))),
+        }
+    }
+
+    /// Creates a new version of a project with `file` as its primary file. The project must
+    /// be given by its ID, see `get_project`.
+    pub async fn create_version(
+        &self,
+        request: &schema::CreateVersionRequest,
+        file: &Path,
+    ) -> Result<schema::Version> {
+        let url = format!("{}/version", self.base_url);
+
+        let content = tokio::fs::read(file)
+            .await
+            .with_context(|| format!("Failed to read file: {}", file.display()))?;
+        let file_name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "modpack.mrpack".to_string());
+        let part_name = request
+            .primary_file
+            .clone()
+            .or_else(|| request.file_parts.first().cloned())
+            .unwrap_or_else(|| "file".to_string());
+        let form = Form::new()
+            .text(
+                "data",
+                serde_json::to_string(request).context("Failed to serialize version data")?,
+            )
+            .part(
+                part_name,
+                Part::bytes(content)
+                    .file_name(file_name)
+                    .mime_str("application/x-modrinth-modpack+zip")?,
+            );
+
+        let response = self
+            .client
+            .post(&url)
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to create a version of Modrinth project {}",
+                    request.project_id
+                )
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!(MinepackError::ModrinthApiError(format!(
+                "Creating the version failed with status: {} {}",
+                status,
+                body.trim()
+            ))));
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse the created version from Modrinth")
+    }
+}
+
+/// Whether Modrinth accepts a published `.mrpack` downloading a file from the URL
+pub fn is_accepted_download(url: &str) -> bool {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.host_str()
+                .map(|host| ACCEPTED_DOWNLOAD_HOSTS.contains(&host))
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `modrinth.index.json` of a `.mrpack` file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Index {
+    #[serde(rename = "formatVersion")]
+    pub format_version: u32,
+    pub game: String,
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<IndexFile>,
+    /// Minecraft and mod loader versions, keyed by `minecraft` or the loader ID
+    pub dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexFile {
+    /// Destination relative to the game directory
+    pub path: String,
+    pub hashes: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<IndexFileEnv>,
+    pub downloads: Vec<String>,
+    #[serde(rename = "fileSize")]
+    pub file_size: u64,
+}
+
+/// Whether a file is used on the client and the server
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct IndexFileEnv {
+    pub client: EnvSupport,
+    pub server: EnvSupport,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvSupport {
+    Required,
+    Optional,
+    Unsupported,
+}
+
+/// The `data` part of POST /v2/version
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateVersionRequest {
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+    pub dependencies: Vec<VersionDependency>,
+    pub game_versions: Vec<String>,
+    pub version_type: VersionType,
+    pub loaders: Vec<String>,
+    pub featured: bool,
+    /// Names of the multipart parts holding the files
+    pub file_parts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VersionDependency {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    /// required, optional, incompatible or embedded
+    pub dependency_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionType {
+    Release,
+    Beta,
+    Alpha,
+}
+
+/// Response from GET /v2/project/{id|slug}, only the fields minepack uses
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Project {
+    /// The base62 ID, the only identifier POST /v2/version accepts
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+}
+
+/// Response from POST /v2/version, only the fields minepack uses
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Version {
+    pub id: String,
+    pub project_id: String,
+    pub version_number: String,
+}
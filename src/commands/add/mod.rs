@@ -1,20 +1,48 @@
 use anyhow::{anyhow, bail, Context, Result};
-use dialoguer::{Confirm, Select};
-use serde_json;
 use std::collections::HashSet;
-use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
 use url::Url;
 
 use crate::api::curseforge::schema::{
     FileDependency, FileRelationType, Mod as CurseForgeModInfo, SearchModsRequestQuery,
 };
 use crate::api::curseforge::CurseforgeClient;
-use crate::models::config::{Link, Reference};
+use crate::models::config::{ContentType, Link, Reference};
+use crate::models::migration::CURRENT_SCHEMA_VERSION;
+use crate::utils::reference_store::{self, ReferenceStore};
 use crate::utils::{determine_mod_side_cf, errors::MinepackError};
 use crate::{api, models, utils};
 
-/// Parse a CurseForge mod URL to extract the slug and optional file ID
-fn parse_curseforge_mod_url(url: &Url) -> Result<(&str, Option<u32>)> {
+/// Options for the add command
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    /// Content type to search for (mod, resourcepack, shaderpack, datapack, world)
+    pub content_type: Option<String>,
+}
+
+/// A reference written by the add command
+#[derive(Debug, Clone)]
+pub struct AddedReference {
+    pub path: PathBuf,
+    pub reference: Reference,
+    /// Whether the reference was added as a dependency of the requested project
+    pub dependency: bool,
+}
+
+/// The result of adding a project to the modpack
+#[derive(Debug, Clone)]
+pub enum AddOutcome {
+    /// The project and the listed dependencies were added, the requested project comes first
+    Added(Vec<AddedReference>),
+    /// The project is pinned in the modpack and was left untouched
+    SkippedPinned(Reference),
+    /// The user declined adding the project
+    Cancelled,
+}
+
+/// Parse a CurseForge project URL to extract the slug, optional file ID and content type
+fn parse_curseforge_mod_url(url: &Url) -> Result<(&str, Option<u32>, ContentType)> {
     // Validate that it's a curseforge.com URL
     if url.host_str() != Some("www.curseforge.com") {
         bail!(MinepackError::InvalidCurseforgeModUrl);
@@ -24,9 +52,11 @@ fn parse_curseforge_mod_url(url: &Url) -> Result<(&str, Option<u32>)> {
     let path_segments: Vec<&str> = url.path().split('/').filter(|s| !s.is_empty()).collect();
 
     // Validate URL structure
-    if path_segments.len() < 3 || path_segments[0] != "minecraft" || path_segments[1] != "mc-mods" {
+    if path_segments.len() < 3 || path_segments[0] != "minecraft" {
         bail!(MinepackError::InvalidCurseforgeModUrl);
     }
+    let content_type = ContentType::from_curseforge_url_segment(path_segments[1])
+        .ok_or_else(|| anyhow!(MinepackError::InvalidCurseforgeModUrl))?;
 
     // If we have a file ID in the URL (/files/[file-id])
     let file_id = if path_segments.len() >= 5 && path_segments[3] == "files" {
@@ -41,28 +71,33 @@ fn parse_curseforge_mod_url(url: &Url) -> Result<(&str, Option<u32>)> {
     // Extract the slug from the URL
     let slug = path_segments[2];
 
-    Ok((slug, file_id))
+    Ok((slug, file_id, content_type))
 }
 
 /// Extract mod information from CurseForge URL and fetch the mod details
 async fn extract_mod_info_from_url(
+    env: &impl utils::Env,
     url_str: &str,
     client: &CurseforgeClient,
     minecraft_version: &str,
     mod_loader: &Option<models::config::ModLoader>,
-) -> Result<(CurseForgeModInfo, Option<u32>)> {
+) -> Result<(CurseForgeModInfo, Option<u32>, ContentType)> {
     // Parse the URL
     let url = Url::parse(url_str).context("Invalid URL format")?;
 
-    let (slug, file_id) = parse_curseforge_mod_url(&url)?;
+    let (slug, file_id, content_type) = parse_curseforge_mod_url(&url)?;
 
     // Search for mod by slug
-    println!("🔍 Looking up mod from URL: {}", slug);
+    env.reporter().message(&format!(
+        "🔍 Looking up {} from URL: {}",
+        content_type, slug
+    ));
     let search_results = client
         .search_mods(&SearchModsRequestQuery {
+            class_id: Some(content_type.class_id()),
             slug: Some(slug.to_string()),
             game_version: Some(minecraft_version.to_string()),
-            mod_loader_type: mod_loader.as_ref().map(|m| m.clone().into()),
+            mod_loader_type: loader_filter(content_type, mod_loader).map(|m| m.into()),
             ..Default::default()
         })
         .await
@@ -82,7 +117,7 @@ async fn extract_mod_info_from_url(
                 .get_mod_info(mod_info.id)
                 .await
                 .context("Failed to fetch detailed mod information")?;
-            Ok((complete_mod_info, file_id))
+            Ok((complete_mod_info, file_id, content_type))
         }
         None => bail!(MinepackError::NoModsFound(slug.to_string())),
     }
@@ -90,17 +125,21 @@ async fn extract_mod_info_from_url(
 
 /// Search for mods by name or keyword
 async fn search_mods_by_name(
+    env: &impl utils::Env,
     query: &str,
     client: &CurseforgeClient,
     minecraft_version: &str,
     mod_loader: &Option<models::config::ModLoader>,
+    content_type: ContentType,
 ) -> Result<CurseForgeModInfo> {
-    println!("🔍 Searching for mod: {}", query);
+    env.reporter()
+        .message(&format!("🔍 Searching for {}: {}", content_type, query));
     let search_results = client
         .search_mods(&SearchModsRequestQuery {
+            class_id: Some(content_type.class_id()),
             search_filter: Some(query.to_string()),
             game_version: Some(minecraft_version.to_string()),
-            mod_loader_type: mod_loader.as_ref().map(|m| m.clone().into()),
+            mod_loader_type: loader_filter(content_type, mod_loader).map(|m| m.into()),
             ..Default::default()
         })
         .await
@@ -116,11 +155,9 @@ async fn search_mods_by_name(
         .map(|m| format!("{} (ID: {}, Downloads: {})", m.name, m.id, m.download_count))
         .collect();
 
-    let selection = Select::new()
-        .with_prompt("Select a mod from the search results")
-        .items(&options)
-        .default(0)
-        .interact()
+    let selection = env
+        .prompter()
+        .select("Select a mod from the search results", &options, 0)
         .context("Failed to select mod from search results")?;
 
     // Get the complete mod info with a single API call
@@ -133,6 +170,7 @@ async fn search_mods_by_name(
 
 /// Select a compatible file version from the mod's available files
 async fn select_file_version(
+    env: &impl utils::Env,
     client: &CurseforgeClient,
     mod_info: &CurseForgeModInfo,
     minecraft_version: &str,
@@ -146,13 +184,13 @@ async fn select_file_version(
         .iter()
         .filter(|file| {
             file.game_versions.contains(&minecraft_version.to_string())
-                && mod_loader.as_ref().map_or(true, |loader| {
+                && mod_loader.as_ref().is_none_or(|loader| {
                     file.game_versions
                         .iter()
                         .any(|v| v.to_lowercase().contains(&loader.id.to_lowercase()))
                 })
         })
-        .map(|file| file.clone())
+        .cloned()
         .collect();
 
     if compatible_files.is_empty() {
@@ -182,24 +220,26 @@ async fn select_file_version(
         }
 
         // If the specified file ID isn't compatible or doesn't exist
-        println!(
+        env.reporter().warning(&format!(
             "Warning: The specified file ID {} is not compatible with Minecraft {}",
             file_id, minecraft_version
-        );
+        ));
 
         // If auto_select is true, just choose the first compatible version
         if auto_select {
-            println!("Automatically selecting first compatible version.");
+            env.reporter()
+                .message("Automatically selecting first compatible version.");
             return Ok(compatible_files[0].clone());
         }
 
         // Ask user what to do
-        let options = vec!["Select a compatible version instead", "Cancel installation"];
-        let selection = Select::new()
-            .with_prompt("What would you like to do?")
-            .items(&options)
-            .default(0)
-            .interact()
+        let options = vec![
+            "Select a compatible version instead".to_string(),
+            "Cancel installation".to_string(),
+        ];
+        let selection = env
+            .prompter()
+            .select("What would you like to do?", &options, 0)
             .context("Failed to get user selection")?;
 
         if selection == 1 {
@@ -220,96 +260,80 @@ async fn select_file_version(
         .map(|f| format!("{}: {}", f.id, f.display_name))
         .collect();
 
-    let file_selection = Select::new()
-        .with_prompt("Select a file version")
-        .items(&file_options)
-        .default(0)
-        .interact()
+    let file_selection = env
+        .prompter()
+        .select("Select a file version", &file_options, 0)
         .context("Failed to select file version")?;
 
     Ok(compatible_files[file_selection].clone())
 }
 
+/// Only mods are tied to a mod loader, so other content types must not be filtered by it
+fn loader_filter(
+    content_type: ContentType,
+    mod_loader: &Option<models::config::ModLoader>,
+) -> Option<models::config::ModLoader> {
+    match content_type {
+        ContentType::Mod => mod_loader.clone(),
+        _ => None,
+    }
+}
+
+/// Determines the content type of a project from its Curseforge class
+fn project_content_type(mod_info: &CurseForgeModInfo, fallback: ContentType) -> ContentType {
+    mod_info
+        .class_id
+        .and_then(ContentType::from_class_id)
+        .unwrap_or(fallback)
+}
+
 /// Create and save the mod reference file
 fn save_mod_reference(
     env: &impl utils::Env,
+    store: &mut ReferenceStore,
     mod_info: &CurseForgeModInfo,
     file: &api::curseforge::schema::File,
     side: models::config::Side,
-) -> Result<()> {
-    // Ensure the mods directory exists
-    let mods_dir = utils::get_mods_dir(env)?;
-    utils::ensure_dir_exists(&mods_dir)?;
-
+    content_type: ContentType,
+) -> Result<(PathBuf, Reference)> {
     // Get the slug for the mod and use it in the JSON filename
-    let slug = if mod_info.slug.is_empty() {
-        // If slug is empty, create a slug from the name
-        mod_info.name.to_lowercase().replace(' ', "-")
-    } else {
-        mod_info.slug.clone()
-    };
+    let slug = reference_store::project_slug(&mod_info.slug, &mod_info.name);
 
-    // Create the JSON reference file in the mods directory
-    let json_file_path = mods_dir.join(format!("{}.ex.json", slug));
-    let json_data = Reference {
+    let manual_download = api::curseforge::manual_download(mod_info, file);
+    if let Some(manual_download) = &manual_download {
+        env.reporter().warning(&format!(
+            "⚠️ The author of {} disallows third-party distribution, launchers can't download it. Download it by hand from {} and run 'minepack fetch-manual'",
+            mod_info.name, manual_download.page_url
+        ));
+    }
+
+    // Create the JSON reference file in the directory for its content type
+    let reference = Reference {
+        schema_version: CURRENT_SCHEMA_VERSION,
         name: mod_info.name.clone(),
-        filename: file.file_name.clone(),
+        filename: reference_store::sanitize_file_name(&file.file_name)
+            .unwrap_or_else(|| format!("{}-{}.jar", slug, file.id)),
         side,
         link: Link::CurseForge {
             project_id: mod_info.id,
             file_id: file.id,
             download_url: file.download_url.clone(),
         },
+        content_type,
+        pinned: false,
+        pin_reason: None,
+        optional: false,
+        description: None,
+        tags: Vec::new(),
+        manual_download,
     };
 
-    let json_content =
-        serde_json::to_string_pretty(&json_data).context("Failed to serialize mod JSON data")?;
-    fs::write(&json_file_path, json_content).with_context(|| {
-        format!(
-            "Failed to write JSON reference file: {}",
-            json_file_path.display()
-        )
-    })?;
-
-    Ok(())
-}
-
-/// Check if a mod is already installed
-fn is_mod_installed(env: &impl utils::Env, mod_id: u32) -> Result<bool> {
-    let mods_dir = utils::get_mods_dir(env)?;
-
-    if !mods_dir.exists() {
-        return Ok(false);
-    }
-
-    // Check all .ex.json files in the mods directory
-    for entry in fs::read_dir(mods_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        // Skip non-JSON files
-        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
-            continue;
-        }
-
-        // Read the JSON file
-        let content = fs::read_to_string(&path)?;
-        let reference: Result<Reference, _> = serde_json::from_str(&content);
-
-        if let Ok(reference) = reference {
-            match reference.link {
-                Link::CurseForge { project_id, .. } if project_id == mod_id => {
-                    return Ok(true);
-                }
-                _ => {}
-            }
-        }
-    }
-
-    Ok(false)
+    let stored = store.save(env, &slug, reference)?;
+    Ok((stored.path.clone(), stored.reference.clone()))
 }
 
 /// Process dependencies for a mod file
+#[allow(clippy::too_many_arguments)]
 async fn process_dependencies(
     env: &impl utils::Env,
     client: &CurseforgeClient,
@@ -318,6 +342,8 @@ async fn process_dependencies(
     mod_loader: &Option<models::config::ModLoader>,
     yes: bool,
     processed_mods: &mut HashSet<u32>,
+    store: &mut ReferenceStore,
+    added: &mut Vec<AddedReference>,
 ) -> Result<()> {
     // Check if the file has dependencies
     if let Some(dependencies) = &file.dependencies {
@@ -333,10 +359,10 @@ async fn process_dependencies(
             return Ok(());
         }
 
-        println!(
+        env.reporter().message(&format!(
             "\n📦 Found {} dependencies for this mod:",
             required_dependencies.len()
-        );
+        ));
 
         for dependency in required_dependencies {
             // Skip if we've already processed this mod ID
@@ -345,11 +371,11 @@ async fn process_dependencies(
             }
 
             // Check if the dependency is already installed
-            if is_mod_installed(env, dependency.mod_id)? {
-                println!(
+            if store.get_by_project_id(dependency.mod_id).is_some() {
+                env.reporter().message(&format!(
                     "  ✓ Dependency (ID: {}) is already installed",
                     dependency.mod_id
-                );
+                ));
                 processed_mods.insert(dependency.mod_id);
                 continue;
             }
@@ -358,34 +384,36 @@ async fn process_dependencies(
             let mod_info = match client.get_mod_info(dependency.mod_id).await {
                 Ok(info) => info,
                 Err(e) => {
-                    println!(
+                    env.reporter().warning(&format!(
                         "  ⚠ Failed to fetch info for dependency (ID: {}): {}",
                         dependency.mod_id, e
-                    );
+                    ));
                     continue;
                 }
             };
 
-            println!(
+            env.reporter().message(&format!(
                 "  → Required dependency: {} (ID: {})",
                 mod_info.name, mod_info.id
-            );
+            ));
 
             // Ask user if they want to add the dependency
             let add_dependency = yes
-                || Confirm::new()
-                    .with_prompt(format!("  Add dependency '{}'?", mod_info.name))
-                    .default(true)
-                    .interact()
+                || env
+                    .prompter()
+                    .confirm(&format!("  Add dependency '{}'?", mod_info.name), true)
                     .context("Failed to confirm dependency addition")?;
 
             if add_dependency {
+                let content_type = project_content_type(&mod_info, ContentType::Mod);
+
                 // Select a compatible file version - pass yes flag as auto_select parameter
                 let dependency_file = match select_file_version(
-                    &client,
+                    env,
+                    client,
                     &mod_info,
                     minecraft_version,
-                    mod_loader,
+                    &loader_filter(content_type, mod_loader),
                     None,
                     yes,
                 )
@@ -393,36 +421,52 @@ async fn process_dependencies(
                 {
                     Ok(file) => file,
                     Err(e) => {
-                        println!(
+                        env.reporter().warning(&format!(
                             "  ⚠ Failed to select file for dependency '{}': {}",
                             mod_info.name, e
-                        );
+                        ));
                         continue;
                     }
                 };
 
                 // Determine side
-                let side = match determine_mod_side_cf(&mod_info.name, &dependency_file) {
-                    Ok(side) => side,
+                let side =
+                    match determine_mod_side_cf(&mod_info.name, &dependency_file, content_type) {
+                        Ok(side) => side,
+                        Err(e) => {
+                            env.reporter().warning(&format!(
+                                "  ⚠ Failed to determine mod side for '{}': {}",
+                                mod_info.name, e
+                            ));
+                            continue;
+                        }
+                    };
+
+                // Save reference
+                match save_mod_reference(
+                    env,
+                    store,
+                    &mod_info,
+                    &dependency_file,
+                    side,
+                    content_type,
+                ) {
+                    Ok((path, reference)) => added.push(AddedReference {
+                        path,
+                        reference,
+                        dependency: true,
+                    }),
                     Err(e) => {
-                        println!(
-                            "  ⚠ Failed to determine mod side for '{}': {}",
+                        env.reporter().warning(&format!(
+                            "  ⚠ Failed to save reference for '{}': {}",
                             mod_info.name, e
-                        );
+                        ));
                         continue;
                     }
-                };
-
-                // Save reference
-                if let Err(e) = save_mod_reference(env, &mod_info, &dependency_file, side) {
-                    println!(
-                        "  ⚠ Failed to save reference for '{}': {}",
-                        mod_info.name, e
-                    );
-                    continue;
                 }
 
-                println!("  ✅ Added dependency: {}", mod_info.name);
+                env.reporter()
+                    .message(&format!("  ✅ Added dependency: {}", mod_info.name));
 
                 // Mark as processed
                 processed_mods.insert(dependency.mod_id);
@@ -436,13 +480,15 @@ async fn process_dependencies(
                     mod_loader,
                     yes,
                     processed_mods,
+                    store,
+                    added,
                 ))
                 .await
                 {
-                    println!(
+                    env.reporter().warning(&format!(
                         "  ⚠ Failed to process nested dependencies for '{}': {}",
                         mod_info.name, e
-                    );
+                    ));
                 }
             }
         }
@@ -451,80 +497,155 @@ async fn process_dependencies(
     Ok(())
 }
 
-pub async fn run<E: utils::Env>(env: &E, mod_query: Option<String>, yes: bool) -> Result<()> {
+pub async fn run<E: utils::Env>(
+    env: &E,
+    mod_query: Option<String>,
+    yes: bool,
+    options: AddOptions,
+) -> Result<()> {
     // Check if we're in a modpack directory
     if !utils::modpack_exists(env) {
         return Err(anyhow!(MinepackError::NoModpackFound));
     }
 
-    let config = utils::load_config(env)?;
-    let client = CurseforgeClient::new().context("Failed to initialize Curseforge API client")?;
-
     // If no mod query is provided, prompt the user for one
     let query = match mod_query {
         Some(q) => q,
-        None => {
-            let query: String = dialoguer::Input::new()
-                .with_prompt("Enter mod name or CurseForge URL")
-                .interact_text()
-                .context("Failed to get mod query")?;
-            query
-        }
+        None => env
+            .prompter()
+            .input("Enter mod name or CurseForge URL", None)
+            .context("Failed to get mod query")?,
     };
 
+    if let AddOutcome::Added(_) = add(env, &query, yes, options).await? {
+        env.reporter()
+            .message("Note: The actual mod file(s) will be downloaded when you build the modpack.");
+    }
+
+    Ok(())
+}
+
+/// Adds the project matching `query` (a name or Curseforge URL) and its required dependencies.
+/// With `yes` set, the first match and the first compatible file are chosen without asking.
+pub async fn add<E: utils::Env>(
+    env: &E,
+    query: &str,
+    yes: bool,
+    options: AddOptions,
+) -> Result<AddOutcome> {
+    let requested_content_type = match &options.content_type {
+        Some(content_type) => ContentType::from_str(&content_type.to_lowercase())
+            .map_err(|_| anyhow!(MinepackError::InvalidContentType(content_type.clone())))?,
+        None => ContentType::Mod,
+    };
+
+    let config = utils::load_config(env)?;
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
+    let reporter = env.reporter();
+
     let mod_loader = &config
         .minecraft
         .mod_loaders
         .iter()
         .find(|m| m.primary)
-        .map(|m| m.clone());
+        .cloned();
 
     // Process the query - it's either a URL or a search term
-    let (mod_info, file_id_from_url) = if query.starts_with("https://www.curseforge.com/") {
-        // Extract mod info from URL
-        extract_mod_info_from_url(&query, &client, &config.minecraft.version, mod_loader).await?
-    } else {
-        // Search for mods by name
-        let mod_info =
-            search_mods_by_name(&query, &client, &config.minecraft.version, mod_loader).await?;
-        (mod_info, None)
-    };
+    let (mod_info, file_id_from_url, content_type) =
+        if query.starts_with("https://www.curseforge.com/") {
+            // Extract mod info from URL, the URL also determines the content type
+            extract_mod_info_from_url(env, query, &client, &config.minecraft.version, mod_loader)
+                .await?
+        } else {
+            // Search for mods by name
+            let mod_info = search_mods_by_name(
+                env,
+                query,
+                &client,
+                &config.minecraft.version,
+                mod_loader,
+                requested_content_type,
+            )
+            .await?;
+            (mod_info, None, requested_content_type)
+        };
+    let content_type = project_content_type(&mod_info, content_type);
 
     // Display the selected mod info
-    println!("Selected mod: {} (ID: {})", mod_info.name, mod_info.id);
-    println!("Description: {}", mod_info.summary);
+    reporter.message(&format!(
+        "Selected {}: {} (ID: {})",
+        content_type, mod_info.name, mod_info.id
+    ));
+    reporter.message(&format!("Description: {}", mod_info.summary));
+
+    // Pinned mods are held at their current file, so don't replace them
+    let mut store = ReferenceStore::load(env)?;
+    if let Some(reference) = store
+        .get_by_project_id(mod_info.id)
+        .map(|stored| stored.reference.clone())
+        .filter(|reference| reference.pinned)
+    {
+        reporter.message(&format!(
+            "📌 {} is pinned at {}{}. Skipping.",
+            reference.name,
+            reference.filename,
+            reference
+                .pin_reason
+                .as_ref()
+                .map(|reason| format!(" ({})", reason))
+                .unwrap_or_default()
+        ));
+        reporter.message(&format!(
+            "Run 'minepack unpin {}' to allow updating it.",
+            mod_info.slug
+        ));
+        return Ok(AddOutcome::SkippedPinned(reference));
+    }
 
     // Select a compatible file version - passing yes flag as auto_select parameter
     let file = select_file_version(
+        env,
         &client,
         &mod_info,
         &config.minecraft.version,
-        mod_loader,
+        &loader_filter(content_type, mod_loader),
         file_id_from_url,
         yes,
     )
     .await?;
-    println!("Selected file: {} (ID: {})", file.display_name, file.id);
+    reporter.message(&format!(
+        "Selected file: {} (ID: {})",
+        file.display_name, file.id
+    ));
 
     // Determine the mod side (client/server/both)
-    let side = determine_mod_side_cf(&mod_info.name, &file)?;
+    let side = determine_mod_side_cf(&mod_info.name, &file, content_type)?;
 
     // Confirm the addition
     let confirm = yes
-        || Confirm::new()
-            .with_prompt("Add this mod to your modpack?")
-            .default(true)
-            .interact()
+        || env
+            .prompter()
+            .confirm("Add this mod to your modpack?", true)
             .context("Failed to confirm mod addition")?;
 
     if !confirm {
-        return Ok(());
+        return Ok(AddOutcome::Cancelled);
     }
 
     // Save the mod reference file
-    save_mod_reference(env, &mod_info, &file, side)?;
-
-    println!("✅ Mod reference added successfully!");
+    let (path, reference) =
+        save_mod_reference(env, &mut store, &mod_info, &file, side, content_type)?;
+    let mut added = vec![AddedReference {
+        path,
+        reference,
+        dependency: false,
+    }];
+
+    reporter.message(&format!(
+        "✅ Reference added successfully to {}/!",
+        content_type.directory()
+    ));
 
     // Keep track of which mods we've processed to avoid cyclic dependencies
     let mut processed_mods = HashSet::new();
@@ -539,10 +660,10 @@ pub async fn run<E: utils::Env>(env: &E, mod_query: Option<String>, yes: bool) -
         mod_loader,
         yes,
         &mut processed_mods,
+        &mut store,
+        &mut added,
     )
     .await?;
 
-    println!("Note: The actual mod file(s) will be downloaded when you build the modpack.");
-
-    Ok(())
+    Ok(AddOutcome::Added(added))
 }
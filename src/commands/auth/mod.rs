@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Context, Result};
+use std::str::FromStr;
+
+use crate::api::curseforge::CurseforgeClient;
+use crate::api::modrinth::ModrinthClient;
+use crate::models::user_config::ApiService;
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// Store an API key in the user configuration after checking it against the API
+pub async fn run<E: utils::Env>(
+    env: &E,
+    service: String,
+    key: Option<String>,
+    verify: bool,
+) -> Result<()> {
+    let service = ApiService::from_str(&service.to_lowercase())
+        .map_err(|_| anyhow!(MinepackError::UnknownApiService(service)))?;
+
+    let key = match key {
+        Some(key) => key,
+        None => env
+            .prompter()
+            .password(&format!("{} API key", service))
+            .context("Failed to get API key")?,
+    };
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(anyhow!(MinepackError::InvalidApiKey(service.to_string())));
+    }
+
+    if verify {
+        env.reporter()
+            .message(&format!("🔑 Checking the API key with {}...", service));
+        let valid = match service {
+            ApiService::Curseforge => {
+                CurseforgeClient::with_api_key(env, &key)?
+                    .validate_api_key()
+                    .await?
+            }
+            ApiService::Modrinth => {
+                ModrinthClient::with_token(env, &key)?
+                    .validate_token()
+                    .await?
+            }
+        };
+        if !valid {
+            return Err(anyhow!(MinepackError::InvalidApiKey(service.to_string())));
+        }
+    }
+
+    let mut user_config = utils::load_user_config(env)?;
+    user_config.credentials_mut(service).api_key = Some(key);
+    let path = utils::save_user_config(env, &user_config)?;
+
+    env.reporter().message(&format!(
+        "✅ Saved the {} API key to {}",
+        service,
+        path.display()
+    ));
+
+    Ok(())
+}
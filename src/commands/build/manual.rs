@@ -0,0 +1,76 @@
+//! Files whose authors disallow third-party distribution.
+//!
+//! Launchers can't download these files, so self-contained exports take them from the cache,
+//! where `minepack fetch-manual` puts them. Files missing from the cache are left out and
+//! listed in `MANUAL_DOWNLOADS.md` in the game directory, with links to download them.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::api::curseforge::project_page_url;
+use crate::models::config::{ContentType, ModEntry, ModpackConfig};
+use crate::utils;
+
+pub const MANUAL_DOWNLOADS_FILENAME: &str = "MANUAL_DOWNLOADS.md";
+
+/// A file left out of an export that has to be downloaded by hand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManualFile {
+    pub name: String,
+    pub file_name: String,
+    pub page_url: String,
+    /// The directory of the game directory the file goes into, e.g. `mods`
+    pub directory: String,
+}
+
+impl ManualFile {
+    pub fn from_entry(entry: &ModEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            file_name: entry.version.clone(),
+            page_url: entry
+                .manual_download
+                .as_ref()
+                .map(|manual_download| manual_download.page_url.clone())
+                .unwrap_or_else(|| project_page_url(entry.project_id)),
+            directory: match entry.content_type {
+                ContentType::DataPack => "saves/<world>/datapacks".to_string(),
+                content_type => content_type.directory().to_string(),
+            },
+        }
+    }
+}
+
+/// Writes the list of files to download by hand into `game_dir`, if there are any
+pub fn write_manual_downloads(
+    config: &ModpackConfig,
+    files: &[ManualFile],
+    game_dir: &Path,
+) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    utils::ensure_dir_exists(game_dir)?;
+    fs::write(
+        game_dir.join(MANUAL_DOWNLOADS_FILENAME),
+        render_manual_downloads(config, files),
+    )
+    .with_context(|| format!("Failed to write {}", MANUAL_DOWNLOADS_FILENAME))
+}
+
+/// Renders a Markdown checklist of the files to download by hand
+pub fn render_manual_downloads(config: &ModpackConfig, files: &[ManualFile]) -> String {
+    let mut markdown = format!(
+        "# Manual downloads\n\nThe authors of these files don't allow them to be shipped with {}. Download each file from its page and put it into the given directory of your game directory.\n\n",
+        config.name
+    );
+    for file in files {
+        markdown.push_str(&format!(
+            "- [ ] [{}]({}): `{}` into `{}/`\n",
+            file.name, file.page_url, file.file_name, file.directory
+        ));
+    }
+    markdown
+}
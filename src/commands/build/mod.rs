@@ -1,171 +1,790 @@
+pub mod manual;
+pub mod modlist;
+pub mod overrides;
+
+use manual::ManualFile;
+use overrides::OverrideFile;
+
 use anyhow::{anyhow, Context, Result};
-use indicatif::{ProgressBar, ProgressStyle};
-use serde_json::Value;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, File};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use walkdir::WalkDir;
+use zip::ZipArchive;
 
 use crate::api::curseforge::{
-    schema::{Manifest, ManifestFile, ManifestMinecraft, ManifestModLoader},
-    CurseforgeClient,
+    self, cdn_file_url, project_page_url,
+    schema::{
+        self as curseforge_schema, Manifest, ManifestFile, ManifestMinecraft, ManifestModLoader,
+    },
+    CurseforgeClient, DEFAULT_CDN_HOSTS,
+};
+use crate::api::modrinth::schema::{self as modrinth_schema, EnvSupport, IndexFileEnv};
+use crate::api::modrinth::{self, ACCEPTED_DOWNLOAD_HOSTS};
+use crate::models::config::{
+    ContentType, ManualDownload, ModEntry, ModpackConfig, Profile, Reference, Side,
 };
-use crate::models::config::ModEntry;
+use crate::ui::{Progress, Reporter};
 use crate::utils;
+use crate::utils::archive::{self, ExtractLimits};
 use crate::utils::errors::MinepackError;
+use crate::utils::reference_store::ReferenceStore;
 
-// Supported export formats
-enum ExportFormat {
+/// The world a dedicated server loads if `server.properties` doesn't name one
+const DEFAULT_SERVER_WORLD: &str = "world";
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
     MultiMC,
     CurseForge,
     Modrinth,
+    /// A zip to unpack on a dedicated server, without the mod loader itself
+    Server,
 }
 
-pub async fn run<E: utils::Env>(env: &E, format: Option<String>) -> Result<()> {
-    // Check if we're in a modpack directory
-    if !utils::modpack_exists(env) {
-        return Err(anyhow!(MinepackError::NoModpackFound));
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::MultiMC,
+        ExportFormat::CurseForge,
+        ExportFormat::Modrinth,
+        ExportFormat::Server,
+    ];
+
+    /// The name shown when choosing a format
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::MultiMC => "MultiMC (.zip)",
+            ExportFormat::CurseForge => "CurseForge (.zip)",
+            ExportFormat::Modrinth => "Modrinth (mrpack)",
+            ExportFormat::Server => "Server (.zip)",
+        }
     }
 
-    let config = utils::load_config(env)?;
+    /// The sides whose override files are shipped in this format
+    pub fn override_sides(&self) -> &'static [Side] {
+        match self {
+            // CurseForge packs only have a single overrides folder, installed by the client
+            ExportFormat::MultiMC | ExportFormat::CurseForge => &[Side::Both, Side::Client],
+            ExportFormat::Modrinth => &[Side::Both, Side::Client, Side::Server],
+            ExportFormat::Server => &[Side::Both, Side::Server],
+        }
+    }
+}
 
-    println!("🔨 Building modpack: {}", config.name);
+impl FromStr for ExportFormat {
+    type Err = MinepackError;
 
-    // Create build directory
-    let build_dir = utils::get_build_dir(env)?;
-    utils::ensure_dir_exists(&build_dir)?;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "multimc" => Ok(ExportFormat::MultiMC),
+            "curseforge" => Ok(ExportFormat::CurseForge),
+            "modrinth" => Ok(ExportFormat::Modrinth),
+            "server" => Ok(ExportFormat::Server),
+            _ => Err(MinepackError::InvalidExportFormat),
+        }
+    }
+}
+
+/// Options for the build command
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Name of the build profile in the modpack configuration
+    pub profile: Option<String>,
+}
+
+/// The result of building the modpack
+#[derive(Debug, Clone)]
+pub struct BuildResult {
+    pub format: ExportFormat,
+    /// The build profile the modpack was built with
+    pub profile: Option<String>,
+    /// Path of the built archive
+    pub output_path: PathBuf,
+    /// Number of mods, resource packs, etc. in the modpack
+    pub entry_count: usize,
+    /// Project files shipped with the modpack, relative to the project directory
+    pub overrides: Vec<PathBuf>,
+    /// Number of project files left out by `.minepackignore`, `overrides.include` or the profile
+    pub skipped_overrides: usize,
+    /// Files left out because they have to be downloaded by hand
+    pub manual_downloads: Vec<ManualFile>,
+}
+
+pub async fn run<E: utils::Env>(
+    env: &E,
+    format: Option<String>,
+    options: BuildOptions,
+) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
 
     // Determine export format from command line argument or prompt user
     let export_format = match format {
-        Some(format_str) => match format_str.to_lowercase().as_str() {
-            "multimc" => ExportFormat::MultiMC,
-            "curseforge" => ExportFormat::CurseForge,
-            "modrinth" => ExportFormat::Modrinth,
-            _ => return Err(anyhow!(MinepackError::InvalidExportFormat)),
-        },
+        Some(format_str) => ExportFormat::from_str(&format_str)?,
         None => {
             // Choose export format via prompt if not specified
-            let format_options = ["MultiMC (.zip)", "CurseForge (.zip)", "Modrinth (mrpack)"];
-            let format_index = dialoguer::Select::new()
-                .with_prompt("Select export format")
-                .items(&format_options)
-                .default(0)
-                .interact()
+            let format_options: Vec<String> = ExportFormat::ALL
+                .iter()
+                .map(|format| format.label().to_string())
+                .collect();
+            let format_index = env
+                .prompter()
+                .select("Select export format", &format_options, 0)
                 .context("Failed to select export format")?;
-
-            match format_index {
-                0 => ExportFormat::MultiMC,
-                1 => ExportFormat::CurseForge,
-                2 => ExportFormat::Modrinth,
-                _ => return Err(anyhow!(MinepackError::InvalidExportFormat)),
-            }
+            ExportFormat::ALL[format_index]
         }
     };
 
-    // Load all mod entries from JSON files
-    let mod_entries = load_mod_entries(env).context("Failed to load mod entries")?;
+    let result = build(env, export_format, &options).await?;
+    report_overrides(env.reporter(), &result);
 
-    // Set up progress bar
-    let pb = ProgressBar::new(mod_entries.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .context("Failed to create progress bar style")?
-            .progress_chars("#>-"),
-    );
+    if !result.manual_downloads.is_empty() {
+        env.reporter().warning(&format!(
+            "⚠️ {} files can't be shipped because their authors disallow third-party distribution, they are listed in {}:",
+            result.manual_downloads.len(),
+            manual::MANUAL_DOWNLOADS_FILENAME
+        ));
+        for file in &result.manual_downloads {
+            env.reporter().warning(&format!(
+                "  {} ({}): {}",
+                file.name, file.file_name, file.page_url
+            ));
+        }
+        env.reporter().warning(
+            "  Download them and run 'minepack fetch-manual' to include them in the next build",
+        );
+    }
+
+    env.reporter().message("✅ Modpack built successfully!");
+    env.reporter()
+        .message(&format!("Output: {}", result.output_path.display()));
+
+    Ok(())
+}
+
+/// Prints which project files were shipped, grouped by top-level directory
+fn report_overrides(reporter: &dyn Reporter, result: &BuildResult) {
+    let mut groups: Vec<(String, bool, usize)> = Vec::new();
+    for path in &result.overrides {
+        let mut components = path.components();
+        let first = components
+            .next()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .unwrap_or_default();
+        let is_dir = components.next().is_some();
+        match groups.last_mut() {
+            Some((name, true, count)) if is_dir && *name == first => *count += 1,
+            _ => groups.push((first, is_dir, 1)),
+        }
+    }
+
+    reporter.message(&format!(
+        "📁 Included {} project files:",
+        result.overrides.len()
+    ));
+    for (name, is_dir, count) in groups {
+        if is_dir {
+            reporter.message(&format!(
+                "  {}/ ({} file{})",
+                name,
+                count,
+                if count == 1 { "" } else { "s" }
+            ));
+        } else {
+            reporter.message(&format!("  {}", name));
+        }
+    }
+    if result.skipped_overrides > 0 {
+        reporter.message(&format!(
+            "  Skipped {} files matching {} or not in overrides.include",
+            result.skipped_overrides,
+            overrides::IGNORE_FILENAME
+        ));
+    }
+}
+
+/// Builds the modpack in the given format into the build directory
+pub async fn build<E: utils::Env>(
+    env: &E,
+    export_format: ExportFormat,
+    options: &BuildOptions,
+) -> Result<BuildResult> {
+    let config = utils::load_config(env)?;
+    let profile = find_profile(&config, options.profile.as_deref())?;
+
+    match &options.profile {
+        Some(name) => env.reporter().message(&format!(
+            "🔨 Building modpack: {} (profile: {})",
+            config.name, name
+        )),
+        None => env
+            .reporter()
+            .message(&format!("🔨 Building modpack: {}", config.name)),
+    }
+
+    // Create build directory
+    let build_dir = utils::get_build_dir(env)?;
+    utils::ensure_dir_exists(&build_dir)?;
+
+    let mod_entries = load_profile_entries(env, profile)?;
+
+    // Project files shipped with the modpack
+    let overrides = overrides::collect_overrides(env, &config, profile)?;
+
+    // Profiles are built next to the full modpack, e.g. `Pack-1.0.0-lite-CurseForge.zip`
+    let archive_stem = match &options.profile {
+        Some(name) => format!("{}-{}-{}", config.name, config.version, name),
+        None => format!("{}-{}", config.name, config.version),
+    };
+    let shipped_overrides: Vec<&OverrideFile> = overrides
+        .files
+        .iter()
+        .filter(|file| export_format.override_sides().contains(&file.side))
+        .collect();
+
+    // Set up progress reporting
+    let pb = env.reporter().progress(mod_entries.len() as u64);
+    let pb = pb.as_ref();
 
     // Create modpack based on selected format
-    match export_format {
+    let (output_path, manual_downloads) = match export_format {
         ExportFormat::MultiMC => {
-            build_multimc_pack(env, &config, &build_dir, &mod_entries, pb).await?
+            build_multimc_pack(
+                env,
+                &config,
+                &build_dir,
+                &archive_stem,
+                &mod_entries,
+                &shipped_overrides,
+                pb,
+            )
+            .await?
         }
         ExportFormat::CurseForge => {
-            build_curseforge_pack(env, &config, &build_dir, &mod_entries, pb).await?
+            build_curseforge_pack(
+                env,
+                &config,
+                &build_dir,
+                &archive_stem,
+                &mod_entries,
+                &shipped_overrides,
+                pb,
+            )
+            .await?
         }
         ExportFormat::Modrinth => {
-            build_modrinth_pack(env, &config, &build_dir, &mod_entries, pb).await?
+            build_modrinth_pack(
+                env,
+                &config,
+                &build_dir,
+                &archive_stem,
+                &mod_entries,
+                &shipped_overrides,
+                pb,
+            )
+            .await?
         }
+        ExportFormat::Server => {
+            build_server_pack(
+                env,
+                &config,
+                &build_dir,
+                &archive_stem,
+                &mod_entries,
+                &shipped_overrides,
+                pb,
+            )
+            .await?
+        }
+    };
+
+    Ok(BuildResult {
+        format: export_format,
+        profile: options.profile.clone(),
+        output_path,
+        entry_count: mod_entries.len(),
+        overrides: shipped_overrides
+            .iter()
+            .map(|file| file.source.clone())
+            .collect(),
+        skipped_overrides: overrides.skipped,
+        manual_downloads,
+    })
+}
+
+/// Looks up a build profile of the modpack configuration by name
+pub(crate) fn find_profile<'a>(
+    config: &'a ModpackConfig,
+    name: Option<&str>,
+) -> Result<Option<&'a Profile>> {
+    name.map(|name| {
+        config.profiles.get(name).ok_or_else(|| {
+            let available: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+            anyhow!(MinepackError::UnknownProfile(
+                name.to_string(),
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ))
+        })
+    })
+    .transpose()
+}
+
+/// Loads the entries of all references, narrowed down to the ones in the build profile
+pub(crate) fn load_profile_entries<E: utils::Env>(
+    env: &E,
+    profile: Option<&Profile>,
+) -> Result<Vec<ModEntry>> {
+    let cdn_host = utils::load_curseforge_settings(env)?
+        .cdn_hosts
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| DEFAULT_CDN_HOSTS[0].to_string());
+    let mut mod_entries = load_mod_entries(env, &cdn_host).context("Failed to load mod entries")?;
+    if let Some(profile) = profile {
+        mod_entries.retain(|entry| profile.includes(&entry.tags, entry.side));
     }
+    Ok(mod_entries)
+}
 
-    println!("✅ Modpack built successfully!");
-    println!(
-        "Output: {}/{}-{}.zip",
-        build_dir.display(),
-        config.name,
-        config.version
-    );
+/// Load mod entries from the reference files in the content directories (mods,
+/// resourcepacks, ...) and their subdirectories. Download URLs point to `cdn_host`.
+fn load_mod_entries<E: utils::Env>(env: &E, cdn_host: &str) -> Result<Vec<ModEntry>> {
+    let folder_tags = utils::load_config(env)?
+        .references
+        .is_some_and(|references| references.folder_tags);
 
-    Ok(())
+    Ok(ReferenceStore::load(env)?
+        .iter()
+        .map(|stored| {
+            let mut entry = mod_entry(&stored.reference, cdn_host);
+            if folder_tags {
+                for folder in &stored.folders {
+                    if !entry.tags.contains(folder) {
+                        entry.tags.push(folder.clone());
+                    }
+                }
+            }
+            entry
+        })
+        .collect())
 }
 
-/// Load mod entries from the JSON files in the mods directory
-fn load_mod_entries<E: utils::Env>(env: &E) -> Result<Vec<ModEntry>> {
-    let mods_dir = utils::get_mods_dir(env)?;
-    let mut mod_entries = Vec::new();
+fn mod_entry(reference: &Reference, cdn_host: &str) -> ModEntry {
+    ModEntry {
+        name: reference.name.clone(),
+        project_id: reference.project_id(),
+        file_id: reference.file_id(),
+        version: reference.filename.clone(),
+        download_url: cdn_file_url(cdn_host, reference.file_id(), &reference.filename),
+        required: !reference.optional,
+        description: reference.description.clone(),
+        content_type: reference.content_type,
+        side: reference.side,
+        tags: reference.tags.clone(),
+        manual_download: reference.manual_download.clone(),
+    }
+}
 
-    for entry in WalkDir::new(&mods_dir).min_depth(1).max_depth(1) {
-        let entry = entry.context("Failed to read directory entry")?;
-        let path = entry.path();
+/// Returns the path of the file for the entry in the cache, downloading it if necessary
+pub(crate) async fn cache_mod_file<E: utils::Env>(
+    env: &E,
+    client: &CurseforgeClient,
+    mod_entry: &ModEntry,
+    pb: &dyn Progress,
+) -> Result<PathBuf> {
+    let filename = &mod_entry.version; // filename is stored in version field now
+    let cache_dir = utils::get_minepack_cache_content_dir(env, mod_entry.content_type)?;
+    let cache_path = cache_dir.join(filename);
+
+    if !cache_path.exists() {
+        pb.set_message(format!("File not in cache, downloading: {}", filename));
+        // Download URL is constructed from project_id and file_id
+        let data = client
+            .download_mod_file(mod_entry.project_id, mod_entry.file_id)
+            .await
+            .with_context(|| format!("Failed to download: {}", mod_entry.name))?;
+
+        utils::ensure_dir_exists(&cache_dir)?;
+        let mut file = File::create(&cache_path)
+            .with_context(|| format!("Failed to create file: {}", cache_path.display()))?;
+        file.write_all(&data)
+            .context("Failed to write downloaded data to file")?;
+    }
 
-        // Only process JSON files with .ex.json extension
-        if path.extension().is_some_and(|ext| ext == "json")
-            && path.to_string_lossy().ends_with(".ex.json")
-        {
-            // Read and parse the JSON file
-            let mut file = File::open(path)
-                .with_context(|| format!("Failed to open JSON file: {}", path.display()))?;
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)
-                .with_context(|| format!("Failed to read JSON file: {}", path.display()))?;
-
-            let json: Value = serde_json::from_str(&contents)
-                .with_context(|| format!("Failed to parse JSON file: {}", path.display()))?;
-
-            // Create ModEntry from new JSON format
-            let name = json["name"].as_str().unwrap_or("Unknown").to_string();
-            let filename = json["filename"].as_str().unwrap_or("Unknown").to_string();
-
-            // Get project_id and file_id from the link object
-            let project_id = json["link"]["project_id"].as_u64().unwrap_or(0) as u32;
-            let file_id = json["link"]["file_id"].as_u64().unwrap_or(0) as u32;
-
-            // Construct download_url if not present (fallback for older JSON files)
-            let download_url = format!(
-                "https://edge.forgecdn.net/files/{}/{}/{}",
-                file_id / 1000,
-                file_id % 1000,
-                filename
-            );
-
-            let mod_entry = ModEntry {
-                name,
-                project_id,
-                file_id,
-                version: filename.clone(), // Use filename as version if not specified
-                download_url,
-                required: true, // Default to required
+    Ok(cache_path)
+}
+
+/// Downloads the files of all entries missing from the cache, several at a time. Returns the
+/// entries whose files are missing and have to be downloaded by hand, because their authors
+/// disallow third-party distribution.
+pub(crate) async fn prefetch_mod_files<'a, E: utils::Env>(
+    env: &E,
+    client: &CurseforgeClient,
+    mod_entries: &[&'a ModEntry],
+    pb: &dyn Progress,
+) -> Result<Vec<&'a ModEntry>> {
+    let concurrency = utils::load_user_config(env)?.concurrency();
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut downloads = JoinSet::new();
+    let mut manual = Vec::new();
+
+    for (index, mod_entry) in mod_entries.iter().enumerate() {
+        let cache_dir = utils::get_minepack_cache_content_dir(env, mod_entry.content_type)?;
+        let cache_path = cache_dir.join(&mod_entry.version);
+        if cache_path.exists() {
+            continue;
+        }
+        if mod_entry.manual_download.is_some() {
+            manual.push(index);
+            continue;
+        }
+
+        pb.set_message(format!("Downloading: {}", mod_entry.version));
+        let permit = semaphore.clone().acquire_owned().await?;
+        let client = client.clone();
+        let (name, project_id, file_id) = (
+            mod_entry.name.clone(),
+            mod_entry.project_id,
+            mod_entry.file_id,
+        );
+        downloads.spawn(async move {
+            let _permit = permit;
+            let data = match client.download_mod_file(project_id, file_id).await {
+                Ok(data) => data,
+                // References added before distribution was disallowed aren't marked
+                Err(err)
+                    if matches!(
+                        err.downcast_ref::<MinepackError>(),
+                        Some(MinepackError::ManualDownloadRequired(_))
+                    ) =>
+                {
+                    return Ok(Some(index));
+                }
+                Err(err) => return Err(err.context(format!("Failed to download: {}", name))),
             };
 
-            mod_entries.push(mod_entry);
+            utils::ensure_dir_exists(&cache_dir)?;
+            fs::write(&cache_path, data)
+                .with_context(|| format!("Failed to create file: {}", cache_path.display()))?;
+            Ok::<Option<usize>, anyhow::Error>(None)
+        });
+    }
+
+    while let Some(result) = downloads.join_next().await {
+        if let Some(index) = result.context("Download task failed")?? {
+            manual.push(index);
         }
     }
 
-    Ok(mod_entries)
+    manual.sort_unstable();
+    let manual: Vec<&'a ModEntry> = manual.into_iter().map(|index| mod_entries[index]).collect();
+    let unmarked: Vec<&ModEntry> = manual
+        .iter()
+        .copied()
+        .filter(|entry| entry.manual_download.is_none())
+        .collect();
+    if !unmarked.is_empty() {
+        if let Err(err) = mark_manual_downloads(env, client, &unmarked).await {
+            env.reporter().warning(&format!(
+                "⚠️ Failed to record the files to download by hand in their references: {:#}",
+                err
+            ));
+        }
+    }
+    Ok(manual)
+}
+
+/// Records where to download the files of `mod_entries` by hand in their references, which
+/// were added before their authors disallowed third-party distribution. `fetch-manual` needs
+/// the fingerprint stored there to recognize the downloaded files.
+async fn mark_manual_downloads<E: utils::Env>(
+    env: &E,
+    client: &CurseforgeClient,
+    mod_entries: &[&ModEntry],
+) -> Result<()> {
+    let files: HashMap<u32, curseforge_schema::File> = client
+        .get_file_infos(mod_entries.iter().map(|entry| entry.file_id).collect())
+        .await?
+        .into_iter()
+        .map(|file| (file.id, file))
+        .collect();
+    // The project page is only a nicer link, the project page URL from the ID works as well
+    let projects: HashMap<u32, curseforge_schema::Mod> = client
+        .get_mod_infos(mod_entries.iter().map(|entry| entry.project_id).collect())
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|project| (project.id, project))
+        .collect();
+
+    let mut store = ReferenceStore::load(env)?;
+    for mod_entry in mod_entries {
+        let Some(file) = files.get(&mod_entry.file_id) else {
+            continue;
+        };
+        let manual_download = match projects.get(&mod_entry.project_id) {
+            Some(project) => curseforge::manual_download(project, file),
+            None => file.download_url.is_none().then(|| ManualDownload {
+                page_url: project_page_url(mod_entry.project_id),
+                fingerprint: file.file_fingerprint,
+            }),
+        };
+        let Some(manual_download) = manual_download else {
+            continue;
+        };
+        let Some(stored) = store.get_by_file_id(mod_entry.file_id) else {
+            continue;
+        };
+
+        let path = stored.path.clone();
+        let mut reference = stored.reference.clone();
+        reference.manual_download = Some(manual_download);
+        store.update(&path, reference)?;
+        env.reporter().message(&format!(
+            "📝 Marked {} as a file to download by hand in {}",
+            mod_entry.name,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the entry is one of the entries to download by hand
+pub(crate) fn is_manual(manual: &[&ModEntry], mod_entry: &ModEntry) -> bool {
+    manual.iter().any(|entry| {
+        entry.project_id == mod_entry.project_id
+            && entry.file_id == mod_entry.file_id
+            && entry.version == mod_entry.version
+    })
+}
+
+/// The directories of a game directory an entry is installed into, with `/` as separator.
+/// Data packs go into the `datapacks` directory of each of the `worlds`, which are
+/// directories of the game directory as well, e.g. `saves/Adventure`.
+pub(crate) fn install_dirs(mod_entry: &ModEntry, worlds: &[String]) -> Vec<String> {
+    match mod_entry.content_type {
+        ContentType::DataPack => worlds
+            .iter()
+            .map(|world| format!("{}/datapacks", world))
+            .collect(),
+        content_type => vec![content_type.directory().to_string()],
+    }
+}
+
+/// The world directories of a client data packs are installed into: the worlds among
+/// `mod_entries`, read from their cached archives, and the ones in `datapacks.worlds` of the
+/// configuration. Warns if there are data packs but no world to install them into.
+pub(crate) async fn data_pack_worlds<E: utils::Env>(
+    env: &E,
+    client: &CurseforgeClient,
+    config: &ModpackConfig,
+    mod_entries: &[&ModEntry],
+    manual: &[&ModEntry],
+    pb: &dyn Progress,
+) -> Result<Vec<String>> {
+    let mut worlds: Vec<String> = Vec::new();
+    for mod_entry in mod_entries {
+        if mod_entry.content_type == ContentType::World && !is_manual(manual, mod_entry) {
+            let cache_path = cache_mod_file(env, client, mod_entry, pb).await?;
+            let folder = world_folder(&cache_path)
+                .with_context(|| format!("Failed to read world: {}", mod_entry.name))?
+                .0;
+            if !worlds.contains(&folder) {
+                worlds.push(folder);
+            }
+        }
+    }
+    for world in config
+        .datapacks
+        .iter()
+        .flat_map(|datapacks| &datapacks.worlds)
+    {
+        check_world_name(world)?;
+        if !worlds.contains(world) {
+            worlds.push(world.clone());
+        }
+    }
+
+    let data_packs = mod_entries
+        .iter()
+        .filter(|entry| entry.content_type == ContentType::DataPack)
+        .count();
+    if worlds.is_empty() && data_packs > 0 {
+        env.reporter().warning(&format!(
+            "⚠️ {} data packs are left out: Minecraft only loads data packs from the datapacks directory of a world, and the modpack has no worlds. List the worlds to install them into in datapacks.worlds",
+            data_packs
+        ));
+    }
+    Ok(worlds
+        .into_iter()
+        .map(|world| format!("{}/{}", ContentType::World.directory(), world))
+        .collect())
+}
+
+/// The world directory a dedicated server loads data packs from: `datapacks.server_world` of
+/// the configuration, the `level-name` of the shipped `server.properties`, or `world`
+fn server_world<E: utils::Env>(
+    env: &E,
+    config: &ModpackConfig,
+    overrides: &[&OverrideFile],
+) -> Result<String> {
+    if let Some(world) = config
+        .datapacks
+        .as_ref()
+        .and_then(|datapacks| datapacks.server_world.clone())
+    {
+        check_world_name(&world)?;
+        return Ok(world);
+    }
+
+    let current_dir = env.current_dir()?;
+    for file in overrides {
+        if file.target != Path::new("server.properties") {
+            continue;
+        }
+        let content = fs::read_to_string(current_dir.join(&file.source))
+            .with_context(|| format!("Failed to read {}", file.source.display()))?;
+        let level_name = content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim() == "level-name")
+            .map(|(_, value)| value.trim().to_string());
+        if let Some(world) = level_name.filter(|world| !world.is_empty()) {
+            check_world_name(&world)?;
+            return Ok(world);
+        }
+    }
+    Ok(DEFAULT_SERVER_WORLD.to_string())
+}
+
+/// World names are used as directory names, so they must not contain a path
+fn check_world_name(world: &str) -> Result<()> {
+    if !is_directory_name(world) {
+        return Err(anyhow!(MinepackError::InvalidWorldName(world.to_string())));
+    }
+    Ok(())
+}
+
+/// Whether a name is the name of a single directory, so that it can't leave `saves/`
+fn is_directory_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\'])
+}
+
+/// Places a cached file into the matching directories of a game directory (e.g. `.minecraft`)
+fn install_mod_file(
+    cache_path: &Path,
+    minecraft_dir: &Path,
+    mod_entry: &ModEntry,
+    worlds: &[String],
+) -> Result<()> {
+    // Worlds are distributed as archives and have to be unpacked into the saves directory
+    if mod_entry.content_type == ContentType::World {
+        return extract_world(
+            cache_path,
+            &minecraft_dir.join(ContentType::World.directory()),
+        )
+        .map(|_| ())
+        .with_context(|| format!("Failed to extract world: {}", mod_entry.name));
+    }
+
+    // Launchers let players enable optional mods, which are shipped disabled
+    let file_name = if mod_entry.content_type == ContentType::Mod && !mod_entry.required {
+        format!("{}.disabled", mod_entry.version)
+    } else {
+        mod_entry.version.clone()
+    };
+    for dir in install_dirs(mod_entry, worlds) {
+        let target_dir = minecraft_dir.join(dir);
+        utils::ensure_dir_exists(&target_dir)?;
+        fs::copy(cache_path, target_dir.join(&file_name))
+            .with_context(|| format!("Failed to copy file: {}", cache_path.display()))?;
+    }
+    Ok(())
+}
+
+/// The name of the folder in `saves/` a world archive is unpacked into, and whether the archive
+/// already contains that folder. Worlds packed without a top-level folder get one named after
+/// the archive.
+fn world_folder(archive_path: &Path) -> Result<(String, bool)> {
+    let file = File::open(archive_path)
+        .with_context(|| format!("Failed to open world archive: {}", archive_path.display()))?;
+    let archive = ZipArchive::new(file).context("Failed to read world archive")?;
+    let level_dat = archive
+        .file_names()
+        .filter(|name| *name == "level.dat" || name.ends_with("/level.dat"))
+        .min_by_key(|name| name.matches('/').count())
+        .ok_or_else(|| anyhow!("World archive has no level.dat: {}", archive_path.display()))?;
+
+    let (folder, packed_in_folder) = match level_dat.split_once('/') {
+        Some((folder, _)) => (folder.to_string(), true),
+        None => (
+            archive_path
+                .file_stem()
+                .ok_or_else(|| anyhow!("Invalid world archive name"))?
+                .to_string_lossy()
+                .to_string(),
+            false,
+        ),
+    };
+    // The folder name is used in the paths of data packs, so it must not leave `saves/`
+    if !is_directory_name(&folder) {
+        return Err(anyhow!(MinepackError::UnsafeArchive(
+            archive_path.display().to_string(),
+            format!("'{}' is not a valid world folder", folder)
+        )));
+    }
+    Ok((folder, packed_in_folder))
+}
+
+/// Extracts a world archive into the saves directory, returning the name of the world's folder
+pub(crate) fn extract_world(archive_path: &Path, saves_dir: &Path) -> Result<String> {
+    let (folder, packed_in_folder) = world_folder(archive_path)?;
+    let target_dir = if packed_in_folder {
+        saves_dir.to_path_buf()
+    } else {
+        saves_dir.join(&folder)
+    };
+
+    utils::ensure_dir_exists(&target_dir)?;
+    archive::extract_zip(archive_path, &target_dir, &ExtractLimits::default())
+        .context("Failed to extract world archive")?;
+    Ok(folder)
 }
 
 async fn build_multimc_pack<E: utils::Env>(
     env: &E,
-    config: &crate::models::config::ModpackConfig,
+    config: &ModpackConfig,
     build_dir: &Path,
+    archive_stem: &str,
     mod_entries: &[ModEntry],
-    pb: ProgressBar,
-) -> Result<()> {
+    overrides: &[&OverrideFile],
+    pb: &dyn Progress,
+) -> Result<(PathBuf, Vec<ManualFile>)> {
     // Create instance directory structure inside a temp directory
     let temp_dir = build_dir.join("temp_multimc");
     utils::ensure_dir_exists(&temp_dir)?;
 
     // Initialize CurseForge client for potential mod downloads
-    let client = CurseforgeClient::new().context("Failed to initialize Curseforge API client")?;
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
 
     // Create MultiMC instance structure
     let instance_dir = temp_dir.join(&config.name);
@@ -174,17 +793,12 @@ async fn build_multimc_pack<E: utils::Env>(
     // Create .minecraft and mods directories
     let minecraft_dir = instance_dir.join(".minecraft");
     utils::ensure_dir_exists(&minecraft_dir)?;
-    let mods_dir = minecraft_dir.join("mods");
+    let mods_dir = minecraft_dir.join(ContentType::Mod.directory());
     utils::ensure_dir_exists(&mods_dir)?;
 
-    // Copy configuration files if they exist
-    let config_dir_path = utils::get_config_dir(env)?;
-    if config_dir_path.exists() {
-        let target_config_dir = minecraft_dir.join("config");
-        utils::ensure_dir_exists(&target_config_dir)?;
-        copy_directory(&config_dir_path, &target_config_dir)
-            .context("Failed to copy configuration files")?;
-    }
+    // Copy configuration files and other user content into the game directory
+    overrides::copy_overrides(env, overrides, &minecraft_dir)
+        .context("Failed to copy user content")?;
 
     // Create instance.cfg
     let instance_cfg = format!(
@@ -225,58 +839,50 @@ async fn build_multimc_pack<E: utils::Env>(
     fs::write(instance_dir.join("mmc-pack.json"), components)
         .context("Failed to write mmc-pack.json")?;
 
-    // Copy all mods from cache
-    pb.set_message("Copying mod files");
-    let cache_mods_dir = utils::get_minepack_cache_mods_dir(env)?;
-
+    // Copy all mods, resource packs, etc. from cache into their instance directories,
+    // an instance is a client so server-only mods are left out
+    let client_entries: Vec<&ModEntry> = mod_entries
+        .iter()
+        .filter(|entry| entry.side != Side::Server)
+        .collect();
+    let manual = prefetch_mod_files(env, &client, &client_entries, pb).await?;
+    let worlds = data_pack_worlds(env, &client, config, &client_entries, &manual, pb).await?;
+    pb.set_message("Copying mod files".to_string());
     for mod_entry in mod_entries {
-        let filename = &mod_entry.version; // filename is stored in version field now
-        let cache_path = cache_mods_dir.join(filename);
-        let target_path = mods_dir.join(filename);
-
-        // If the mod file exists in cache, copy it
-        if cache_path.exists() {
-            fs::copy(&cache_path, &target_path)
-                .with_context(|| format!("Failed to copy mod file: {}", cache_path.display()))?;
-        } else {
-            // Try to download it if not found in cache
-            pb.set_message(format!("Mod file not in cache, downloading: {}", filename));
-            // Download URL is constructed from project_id and file_id
-            let data = client
-                .download_mod_file(mod_entry.project_id, mod_entry.file_id)
-                .await
-                .with_context(|| format!("Failed to download mod: {}", mod_entry.name))?;
-
-            // Save to target directly
-            let mut file = File::create(&target_path)
-                .with_context(|| format!("Failed to create file: {}", target_path.display()))?;
-            file.write_all(&data)
-                .context("Failed to write mod data to file")?;
+        if mod_entry.side != Side::Server && !is_manual(&manual, mod_entry) {
+            let cache_path = cache_mod_file(env, &client, mod_entry, pb).await?;
+            install_mod_file(&cache_path, &minecraft_dir, mod_entry, &worlds)?;
         }
-
         pb.inc(1);
     }
+    write_credits(env, &client, config, &client_entries, &minecraft_dir).await?;
+    let manual_files: Vec<ManualFile> = manual.into_iter().map(ManualFile::from_entry).collect();
+    manual::write_manual_downloads(config, &manual_files, &minecraft_dir)?;
 
     // Create zip archive
-    let output_path = build_dir.join(format!("{}-{}-MultiMC.zip", config.name, config.version));
-    zip_directory(&temp_dir, &output_path).context("Failed to create zip archive")?;
+    let output_path = build_dir.join(format!("{}-MultiMC.zip", archive_stem));
+    zip_directory(&temp_dir, &output_path, archive_timestamp(env)?)
+        .context("Failed to create zip archive")?;
 
     // Clean up temp directory
     fs::remove_dir_all(temp_dir).context("Failed to clean up temporary directory")?;
 
-    pb.finish_with_message(format!("Built MultiMC pack: {}", output_path.display()));
-    Ok(())
+    pb.finish(format!("Built MultiMC pack: {}", output_path.display()));
+    Ok((output_path, manual_files))
 }
 
 async fn build_curseforge_pack<E: utils::Env>(
     env: &E,
-    config: &crate::models::config::ModpackConfig,
+    config: &ModpackConfig,
     build_dir: &Path,
+    archive_stem: &str,
     mod_entries: &[ModEntry],
-    pb: ProgressBar,
-) -> Result<()> {
-    // Initialize CurseForge client for potential mod downloads
-    let _client = CurseforgeClient::new().context("Failed to initialize Curseforge API client")?;
+    overrides: &[&OverrideFile],
+    pb: &dyn Progress,
+) -> Result<(PathBuf, Vec<ManualFile>)> {
+    // Initialize CurseForge client for the mod list
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
 
     // Create directory structure inside a temp directory
     let temp_dir = build_dir.join("temp_curseforge");
@@ -286,7 +892,7 @@ async fn build_curseforge_pack<E: utils::Env>(
     let loader_type = &config.minecraft.mod_loaders[0].id;
 
     // Create manifest using proper types
-    pb.set_message("Building manifest");
+    pb.set_message("Building manifest".to_string());
 
     // Create mod loader entry - use the required version
     let loader_id = format!(
@@ -338,245 +944,336 @@ async fn build_curseforge_pack<E: utils::Env>(
     let overrides_dir = temp_dir.join("overrides");
     utils::ensure_dir_exists(&overrides_dir)?;
 
-    // Copy config directory if it exists
-    let config_dir_path = utils::get_config_dir(env)?;
-    if config_dir_path.exists() {
-        let target_config_dir = overrides_dir.join("config");
-        utils::ensure_dir_exists(&target_config_dir)?;
-        copy_directory(&config_dir_path, &target_config_dir)
-            .context("Failed to copy configuration files")?;
-    }
-
-    // Copy user content to overrides directory
-    pb.set_message("Copying user content to overrides");
-    copy_user_content(env, &overrides_dir).context("Failed to copy user content to overrides")?;
+    // Copy configuration files and other user content to overrides directory
+    pb.set_message("Copying user content to overrides".to_string());
+    overrides::copy_overrides(env, overrides, &overrides_dir)
+        .context("Failed to copy user content to overrides")?;
+
+    // CurseForge packs list their mods in modlist.html next to the manifest
+    pb.set_message("Writing mod list".to_string());
+    let entries: Vec<&ModEntry> = mod_entries.iter().collect();
+    let mod_list = modlist::collect_mod_list(env, &client, &entries).await;
+    fs::write(
+        temp_dir.join(modlist::MODLIST_FILENAME),
+        modlist::render_modlist(&mod_list),
+    )
+    .context("Failed to write modlist.html")?;
+    modlist::write_credits(config, &mod_list, &overrides_dir)?;
 
     // Create zip archive
-    let output_path = build_dir.join(format!("{}-{}-CurseForge.zip", config.name, config.version));
-    zip_directory(&temp_dir, &output_path).context("Failed to create zip archive")?;
+    let output_path = build_dir.join(format!("{}-CurseForge.zip", archive_stem));
+    zip_directory(&temp_dir, &output_path, archive_timestamp(env)?)
+        .context("Failed to create zip archive")?;
 
     // Clean up temp directory
     fs::remove_dir_all(temp_dir).context("Failed to clean up temporary directory")?;
 
-    pb.finish_with_message(format!("Built CurseForge pack: {}", output_path.display()));
-    Ok(())
+    pb.finish(format!("Built CurseForge pack: {}", output_path.display()));
+    // The CurseForge app asks players to download these files themselves
+    Ok((output_path, Vec::new()))
 }
 
 async fn build_modrinth_pack<E: utils::Env>(
     env: &E,
-    config: &crate::models::config::ModpackConfig,
+    config: &ModpackConfig,
     build_dir: &Path,
+    archive_stem: &str,
     mod_entries: &[ModEntry],
-    pb: ProgressBar,
-) -> Result<()> {
-    // Initialize CurseForge client for potential mod downloads
-    let _client = CurseforgeClient::new().context("Failed to initialize Curseforge API client")?;
+    overrides: &[&OverrideFile],
+    pb: &dyn Progress,
+) -> Result<(PathBuf, Vec<ManualFile>)> {
+    // Initialize CurseForge client for world downloads
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
 
     // Create directory structure inside a temp directory
     let temp_dir = build_dir.join("temp_modrinth");
     utils::ensure_dir_exists(&temp_dir)?;
 
-    // Create modrinth.index.json
-    let loader_type = &config.minecraft.mod_loaders[0].id;
-
-    // Use mod loader version (now required)
-    let loader_version = &config.minecraft.mod_loaders[0].version;
-
-    let mut index = String::from("{\n");
-    index.push_str("  \"formatVersion\": 1,\n");
-    index.push_str("  \"game\": \"minecraft\",\n");
-    index.push_str(&format!("  \"versionId\": \"{}\",\n", config.version));
-    index.push_str(&format!("  \"name\": \"{}\",\n", config.name));
+    // Create overrides directory for configs
+    let overrides_dir = temp_dir.join("overrides");
+    utils::ensure_dir_exists(&overrides_dir)?;
 
-    if let Some(desc) = &config.description {
-        index.push_str(&format!("  \"summary\": \"{}\",\n", desc));
+    // Worlds can't be unpacked by launchers, so they are shipped unpacked in the overrides
+    pb.set_message("Adding worlds to overrides".to_string());
+    let (world_entries, file_entries): (Vec<&ModEntry>, Vec<&ModEntry>) = mod_entries
+        .iter()
+        .partition(|entry| entry.content_type == ContentType::World);
+    let mut manual = prefetch_mod_files(env, &client, &world_entries, pb).await?;
+    for world_entry in &world_entries {
+        if !is_manual(&manual, world_entry) {
+            let cache_path = cache_mod_file(env, &client, world_entry, pb).await?;
+            install_mod_file(&cache_path, &overrides_dir, world_entry, &[])?;
+        }
+        pb.inc(1);
     }
+    let entries: Vec<&ModEntry> = mod_entries.iter().collect();
+    let worlds = data_pack_worlds(env, &client, config, &entries, &manual, pb).await?;
+
+    // Launchers verify the downloaded files against the hashes and size in the index
+    pb.set_message("Building Modrinth index".to_string());
+    let missing = prefetch_mod_files(env, &client, &file_entries, pb).await?;
+    let mut files = Vec::new();
+    let mut rejected = Vec::new();
+    for mod_entry in file_entries {
+        // Launchers can't download these, so they can't be listed in the index
+        if mod_entry.manual_download.is_some() || is_manual(&missing, mod_entry) {
+            manual.push(mod_entry);
+            pb.inc(1);
+            continue;
+        }
 
-    index.push_str("  \"files\": [\n");
+        let cache_path = cache_mod_file(env, &client, mod_entry, pb).await?;
+        let data = fs::read(&cache_path)
+            .with_context(|| format!("Failed to read file: {}", cache_path.display()))?;
+        let hashes = BTreeMap::from([
+            ("sha1".to_string(), format!("{:x}", Sha1::digest(&data))),
+            ("sha512".to_string(), format!("{:x}", Sha512::digest(&data))),
+        ]);
+        if !modrinth::is_accepted_download(&mod_entry.download_url) {
+            rejected.push(mod_entry.name.as_str());
+        }
 
-    pb.set_message("Building Modrinth index");
-    for (i, mod_entry) in mod_entries.iter().enumerate() {
-        // Use filename from the version field as it now contains filename
-        let filename = &mod_entry.version;
+        // Data packs are listed once for every world they are installed into
+        for dir in install_dirs(mod_entry, &worlds) {
+            files.push(modrinth_schema::IndexFile {
+                // Use filename from the version field as it now contains filename
+                path: format!("{}/{}", dir, mod_entry.version),
+                hashes: hashes.clone(),
+                env: Some(modrinth_env(mod_entry)),
+                downloads: vec![mod_entry.download_url.clone()],
+                file_size: data.len() as u64,
+            });
+        }
+        pb.inc(1);
+    }
 
-        index.push_str("    {\n");
-        index.push_str(&format!("      \"path\": \"mods/{}\",\n", filename));
-        index.push_str("      \"hashes\": {},\n");
-        index.push_str(&format!(
-            "      \"downloads\": [\"{}\"],\n",
-            mod_entry.download_url
-        ));
-        index.push_str("      \"fileSize\": 0\n");
-        index.push_str(&format!(
-            "    }}{}\n",
-            if i < mod_entries.len() - 1 { "," } else { "" }
+    // Launchers download from anywhere, but Modrinth only publishes packs using its own hosts
+    if !rejected.is_empty() {
+        env.reporter().warning(&format!(
+            "⚠️ Modrinth only accepts packs downloading files from {}, this pack can't be published there: {}",
+            ACCEPTED_DOWNLOAD_HOSTS.join(", "),
+            rejected.join(", ")
         ));
-        pb.inc(1);
     }
 
-    index.push_str("  ],\n");
-    index.push_str("  \"dependencies\": {\n");
-    index.push_str(&format!(
-        "    \"minecraft\": \"{}\",\n",
-        config.minecraft.version
-    ));
-    index.push_str(&format!(
-        "    \"{}\": \"{}\"\n",
-        loader_type, loader_version
-    ));
-    index.push_str("  }\n");
-    index.push_str("}\n");
+    // Use mod loader version (now required)
+    let mod_loader = &config.minecraft.mod_loaders[0];
+    let index = modrinth_schema::Index {
+        format_version: 1,
+        game: "minecraft".to_string(),
+        version_id: config.version.clone(),
+        name: config.name.clone(),
+        summary: config.description.clone(),
+        files,
+        dependencies: BTreeMap::from([
+            ("minecraft".to_string(), config.minecraft.version.clone()),
+            (mod_loader.id.clone(), mod_loader.version.clone()),
+        ]),
+    };
 
-    fs::write(temp_dir.join("modrinth.index.json"), index)
+    let index_json =
+        serde_json::to_string_pretty(&index).context("Failed to serialize modrinth.index.json")?;
+    fs::write(temp_dir.join("modrinth.index.json"), index_json)
         .context("Failed to write modrinth.index.json")?;
 
-    // Create overrides directory for configs
-    let overrides_dir = temp_dir.join("overrides");
-    utils::ensure_dir_exists(&overrides_dir)?;
-
-    // Copy config directory if it exists
-    let config_dir_path = utils::get_config_dir(env)?;
-    if config_dir_path.exists() {
-        let target_config_dir = overrides_dir.join("config");
-        utils::ensure_dir_exists(&target_config_dir)?;
-        copy_directory(&config_dir_path, &target_config_dir)
-            .context("Failed to copy configuration files")?;
+    // Copy configuration files and other user content to the overrides directory of their side
+    pb.set_message("Copying user content to overrides".to_string());
+    for (dir, side) in overrides::OVERRIDE_DIRS {
+        let files: Vec<&OverrideFile> = overrides
+            .iter()
+            .copied()
+            .filter(|file| file.side == side)
+            .collect();
+        overrides::copy_overrides(env, &files, &temp_dir.join(dir))
+            .context("Failed to copy user content to overrides")?;
     }
-
-    // Copy user content to overrides directory
-    pb.set_message("Copying user content to overrides");
-    copy_user_content(env, &overrides_dir).context("Failed to copy user content to overrides")?;
+    write_credits(env, &client, config, &entries, &overrides_dir).await?;
+    let manual_files: Vec<ManualFile> = manual.into_iter().map(ManualFile::from_entry).collect();
+    manual::write_manual_downloads(config, &manual_files, &overrides_dir)?;
 
     // Create zip archive (with .mrpack extension)
-    let output_path = build_dir.join(format!("{}-{}.mrpack", config.name, config.version));
-    zip_directory(&temp_dir, &output_path).context("Failed to create mrpack archive")?;
+    let output_path = build_dir.join(format!("{}.mrpack", archive_stem));
+    zip_directory(&temp_dir, &output_path, archive_timestamp(env)?)
+        .context("Failed to create mrpack archive")?;
 
     // Clean up temp directory
     fs::remove_dir_all(temp_dir).context("Failed to clean up temporary directory")?;
 
-    pb.finish_with_message(format!("Built Modrinth pack: {}", output_path.display()));
-    Ok(())
+    pb.finish(format!("Built Modrinth pack: {}", output_path.display()));
+    Ok((output_path, manual_files))
 }
 
-// Copy a directory recursively
-fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
-    for entry in WalkDir::new(src) {
-        let entry = entry.context("Failed to read directory entry")?;
-        let path = entry.path();
-        let relative_path = path.strip_prefix(src).context("Failed to strip prefix")?;
-        let target_path = dst.join(relative_path);
-
-        if path.is_dir() {
-            fs::create_dir_all(&target_path).context("Failed to create directory")?;
-        } else {
-            fs::copy(path, &target_path).context("Failed to copy file")?;
-        }
+/// Whether an entry is used on the client and the server of a Modrinth pack
+fn modrinth_env(mod_entry: &ModEntry) -> IndexFileEnv {
+    let support = |supported: bool| match (supported, mod_entry.required) {
+        (false, _) => EnvSupport::Unsupported,
+        (true, true) => EnvSupport::Required,
+        (true, false) => EnvSupport::Optional,
+    };
+    IndexFileEnv {
+        client: support(mod_entry.side != Side::Server),
+        server: support(mod_entry.side != Side::Client),
     }
-    Ok(())
 }
 
-// Copy user content to overrides directory, excluding build-specific directories
-fn copy_user_content<E: utils::Env>(env: &E, overrides_dir: &Path) -> Result<()> {
-    let current_dir = env.current_dir()?;
-
-    // Directories to exclude from being copied (these are handled separately or shouldn't be included)
-    let excluded_dirs = ["build", ".minepack", ".git", "target"];
+async fn build_server_pack<E: utils::Env>(
+    env: &E,
+    config: &ModpackConfig,
+    build_dir: &Path,
+    archive_stem: &str,
+    mod_entries: &[ModEntry],
+    overrides: &[&OverrideFile],
+    pb: &dyn Progress,
+) -> Result<(PathBuf, Vec<ManualFile>)> {
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
+
+    // The archive is unpacked into the server directory
+    let temp_dir = build_dir.join("temp_server");
+    utils::ensure_dir_exists(&temp_dir)?;
 
-    for entry in fs::read_dir(&current_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    // Copy configuration files and other user content into the server directory
+    overrides::copy_overrides(env, overrides, &temp_dir).context("Failed to copy user content")?;
 
-        // Skip excluded directories
-        if path.is_dir() && excluded_dirs.contains(&file_name.as_ref()) {
-            continue;
+    // Servers only load mods and data packs, client-only mods are left out. Data packs go
+    // into the world the server loads.
+    let worlds = [server_world(env, config, overrides)?];
+    let server_entries: Vec<&ModEntry> = mod_entries
+        .iter()
+        .filter(|entry| {
+            entry.side != Side::Client
+                && matches!(entry.content_type, ContentType::Mod | ContentType::DataPack)
+        })
+        .collect();
+    let manual = prefetch_mod_files(env, &client, &server_entries, pb).await?;
+    pb.set_message("Copying mod files".to_string());
+    for mod_entry in &server_entries {
+        if !is_manual(&manual, mod_entry) {
+            let cache_path = cache_mod_file(env, &client, mod_entry, pb).await?;
+            install_mod_file(&cache_path, &temp_dir, mod_entry, &worlds)?;
         }
+        pb.inc(1);
+    }
+    pb.inc((mod_entries.len() - server_entries.len()) as u64);
+    write_credits(env, &client, config, &server_entries, &temp_dir).await?;
+    let manual_files: Vec<ManualFile> = manual.into_iter().map(ManualFile::from_entry).collect();
+    manual::write_manual_downloads(config, &manual_files, &temp_dir)?;
 
-        // Skip the minepack.json file
-        if path.is_file() && file_name == "minepack.json" {
-            continue;
-        }
+    // Create zip archive
+    let output_path = build_dir.join(format!("{}-Server.zip", archive_stem));
+    zip_directory(&temp_dir, &output_path, archive_timestamp(env)?)
+        .context("Failed to create zip archive")?;
 
-        // Skip .ex.json files
-        if path.is_file() && path.to_string_lossy().ends_with(".ex.json") {
-            continue;
-        }
+    // Clean up temp directory
+    fs::remove_dir_all(temp_dir).context("Failed to clean up temporary directory")?;
 
-        let relative_path = path.file_name().unwrap_or_default();
-        let target_path = overrides_dir.join(relative_path);
+    pb.finish(format!("Built server pack: {}", output_path.display()));
+    Ok((output_path, manual_files))
+}
 
-        if path.is_dir() {
-            copy_directory_excluding_ex_json(&path, &target_path)?;
-        } else if path.is_file() {
-            fs::copy(&path, &target_path)?;
-        }
+/// Writes the attribution files enabled in the modpack configuration into the game directory
+async fn write_credits<E: utils::Env>(
+    env: &E,
+    client: &CurseforgeClient,
+    config: &ModpackConfig,
+    mod_entries: &[&ModEntry],
+    game_dir: &Path,
+) -> Result<()> {
+    if config.credits.is_none() {
+        return Ok(());
     }
-
-    Ok(())
+    let mod_list = modlist::collect_mod_list(env, client, mod_entries).await;
+    modlist::write_credits(config, &mod_list, game_dir)
 }
 
-// Copy a directory recursively, excluding .ex.json files
-fn copy_directory_excluding_ex_json(src: &Path, dst: &Path) -> Result<()> {
-    for entry in WalkDir::new(src) {
-        let entry = entry.context("Failed to read directory entry")?;
-        let path = entry.path();
-
-        // Skip .ex.json files
-        if path.is_file() && path.to_string_lossy().ends_with(".ex.json") {
-            continue;
-        }
-
-        let relative_path = path.strip_prefix(src).context("Failed to strip prefix")?;
-        let target_path = dst.join(relative_path);
+/// Earliest time a zip archive can store, 1980-01-01T00:00:00Z
+const ZIP_EPOCH: i64 = 315_532_800;
+/// Latest time a zip archive can store, 2107-12-31T23:59:58Z
+const ZIP_MAX_TIME: i64 = 4_354_819_198;
+
+/// Modification time of all archive entries, so that the same inputs produce the same archive.
+/// Uses `SOURCE_DATE_EPOCH` if set, otherwise the earliest time a zip archive can store.
+fn archive_timestamp<E: utils::Env>(env: &E) -> Result<zip::DateTime> {
+    let secs = match env.var("SOURCE_DATE_EPOCH") {
+        Some(value) => value
+            .trim()
+            .parse::<i64>()
+            .with_context(|| format!("SOURCE_DATE_EPOCH is not a Unix timestamp: {}", value))?,
+        None => ZIP_EPOCH,
+    };
+    zip_datetime(secs)
+}
 
-        if path.is_dir() {
-            fs::create_dir_all(&target_path).context("Failed to create directory")?;
-        } else {
-            fs::copy(path, &target_path).context("Failed to copy file")?;
-        }
-    }
-    Ok(())
+/// Converts a Unix timestamp to the date and time format of zip archives, clamped to its range
+fn zip_datetime(secs: i64) -> Result<zip::DateTime> {
+    let secs = secs.clamp(ZIP_EPOCH, ZIP_MAX_TIME);
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's `civil_from_days`)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    zip::DateTime::from_date_and_time(
+        year as u16,
+        month as u8,
+        day as u8,
+        (time / 3600) as u8,
+        (time % 3600 / 60) as u8,
+        (time % 60) as u8,
+    )
+    .map_err(|_| anyhow!("Timestamp out of range for zip archives: {}", secs))
 }
 
-// Create a zip archive from a directory
-fn zip_directory(src_dir: &Path, dst_file: &Path) -> Result<()> {
+/// Create a zip archive from a directory. Entries are sorted and get the same timestamp
+/// and permissions, so the archive only depends on the contents of the directory.
+fn zip_directory(src_dir: &Path, dst_file: &Path, timestamp: zip::DateTime) -> Result<()> {
     let file = File::create(dst_file)
         .with_context(|| format!("Failed to create zip file: {}", dst_file.display()))?;
 
     let mut zip = zip::ZipWriter::new(file);
     let options = zip::write::FileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated)
-        .unix_permissions(0o755);
+        .last_modified_time(timestamp);
 
     // Add files to the zip archive
-    for entry in WalkDir::new(src_dir) {
+    for entry in WalkDir::new(src_dir).sort_by_file_name() {
         let entry = entry.context("Failed to read file for zipping")?;
         let path = entry.path();
         let relative_path = path
             .strip_prefix(src_dir)
             .context("Failed to strip prefix for zip entry")?;
+        if relative_path.as_os_str().is_empty() {
+            continue;
+        }
+
+        // Zip archives always use `/` as separator
+        let entry_name = relative_path
+            .components()
+            .map(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .ok_or_else(|| anyhow!("Invalid path"))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join("/");
 
         if path.is_dir() {
-            let path_string = relative_path
-                .to_str()
-                .ok_or_else(|| anyhow!("Invalid path"))?;
-            let dir_path = if path_string.is_empty() {
-                continue;
-            } else {
-                format!("{}/", path_string)
-            };
-            zip.add_directory(&dir_path, options)
+            zip.add_directory(format!("{}/", entry_name), options.unix_permissions(0o755))
                 .context("Failed to add directory to zip")?;
         } else {
             let mut file = File::open(path)
                 .with_context(|| format!("Failed to open file: {}", path.display()))?;
-            zip.start_file(
-                relative_path
-                    .to_str()
-                    .ok_or_else(|| anyhow!("Invalid path"))?,
-                options,
-            )
-            .context("Failed to add file to zip")?;
+            zip.start_file(entry_name, options.unix_permissions(0o644))
+                .context("Failed to add file to zip")?;
             std::io::copy(&mut file, &mut zip).context("Failed to copy file contents to zip")?;
         }
     }
@@ -584,3 +1281,33 @@ fn zip_directory(src_dir: &Path, dst_file: &Path) -> Result<()> {
     zip.finish().context("Failed to write zip file")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_and_time(datetime: zip::DateTime) -> (u16, u8, u8, u8, u8, u8) {
+        (
+            datetime.year(),
+            datetime.month(),
+            datetime.day(),
+            datetime.hour(),
+            datetime.minute(),
+            datetime.second(),
+        )
+    }
+
+    #[test]
+    fn test_zip_datetime() {
+        // 2024-02-29T13:45:30Z
+        assert_eq!(
+            date_and_time(zip_datetime(1_709_214_330).unwrap()),
+            (2024, 2, 29, 13, 45, 30)
+        );
+        // Times before 1980 are clamped to the earliest time a zip archive can store
+        assert_eq!(
+            date_and_time(zip_datetime(0).unwrap()),
+            (1980, 1, 1, 0, 0, 0)
+        );
+    }
+}
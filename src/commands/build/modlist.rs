@@ -0,0 +1,186 @@
+//! Attribution files listing the projects in the modpack.
+//!
+//! CurseForge packs always contain a `modlist.html` next to their manifest, like the ones
+//! exported by the CurseForge app. `credits` in the modpack configuration adds a
+//! `CREDITS.md` and a `modlist.html` to the game directory of every export format.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::api::curseforge::CurseforgeClient;
+use crate::models::config::{ModEntry, ModpackConfig};
+use crate::utils;
+
+pub const MODLIST_FILENAME: &str = "modlist.html";
+pub const CREDITS_FILENAME: &str = "CREDITS.md";
+
+/// A project listed in the attribution files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModListEntry {
+    pub name: String,
+    /// The project page, if it could be looked up on Curseforge
+    pub url: Option<String>,
+    pub authors: Vec<String>,
+    pub file_name: String,
+    pub required: bool,
+    /// Shown next to the name, so players know what optional content adds
+    pub description: Option<String>,
+}
+
+impl ModListEntry {
+    /// What follows the name of the project: whether it's optional and its description, on a
+    /// single line
+    fn note(&self) -> String {
+        let optional = if self.required { "" } else { " (optional)" };
+        let description = self
+            .description
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if description.is_empty() {
+            optional.to_string()
+        } else {
+            format!("{}: {}", optional, description)
+        }
+    }
+}
+
+/// Looks up project pages and authors on Curseforge. The list is still written when the
+/// lookup fails, with the names from the references only.
+pub async fn collect_mod_list<E: utils::Env>(
+    env: &E,
+    client: &CurseforgeClient,
+    mod_entries: &[&ModEntry],
+) -> Vec<ModListEntry> {
+    let mut project_ids: Vec<u32> = mod_entries.iter().map(|entry| entry.project_id).collect();
+    project_ids.sort_unstable();
+    project_ids.dedup();
+
+    let mut projects = HashMap::new();
+    if !project_ids.is_empty() {
+        match client.get_mod_infos(project_ids).await {
+            Ok(infos) => projects.extend(infos.into_iter().map(|info| (info.id, info))),
+            Err(err) => env.reporter().warning(&format!(
+                "⚠️ Failed to look up project pages and authors for the mod list: {:#}",
+                err
+            )),
+        }
+    }
+
+    let mut entries: Vec<ModListEntry> = mod_entries
+        .iter()
+        .map(|entry| {
+            let project = projects.get(&entry.project_id);
+            ModListEntry {
+                name: project
+                    .map(|project| project.name.clone())
+                    .unwrap_or_else(|| entry.name.clone()),
+                url: project.and_then(|project| project.links.website_url.clone()),
+                authors: project
+                    .map(|project| {
+                        project
+                            .authors
+                            .iter()
+                            .map(|author| author.name.clone())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                file_name: entry.version.clone(),
+                required: entry.required,
+                description: entry.description.clone(),
+            }
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+    entries
+}
+
+/// Writes the attribution files enabled in the modpack configuration into `game_dir`
+pub fn write_credits(
+    config: &ModpackConfig,
+    entries: &[ModListEntry],
+    game_dir: &Path,
+) -> Result<()> {
+    let Some(credits) = &config.credits else {
+        return Ok(());
+    };
+
+    utils::ensure_dir_exists(game_dir)?;
+    if credits.markdown {
+        fs::write(
+            game_dir.join(CREDITS_FILENAME),
+            render_credits(config, entries),
+        )
+        .with_context(|| format!("Failed to write {}", CREDITS_FILENAME))?;
+    }
+    if credits.html {
+        fs::write(game_dir.join(MODLIST_FILENAME), render_modlist(entries))
+            .with_context(|| format!("Failed to write {}", MODLIST_FILENAME))?;
+    }
+    Ok(())
+}
+
+/// Renders the mod list in the format of the CurseForge app
+pub fn render_modlist(entries: &[ModListEntry]) -> String {
+    let mut html = String::from("<ul>\n");
+    for entry in entries {
+        let mut label = escape_html(&entry.name);
+        if !entry.authors.is_empty() {
+            label.push_str(&format!(" (by {})", escape_html(&entry.authors.join(", "))));
+        }
+        let link = match &entry.url {
+            Some(url) => format!("<a href=\"{}\">{}</a>", escape_html(url), label),
+            None => label,
+        };
+        html.push_str(&format!(
+            "<li>{}{}</li>\n",
+            link,
+            escape_html(&entry.note())
+        ));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+/// Renders a Markdown table crediting the authors of every project
+pub fn render_credits(config: &ModpackConfig, entries: &[ModListEntry]) -> String {
+    let mut markdown = format!(
+        "# Credits\n\n{} {} by {} is built from the following projects. Thanks to their authors!\n\n",
+        config.name, config.version, config.author
+    );
+    markdown.push_str("| Project | Authors | File |\n| --- | --- | --- |\n");
+    for entry in entries {
+        let name = escape_markdown(&entry.name);
+        let project = match &entry.url {
+            Some(url) => format!("[{}]({})", name, url),
+            None => name,
+        };
+        markdown.push_str(&format!(
+            "| {}{} | {} | {} |\n",
+            project,
+            escape_markdown(&entry.note()),
+            escape_markdown(&entry.authors.join(", ")),
+            escape_markdown(&entry.file_name)
+        ));
+    }
+    markdown
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes characters that would break a Markdown table cell or link text
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
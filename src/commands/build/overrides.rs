@@ -0,0 +1,208 @@
+//! Selects the project files that are shipped with the modpack as overrides.
+//!
+//! Files in `overrides/`, `client-overrides/` and `server-overrides/` are placed into the
+//! game directory of the matching side. Other project files are shipped to both sides as
+//! they are.
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::config::{ModpackConfig, Profile, Side};
+use crate::utils;
+
+/// Gitignore-style rules for files that are never shipped, in the project directory
+pub const IGNORE_FILENAME: &str = ".minepackignore";
+
+/// Top-level directories that are never shipped
+const EXCLUDED_DIRS: [&str; 4] = ["build", ".minepack", ".git", "target"];
+
+/// Top-level files that are never shipped
+const EXCLUDED_FILES: [&str; 3] = ["minepack.json", "minepack.toml", IGNORE_FILENAME];
+
+/// Project directories whose content is placed into the game directory of a side
+pub const OVERRIDE_DIRS: [(&str, Side); 3] = [
+    ("overrides", Side::Both),
+    ("client-overrides", Side::Client),
+    ("server-overrides", Side::Server),
+];
+
+/// A project file shipped with the modpack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverrideFile {
+    /// Path relative to the project directory
+    pub source: PathBuf,
+    /// Path relative to the game directory
+    pub target: PathBuf,
+    /// The side the file is shipped to
+    pub side: Side,
+}
+
+/// The files shipped as overrides
+#[derive(Debug, Default)]
+pub struct OverrideFiles {
+    /// Sorted by their path in the project directory
+    pub files: Vec<OverrideFile>,
+    /// Number of files left out by `.minepackignore`, `overrides.include` or the build profile
+    pub skipped: usize,
+}
+
+/// Collects the project files to ship. Reference files and minepack's own files are always
+/// left out, as are files matching `.minepackignore`. If `overrides.include` is set in the
+/// modpack configuration, only files matching one of its patterns are shipped. A build
+/// profile narrows the files down further by side and its own patterns.
+pub fn collect_overrides<E: utils::Env>(
+    env: &E,
+    config: &ModpackConfig,
+    profile: Option<&Profile>,
+) -> Result<OverrideFiles> {
+    let current_dir = env.current_dir()?;
+    let ignore = load_ignore_file(&current_dir)?;
+
+    // A file has to match every include list that is set
+    let mut includes = Vec::new();
+    if let Some(overrides) = &config.overrides {
+        if !overrides.include.is_empty() {
+            includes.push(build_matcher(
+                &current_dir,
+                &overrides.include,
+                "overrides.include",
+            )?);
+        }
+    }
+    if let Some(profile) = profile {
+        if !profile.include_overrides.is_empty() {
+            includes.push(build_matcher(
+                &current_dir,
+                &profile.include_overrides,
+                "include_overrides",
+            )?);
+        }
+    }
+    let profile_exclude = profile
+        .filter(|profile| !profile.exclude_overrides.is_empty())
+        .map(|profile| {
+            build_matcher(
+                &current_dir,
+                &profile.exclude_overrides,
+                "exclude_overrides",
+            )
+        })
+        .transpose()?;
+
+    let mut overrides = OverrideFiles::default();
+    let walker = WalkDir::new(&current_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() > 1
+                || !entry.file_type().is_dir()
+                || !EXCLUDED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+        });
+
+    for entry in walker {
+        let entry = entry.context("Failed to read directory entry")?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+
+        let relative_path = entry
+            .path()
+            .strip_prefix(&current_dir)
+            .context("Failed to strip prefix")?;
+        let file_name = entry.file_name().to_string_lossy();
+        if file_name.ends_with(".ex.json")
+            || (entry.depth() == 1 && EXCLUDED_FILES.contains(&file_name.as_ref()))
+        {
+            continue;
+        }
+
+        let matches = |matcher: &Gitignore| {
+            matcher
+                .matched_path_or_any_parents(relative_path, false)
+                .is_ignore()
+        };
+        let (target, side) = override_target(relative_path);
+        let ignored = matches(&ignore) || profile_exclude.as_ref().is_some_and(matches);
+        let included = includes.iter().all(matches)
+            && profile.is_none_or(|profile| profile.includes_side(side));
+        if ignored || !included {
+            overrides.skipped += 1;
+            continue;
+        }
+
+        overrides.files.push(OverrideFile {
+            source: relative_path.to_path_buf(),
+            target,
+            side,
+        });
+    }
+
+    Ok(overrides)
+}
+
+/// Where a project file is placed in the game directory and for which side
+fn override_target(relative_path: &Path) -> (PathBuf, Side) {
+    let mut components = relative_path.components();
+    let first = components
+        .next()
+        .map(|component| component.as_os_str().to_string_lossy().to_string());
+
+    for (dir, side) in OVERRIDE_DIRS {
+        if first.as_deref() == Some(dir) {
+            return (components.as_path().to_path_buf(), side);
+        }
+    }
+    (relative_path.to_path_buf(), Side::Both)
+}
+
+/// Copies the override files from the project directory into `target_dir`. Files for both
+/// sides are copied first, so that side-specific files replace them.
+pub fn copy_overrides<E: utils::Env>(
+    env: &E,
+    files: &[&OverrideFile],
+    target_dir: &Path,
+) -> Result<()> {
+    let current_dir = env.current_dir()?;
+    let (common, side_specific): (Vec<&OverrideFile>, Vec<&OverrideFile>) =
+        files.iter().partition(|file| file.side == Side::Both);
+
+    for file in common.into_iter().chain(side_specific) {
+        let target_path = target_dir.join(&file.target);
+        if let Some(parent) = target_path.parent() {
+            utils::ensure_dir_exists(parent)?;
+        }
+        fs::copy(current_dir.join(&file.source), &target_path)
+            .with_context(|| format!("Failed to copy file: {}", file.source.display()))?;
+    }
+    Ok(())
+}
+
+fn load_ignore_file(current_dir: &Path) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(current_dir);
+    let ignore_path = current_dir.join(IGNORE_FILENAME);
+    if ignore_path.exists() {
+        if let Some(err) = builder.add(&ignore_path) {
+            return Err(err).with_context(|| format!("Failed to parse {}", IGNORE_FILENAME));
+        }
+    }
+    builder
+        .build()
+        .with_context(|| format!("Failed to parse {}", IGNORE_FILENAME))
+}
+
+/// Builds a matcher for gitignore-style patterns from the setting `name`
+fn build_matcher(current_dir: &Path, patterns: &[String], name: &str) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(current_dir);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("Invalid pattern in {}: {}", name, pattern))?;
+    }
+    builder
+        .build()
+        .with_context(|| format!("Failed to parse {}", name))
+}
@@ -0,0 +1,577 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use url::Url;
+use zip::ZipArchive;
+
+use crate::api::curseforge::schema::Manifest;
+use crate::api::curseforge::CurseforgeClient;
+use crate::api::modrinth::schema::Index;
+use crate::models::config::{ContentType, Reference};
+use crate::utils;
+use crate::utils::errors::MinepackError;
+use crate::utils::reference_store;
+
+/// Options for the changelog command
+#[derive(Debug, Clone, Default)]
+pub struct ChangelogOptions {
+    /// Git revision, CurseForge `manifest.json`, `modrinth.index.json` or built modpack to
+    /// compare from
+    pub from: String,
+    /// Same as `from`, the current references of the project if not set
+    pub to: Option<String>,
+    /// Output format (markdown, json)
+    pub format: Option<String>,
+    /// Include the Curseforge changelogs of updated files
+    pub upstream: bool,
+    /// Write the changelog to this file instead of printing it
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChangelogFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+impl FromStr for ChangelogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(ChangelogFormat::Markdown),
+            "json" => Ok(ChangelogFormat::Json),
+            _ => Err(anyhow!(MinepackError::InvalidChangelogFormat(
+                s.to_string()
+            ))),
+        }
+    }
+}
+
+/// The differences between two versions of the modpack
+#[derive(Debug, Serialize)]
+pub struct Changelog {
+    pub from: String,
+    pub to: String,
+    pub added: Vec<ModChange>,
+    pub removed: Vec<ModChange>,
+    pub updated: Vec<ModChange>,
+    pub downgraded: Vec<ModChange>,
+}
+
+impl Changelog {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.updated.is_empty()
+            && self.downgraded.is_empty()
+    }
+}
+
+/// A project that was added, removed or changed to another file
+#[derive(Debug, Serialize, Clone)]
+pub struct ModChange {
+    pub project_id: u32,
+    pub name: String,
+    /// The file before the change, not set for added projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_file: Option<FileVersion>,
+    /// The file after the change, not set for removed projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_file: Option<FileVersion>,
+    /// Changelog of the new file as HTML, fetched from Curseforge with `upstream`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_changelog: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FileVersion {
+    pub file_id: u32,
+    pub file_name: String,
+    /// The name Curseforge shows for the file, the file name if it couldn't be looked up
+    pub display_name: String,
+}
+
+/// A file in one version of the modpack. Built modpacks don't record everything, the
+/// missing parts are looked up on Curseforge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PackFile {
+    project_id: Option<u32>,
+    file_id: u32,
+    name: Option<String>,
+    file_name: Option<String>,
+}
+
+impl From<&Reference> for PackFile {
+    fn from(reference: &Reference) -> Self {
+        Self {
+            project_id: Some(reference.project_id()),
+            file_id: reference.file_id(),
+            name: Some(reference.name.clone()),
+            file_name: Some(reference.filename.clone()),
+        }
+    }
+}
+
+/// Compare two versions of the modpack and print the changes
+pub async fn run<E: utils::Env>(env: &E, options: ChangelogOptions) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let format = options
+        .format
+        .as_deref()
+        .map(ChangelogFormat::from_str)
+        .transpose()?
+        .unwrap_or_default();
+    let changelog = changelog(env, &options).await?;
+    let content = match format {
+        ChangelogFormat::Markdown => render_markdown(&changelog),
+        ChangelogFormat::Json => {
+            serde_json::to_string_pretty(&changelog).context("Failed to serialize changelog")?
+        }
+    };
+
+    match &options.output {
+        Some(path) => {
+            let path = env.current_dir()?.join(path);
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write changelog: {}", path.display()))?;
+            env.reporter()
+                .message(&format!("✅ Changelog written to {}", path.display()));
+        }
+        None => env.reporter().message(content.trim_end()),
+    }
+
+    Ok(())
+}
+
+/// Compares the modpack at `options.from` with `options.to`. Projects are matched by their
+/// Curseforge project ID, file IDs tell updates from downgrades.
+pub async fn changelog<E: utils::Env>(env: &E, options: &ChangelogOptions) -> Result<Changelog> {
+    // The API is only needed for built modpacks, display names and upstream changelogs
+    let client = CurseforgeClient::new(env).ok();
+
+    let from = load_version(env, &options.from, client.as_ref()).await?;
+    let to = match &options.to {
+        Some(source) => load_version(env, source, client.as_ref()).await?,
+        None => utils::read_references(env)?
+            .iter()
+            .map(|(_, reference)| PackFile::from(reference))
+            .collect(),
+    };
+
+    let from = by_project(from);
+    let to = by_project(to);
+
+    // Built modpacks only record IDs, names are taken from the other version where possible
+    let mut names: HashMap<u32, String> = from
+        .iter()
+        .chain(&to)
+        .filter_map(|(project_id, file)| file.name.clone().map(|name| (*project_id, name)))
+        .collect();
+    let mut file_infos = HashMap::new();
+
+    // Only the projects that changed are looked up
+    let changed: BTreeSet<u32> = from
+        .keys()
+        .chain(to.keys())
+        .filter(|project_id| {
+            from.get(project_id).map(|file| file.file_id)
+                != to.get(project_id).map(|file| file.file_id)
+        })
+        .copied()
+        .collect();
+    let unnamed: Vec<u32> = changed
+        .iter()
+        .filter(|project_id| !names.contains_key(project_id))
+        .copied()
+        .collect();
+    let changed_files: Vec<u32> = changed
+        .iter()
+        .flat_map(|project_id| [from.get(project_id), to.get(project_id)])
+        .flatten()
+        .map(|file| file.file_id)
+        .collect();
+
+    if let Some(client) = &client {
+        if !unnamed.is_empty() {
+            match client.get_mod_infos(unnamed).await {
+                Ok(mods) => names.extend(mods.into_iter().map(|info| (info.id, info.name))),
+                Err(err) => env
+                    .reporter()
+                    .warning(&format!("⚠️ Failed to look up project names: {:#}", err)),
+            }
+        }
+        if !changed_files.is_empty() {
+            match client.get_file_infos(changed_files).await {
+                Ok(files) => file_infos.extend(files.into_iter().map(|file| (file.id, file))),
+                Err(err) => env.reporter().warning(&format!(
+                    "⚠️ Failed to look up file display names, showing file names: {:#}",
+                    err
+                )),
+            }
+        }
+    }
+
+    let version = |file: &PackFile| {
+        let info = file_infos.get(&file.file_id);
+        let file_name = file
+            .file_name
+            .clone()
+            .or_else(|| info.map(|info| info.file_name.clone()))
+            .unwrap_or_else(|| format!("file {}", file.file_id));
+        FileVersion {
+            file_id: file.file_id,
+            display_name: info
+                .map(|info| info.display_name.clone())
+                .unwrap_or_else(|| file_name.clone()),
+            file_name,
+        }
+    };
+    let change = |project_id: u32, old: Option<&PackFile>, new: Option<&PackFile>| ModChange {
+        project_id,
+        name: names
+            .get(&project_id)
+            .cloned()
+            .unwrap_or_else(|| format!("Project {}", project_id)),
+        old_file: old.map(version),
+        new_file: new.map(version),
+        upstream_changelog: None,
+    };
+
+    let mut changelog = Changelog {
+        from: options.from.clone(),
+        to: options
+            .to
+            .clone()
+            .unwrap_or_else(|| "working tree".to_string()),
+        added: Vec::new(),
+        removed: Vec::new(),
+        updated: Vec::new(),
+        downgraded: Vec::new(),
+    };
+    for (project_id, old) in &from {
+        match to.get(project_id) {
+            None => changelog.removed.push(change(*project_id, Some(old), None)),
+            // Curseforge file IDs increase with every upload
+            Some(new) if new.file_id > old.file_id => {
+                changelog
+                    .updated
+                    .push(change(*project_id, Some(old), Some(new)))
+            }
+            Some(new) if new.file_id < old.file_id => {
+                changelog
+                    .downgraded
+                    .push(change(*project_id, Some(old), Some(new)))
+            }
+            Some(_) => {}
+        }
+    }
+    for (project_id, new) in &to {
+        if !from.contains_key(project_id) {
+            changelog.added.push(change(*project_id, None, Some(new)));
+        }
+    }
+
+    for changes in [
+        &mut changelog.added,
+        &mut changelog.removed,
+        &mut changelog.updated,
+        &mut changelog.downgraded,
+    ] {
+        changes.sort_by_key(|change| change.name.to_lowercase());
+    }
+
+    if options.upstream {
+        let client = client.ok_or_else(|| anyhow!(MinepackError::ApiKeyNotFound))?;
+        for change in &mut changelog.updated {
+            let Some(new_file) = &change.new_file else {
+                continue;
+            };
+            match client
+                .get_mod_file_changelog(change.project_id, new_file.file_id)
+                .await
+            {
+                Ok(upstream) => change.upstream_changelog = Some(upstream),
+                Err(err) => env.reporter().warning(&format!(
+                    "⚠️ Failed to fetch the changelog of {}: {:#}",
+                    change.name, err
+                )),
+            }
+        }
+    }
+
+    Ok(changelog)
+}
+
+/// Indexes the files of a version by project, files without a project were dropped before
+fn by_project(files: Vec<PackFile>) -> BTreeMap<u32, PackFile> {
+    files
+        .into_iter()
+        .filter_map(|file| file.project_id.map(|project_id| (project_id, file)))
+        .collect()
+}
+
+/// Reads the files of a modpack version. `source` is a CurseForge `manifest.json`, a
+/// `modrinth.index.json` or a built modpack if such a file exists, a git revision otherwise.
+async fn load_version<E: utils::Env>(
+    env: &E,
+    source: &str,
+    client: Option<&CurseforgeClient>,
+) -> Result<Vec<PackFile>> {
+    let current_dir = env.current_dir()?;
+    let path = current_dir.join(source);
+
+    let mut files = if path.is_file() {
+        read_pack_file(&path)?
+    } else {
+        read_git_revision(&current_dir, source)?
+    };
+
+    // Modrinth indexes only know the files, their projects have to be looked up
+    let file_ids: Vec<u32> = files
+        .iter()
+        .filter(|file| file.project_id.is_none())
+        .map(|file| file.file_id)
+        .collect();
+    if !file_ids.is_empty() {
+        let client = client.ok_or_else(|| anyhow!(MinepackError::ApiKeyNotFound))?;
+        let projects: HashMap<u32, u32> = client
+            .get_file_infos(file_ids)
+            .await
+            .with_context(|| format!("Failed to look up the projects of the files in {}", source))?
+            .into_iter()
+            .map(|file| (file.id, file.mod_id))
+            .collect();
+        for file in &mut files {
+            if file.project_id.is_none() {
+                file.project_id = Some(*projects.get(&file.file_id).ok_or_else(|| {
+                    anyhow!(MinepackError::CurseforgeApiError(format!(
+                        "File ID {} not found",
+                        file.file_id
+                    )))
+                })?);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Reads the files from a manifest, an index or a built modpack containing one of them
+fn read_pack_file(path: &Path) -> Result<Vec<PackFile>> {
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    if is_json {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        return parse_pack_json(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()));
+    }
+
+    let file =
+        fs::File::open(path).with_context(|| format!("Failed to open: {}", path.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read modpack archive: {}", path.display()))?;
+    for name in ["manifest.json", "modrinth.index.json"] {
+        let Ok(mut entry) = archive.by_name(name) else {
+            continue;
+        };
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .with_context(|| format!("Failed to read {} from {}", name, path.display()))?;
+        return parse_pack_json(&content)
+            .with_context(|| format!("Failed to parse {} in {}", name, path.display()));
+    }
+
+    Err(anyhow!(MinepackError::InvalidFileFormat(format!(
+        "{} has no manifest.json or modrinth.index.json",
+        path.display()
+    ))))
+}
+
+fn parse_pack_json(content: &str) -> Result<Vec<PackFile>> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+
+    if value.get("formatVersion").is_some() {
+        let index: Index = serde_json::from_value(value)?;
+        return index
+            .files
+            .iter()
+            .map(|file| {
+                let file_id = file
+                    .downloads
+                    .iter()
+                    .find_map(|url| curseforge_file_id(url))
+                    .ok_or_else(|| {
+                        anyhow!(MinepackError::InvalidFileFormat(format!(
+                            "{} is not downloaded from Curseforge",
+                            file.path
+                        )))
+                    })?;
+                Ok(PackFile {
+                    project_id: None,
+                    file_id,
+                    name: None,
+                    file_name: Path::new(&file.path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string()),
+                })
+            })
+            .collect();
+    }
+
+    let manifest: Manifest = serde_json::from_value(value)?;
+    Ok(manifest
+        .files
+        .iter()
+        .map(|file| PackFile {
+            project_id: Some(file.project_id),
+            file_id: file.file_id,
+            name: None,
+            file_name: None,
+        })
+        .collect())
+}
+
+/// The file ID of a Curseforge CDN URL (`.../files/6332/315/name.jar`)
+fn curseforge_file_id(url: &str) -> Option<u32> {
+    let url = Url::parse(url).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    let [.., "files", high, low, _] = segments.as_slice() else {
+        return None;
+    };
+    Some(high.parse::<u32>().ok()? * 1000 + low.parse::<u32>().ok()?)
+}
+
+/// Reads the reference files of a git revision
+fn read_git_revision(current_dir: &Path, revision: &str) -> Result<Vec<PackFile>> {
+    let commit = format!("{}^{{commit}}", revision);
+    if git(current_dir, &["rev-parse", "--verify", "--quiet", &commit]).is_err() {
+        return Err(anyhow!(MinepackError::UnknownChangelogSource(
+            revision.to_string()
+        )));
+    }
+
+    let mut files = Vec::new();
+    for content_type in ContentType::ALL {
+        // Paths are listed relative to the project directory
+        let paths = git(
+            current_dir,
+            &[
+                "ls-tree",
+                "-r",
+                "--name-only",
+                revision,
+                "--",
+                content_type.directory(),
+            ],
+        )?;
+        for path in paths.lines().filter(|path| path.ends_with(".ex.json")) {
+            let content = git(current_dir, &["show", &format!("{}:./{}", revision, path)])?;
+            let reference = reference_store::parse_reference(
+                &format!("{}:{}", revision, path),
+                &content,
+                content_type,
+            )?;
+            files.push(PackFile::from(&reference));
+        }
+    }
+    Ok(files)
+}
+
+fn git(current_dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(current_dir)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).context("git printed invalid UTF-8")
+}
+
+/// Renders the changelog as Markdown release notes
+pub fn render_markdown(changelog: &Changelog) -> String {
+    let mut markdown = format!(
+        "# Changelog\n\nChanges from `{}` to `{}`.\n",
+        changelog.from, changelog.to
+    );
+    if changelog.is_empty() {
+        markdown.push_str("\nNo mods were changed.\n");
+        return markdown;
+    }
+
+    let sections = [
+        ("Added", &changelog.added),
+        ("Updated", &changelog.updated),
+        ("Downgraded", &changelog.downgraded),
+        ("Removed", &changelog.removed),
+    ];
+    for (title, changes) in sections {
+        if changes.is_empty() {
+            continue;
+        }
+        markdown.push_str(&format!("\n## {}\n\n", title));
+        for change in changes {
+            let line = match (&change.old_file, &change.new_file) {
+                (Some(old), Some(new)) => format!(
+                    "- **{}**: {} → {}\n",
+                    change.name, old.display_name, new.display_name
+                ),
+                (None, Some(file)) | (Some(file), None) => {
+                    format!("- **{}** ({})\n", change.name, file.display_name)
+                }
+                (None, None) => format!("- **{}**\n", change.name),
+            };
+            markdown.push_str(&line);
+            if let Some(upstream) = &change.upstream_changelog {
+                markdown.push_str("  <details><summary>Changelog</summary>\n\n");
+                for line in upstream.trim().lines() {
+                    markdown.push_str(&format!("  {}\n", line));
+                }
+                markdown.push_str("  </details>\n");
+            }
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curseforge_file_id() {
+        assert_eq!(
+            curseforge_file_id(
+                "https://edge.forgecdn.net/files/6332/315/oritech-fabric-0.14.5.jar"
+            ),
+            Some(6332315)
+        );
+        assert_eq!(
+            curseforge_file_id("https://edge.forgecdn.net/files/5971/9/owo-lib.jar"),
+            Some(5971009)
+        );
+        assert_eq!(
+            curseforge_file_id("https://cdn.modrinth.com/data/AANobbMI/versions/1/sodium.jar"),
+            None
+        );
+    }
+}
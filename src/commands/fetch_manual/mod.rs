@@ -0,0 +1,199 @@
+//! Picks up files that have to be downloaded by hand and puts them into the cache.
+//!
+//! Downloaded files are recognized by their Curseforge fingerprint, so it doesn't matter
+//! if the browser renamed them (e.g. `mod (1).jar`).
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::api::curseforge::fingerprint::fingerprint;
+use crate::commands::build::{self, manual::ManualFile};
+use crate::models::config::ModEntry;
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// How often a watched directory is scanned by default
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Options for the fetch-manual command
+#[derive(Debug, Clone)]
+pub struct FetchManualOptions {
+    /// Directory to look for downloaded files in, e.g. `~/Downloads`
+    pub dir: Option<PathBuf>,
+    /// Keep scanning `dir` until every file was found
+    pub watch: bool,
+    pub interval: Duration,
+}
+
+impl Default for FetchManualOptions {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            watch: false,
+            interval: DEFAULT_WATCH_INTERVAL,
+        }
+    }
+}
+
+/// The result of fetching files into the cache
+#[derive(Debug, Clone, Default)]
+pub struct FetchManualResult {
+    /// Files copied into the cache, by their path in the cache
+    pub fetched: Vec<PathBuf>,
+    /// Files that still have to be downloaded
+    pub missing: Vec<ManualFile>,
+}
+
+pub async fn run<E: utils::Env>(env: &E, options: FetchManualOptions) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let result = fetch_manual(env, &options).await?;
+
+    let reporter = env.reporter();
+    if result.missing.is_empty() {
+        reporter.message("✅ All files to download by hand are in the cache");
+    } else {
+        reporter.message(&format!(
+            "📥 {} files still have to be downloaded by hand:",
+            result.missing.len()
+        ));
+        for file in &result.missing {
+            reporter.message(&format!(
+                "  {} ({}): {}",
+                file.name, file.file_name, file.page_url
+            ));
+        }
+        if options.dir.is_none() {
+            reporter.message("Download them and run 'minepack fetch-manual --watch <DIR>'");
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies the files to download by hand from `options.dir` into the cache. With
+/// `options.watch`, the directory is scanned until every file was found.
+pub async fn fetch_manual<E: utils::Env>(
+    env: &E,
+    options: &FetchManualOptions,
+) -> Result<FetchManualResult> {
+    let mod_entries = build::load_profile_entries(env, None)?;
+    let mut pending: Vec<(&ModEntry, PathBuf)> = Vec::new();
+    for mod_entry in &mod_entries {
+        if mod_entry.manual_download.is_none() {
+            continue;
+        }
+        let cache_path = utils::get_minepack_cache_content_dir(env, mod_entry.content_type)?
+            .join(&mod_entry.version);
+        if !cache_path.exists() {
+            pending.push((mod_entry, cache_path));
+        }
+    }
+
+    let mut result = FetchManualResult::default();
+    let Some(dir) = &options.dir else {
+        result.missing = pending
+            .iter()
+            .map(|(entry, _)| ManualFile::from_entry(entry))
+            .collect();
+        return Ok(result);
+    };
+    let dir = env.current_dir()?.join(dir);
+    if !dir.is_dir() {
+        return Err(anyhow!(MinepackError::FileNotFound(
+            dir.display().to_string()
+        )));
+    }
+
+    if options.watch && !pending.is_empty() {
+        env.reporter().message(&format!(
+            "👀 Watching {} for {} files, download them from:",
+            dir.display(),
+            pending.len()
+        ));
+        for (entry, _) in &pending {
+            let file = ManualFile::from_entry(entry);
+            env.reporter().message(&format!(
+                "  {} ({}): {}",
+                file.name, file.file_name, file.page_url
+            ));
+        }
+    }
+
+    // Files that didn't match, skipped on the next scan unless they changed
+    let mut checked: HashMap<PathBuf, (u64, Option<SystemTime>)> = HashMap::new();
+    loop {
+        for path in scan_dir(&dir, &mut checked)? {
+            let content = fs::read(&path)
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            let file_fingerprint = fingerprint(&content) as u64;
+            let Some(index) = pending.iter().position(|(entry, _)| {
+                entry
+                    .manual_download
+                    .as_ref()
+                    .is_some_and(|manual_download| manual_download.fingerprint == file_fingerprint)
+            }) else {
+                continue;
+            };
+
+            let (entry, cache_path) = pending.remove(index);
+            if let Some(parent) = cache_path.parent() {
+                utils::ensure_dir_exists(parent)?;
+            }
+            fs::write(&cache_path, &content)
+                .with_context(|| format!("Failed to write file: {}", cache_path.display()))?;
+            env.reporter().message(&format!(
+                "✓ Found {} as {}",
+                entry.name,
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            result.fetched.push(cache_path);
+        }
+
+        if !options.watch || pending.is_empty() {
+            break;
+        }
+        tokio::time::sleep(options.interval).await;
+    }
+
+    result.missing = pending
+        .iter()
+        .map(|(entry, _)| ManualFile::from_entry(entry))
+        .collect();
+    Ok(result)
+}
+
+/// The jars and zips in `dir` that weren't checked yet or changed since. Browsers download
+/// into temporary files with other extensions, so partial downloads are skipped.
+fn scan_dir(
+    dir: &Path,
+    checked: &mut HashMap<PathBuf, (u64, Option<SystemTime>)>,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        let is_archive = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jar") || ext.eq_ignore_ascii_case("zip"));
+        let metadata = entry.metadata()?;
+        if !is_archive || !metadata.is_file() || metadata.len() == 0 {
+            continue;
+        }
+
+        let state = (metadata.len(), metadata.modified().ok());
+        if checked.insert(path.clone(), state) != Some(state) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
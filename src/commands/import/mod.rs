@@ -1,14 +1,17 @@
 use anyhow::{anyhow, Context, Result};
-use dialoguer::Confirm;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::fs::{self, File};
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use tempfile::tempdir;
-use zip::ZipArchive;
 
-use crate::api::curseforge::{schema::Manifest, CurseforgeClient};
-use crate::models::config::{self, Minecraft, ModLoader, ModpackConfig, Side};
+use crate::api::curseforge::{self, schema::Manifest, CurseforgeClient};
+use crate::commands::build::overrides;
+use crate::models::config::{self, ContentType, Minecraft, ModLoader, ModpackConfig, Side};
+use crate::models::migration::CURRENT_SCHEMA_VERSION;
+use crate::ui::Progress;
+use crate::utils::archive::{self, ExtractLimits};
 use crate::utils::errors::MinepackError;
+use crate::utils::reference_store::{self, ReferenceStore};
 use crate::utils::{self, determine_mod_side_cf};
 
 /// Extracted mod information from modlist.html or manifest.json
@@ -20,16 +23,43 @@ struct ModData {
     file_name: Option<String>,
     download_url: Option<String>,
     side: Side,
+    content_type: ContentType,
+    /// Files the manifest marks as not required
+    optional: bool,
+    manual_download: Option<config::ManualDownload>,
+}
+
+/// The result of importing a modpack
+#[derive(Debug)]
+pub struct ImportResult {
+    pub config: ModpackConfig,
+    /// Reference files created for the mods in the manifest
+    pub references: Vec<PathBuf>,
+    /// Pinned references of the previous modpack that were kept at their file
+    pub kept_pinned: Vec<config::Reference>,
+    /// Files of the manifest no reference was created for
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// A file of the manifest that couldn't be imported
+#[derive(Debug, Clone)]
+pub struct SkippedFile {
+    pub project_id: u32,
+    pub file_id: u32,
+    /// Why no reference was created, e.g. because the project was deleted
+    pub reason: String,
 }
 
 /// Import a modpack from a CurseForge zip file
 pub async fn run<E: utils::Env>(env: &E, modpack_path: String, yes: bool) -> Result<()> {
     // Check if we're in a modpack directory and confirm overwrite if exists
     if utils::modpack_exists(env) && !yes {
-        let confirm = Confirm::new()
-            .with_prompt("A modpack already exists in this directory. Do you want to overwrite it?")
-            .default(false)
-            .interact()
+        let confirm = env
+            .prompter()
+            .confirm(
+                "A modpack already exists in this directory. Do you want to overwrite it?",
+                false,
+            )
             .context("Failed to confirm overwrite")?;
 
         if !confirm {
@@ -37,12 +67,79 @@ pub async fn run<E: utils::Env>(env: &E, modpack_path: String, yes: bool) -> Res
         }
     }
 
-    println!("📦 Importing modpack from {}", modpack_path);
+    let result = import(env, Path::new(&modpack_path)).await?;
+    let reporter = env.reporter();
+    let config = &result.config;
+
+    if !result.kept_pinned.is_empty() {
+        reporter.message(&format!(
+            "📌 Kept {} pinned mods at their current file:",
+            result.kept_pinned.len()
+        ));
+        for reference in &result.kept_pinned {
+            reporter.message(&format!(
+                "  - {} ({}){}",
+                reference.name,
+                reference.filename,
+                reference
+                    .pin_reason
+                    .as_ref()
+                    .map(|reason| format!(": {}", reason))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+
+    if !result.skipped.is_empty() {
+        reporter.warning(&format!(
+            "⚠️ {} files of the manifest could not be imported:",
+            result.skipped.len()
+        ));
+        for file in &result.skipped {
+            reporter.warning(&format!(
+                "  - project {} (file {}): {}",
+                file.project_id, file.file_id, file.reason
+            ));
+        }
+        reporter.message("   Add replacements with 'minepack add'.");
+    }
+
+    reporter.message(&format!(
+        "✅ Modpack imported: {} references created, {} pinned kept, {} skipped",
+        result.references.len(),
+        result.kept_pinned.len(),
+        result.skipped.len()
+    ));
+    reporter.message(&format!("Name: {}", config.name));
+    reporter.message(&format!("Version: {}", config.version));
+    reporter.message(&format!("Minecraft Version: {}", config.minecraft.version));
+    reporter.message(&format!(
+        "Mod Loader: {}-{}",
+        config.minecraft.mod_loaders[0].id, config.minecraft.mod_loaders[0].version
+    ));
+
+    // Note to user about mod references
+    reporter.message("\n⚠️  Note: Mod files have not been downloaded yet.");
+    reporter.message("   References to the mods have been created, but the actual jar files");
+    reporter.message("   will be downloaded on demand when you build or use the modpack.");
+
+    Ok(())
+}
+
+/// Imports a CurseForge modpack zip into the current directory, replacing an existing modpack.
+/// Pinned references of the existing modpack are kept.
+pub async fn import<E: utils::Env>(env: &E, modpack_file_path: &Path) -> Result<ImportResult> {
+    let reporter = env.reporter();
+    reporter.message(&format!(
+        "📦 Importing modpack from {}",
+        modpack_file_path.display()
+    ));
 
     // Validate the file exists
-    let modpack_file_path = Path::new(&modpack_path);
     if !modpack_file_path.exists() {
-        return Err(anyhow!(MinepackError::FileNotFound(modpack_path)));
+        return Err(anyhow!(MinepackError::FileNotFound(
+            modpack_file_path.display().to_string()
+        )));
     }
 
     // Check if it's a zip file
@@ -53,13 +150,18 @@ pub async fn run<E: utils::Env>(env: &E, modpack_path: String, yes: bool) -> Res
     }
 
     // Initialize CurseForge client for API operations (only used when absolutely necessary)
-    let client = CurseforgeClient::new().context("Failed to initialize CurseForge API client")?;
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize CurseForge API client")?;
 
     // Create a temporary directory to extract the modpack
     let temp_dir = tempdir().context("Failed to create temporary directory")?;
-    println!("Extracting modpack to temporary directory...");
-    extract_zip(modpack_file_path, temp_dir.path())
-        .context("Failed to extract modpack zip file")?;
+    reporter.message("Extracting modpack to temporary directory...");
+    archive::extract_zip(
+        modpack_file_path,
+        temp_dir.path(),
+        &ExtractLimits::default(),
+    )
+    .context("Failed to extract modpack zip file")?;
 
     // Check if manifest.json exists in the extracted files
     let manifest_path = temp_dir.path().join("manifest.json");
@@ -111,6 +213,14 @@ pub async fn run<E: utils::Env>(env: &E, modpack_path: String, yes: bool) -> Res
         ),
     );
 
+    // Pinned references of an existing modpack are kept at their current file
+    let mut store = if utils::modpack_exists(env) {
+        ReferenceStore::load(env)?
+    } else {
+        ReferenceStore::default()
+    };
+    let mut kept_pinned = Vec::new();
+
     // Create modpack structure
     utils::create_modpack_structure(env)?;
 
@@ -118,159 +228,181 @@ pub async fn run<E: utils::Env>(env: &E, modpack_path: String, yes: bool) -> Res
     utils::save_config(env, &config)?;
 
     // Process mods from manifest
-    println!("Processing mods...");
-    let pb = ProgressBar::new(manifest.files.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .context("Failed to create progress bar style")?
-            .progress_chars("#>-"),
-    );
-
-    // Ensure the mods directory exists
-    let mods_dir = utils::get_mods_dir(env)?;
-    utils::ensure_dir_exists(&mods_dir)?;
-
-    // Process each mod in the manifest
-    let mod_ids: Vec<u32> = manifest.files.iter().map(|file| file.project_id).collect();
-    let file_ids: Vec<u32> = manifest.files.iter().map(|file| file.file_id).collect();
-    let mod_infos = client.get_mod_infos(mod_ids).await?;
-    let file_infos = client.get_file_infos(file_ids).await?;
-    for (index, file_entry) in manifest.files.iter().enumerate() {
+    reporter.message("Processing mods...");
+    let pb = reporter.progress(manifest.files.len() as u64);
+    let mut references = Vec::new();
+
+    // The API leaves out unavailable projects and doesn't keep the order, so the results
+    // are matched by ID
+    let mut mod_ids: Vec<u32> = manifest.files.iter().map(|file| file.project_id).collect();
+    let mut file_ids: Vec<u32> = manifest.files.iter().map(|file| file.file_id).collect();
+    for ids in [&mut mod_ids, &mut file_ids] {
+        ids.sort_unstable();
+        ids.dedup();
+    }
+    let mod_infos: HashMap<u32, curseforge::schema::Mod> = client
+        .get_mod_infos(mod_ids)
+        .await?
+        .into_iter()
+        .map(|info| (info.id, info))
+        .collect();
+    let file_infos: HashMap<u32, curseforge::schema::File> = client
+        .get_file_infos(file_ids)
+        .await?
+        .into_iter()
+        .map(|info| (info.id, info))
+        .collect();
+    let mut skipped = Vec::new();
+    for file_entry in &manifest.files {
         pb.set_message(format!(
             "Creating reference for mod: {}",
             file_entry.project_id
         ));
 
-        // Get mod info from modlist if available, or try a minimal API call
-        let mod_info = mod_infos.get(index).with_context(|| {
-            format!(
-                "Failed to get mod info for project ID: {}",
-                file_entry.project_id
-            )
-        })?;
+        // Skip mods that are pinned in the existing modpack
+        if let Some(stored) = store
+            .get_by_project_id(file_entry.project_id)
+            .filter(|stored| stored.reference.pinned)
+        {
+            kept_pinned.push(stored.reference.clone());
+            pb.inc(1);
+            continue;
+        }
+
+        let mut skip = |reason: String| {
+            pb.println(&format!(
+                "⚠ Skipped project {} (file {}): {}",
+                file_entry.project_id, file_entry.file_id, reason
+            ));
+            skipped.push(SkippedFile {
+                project_id: file_entry.project_id,
+                file_id: file_entry.file_id,
+                reason,
+            });
+            pb.inc(1);
+        };
+        let (mod_info, file_info) = match (
+            mod_infos.get(&file_entry.project_id),
+            file_infos.get(&file_entry.file_id),
+        ) {
+            (None, _) => {
+                skip("the project is unavailable or was deleted".to_string());
+                continue;
+            }
+            (Some(mod_info), _) if !mod_info.is_available => {
+                skip(format!("{} is no longer available", mod_info.name));
+                continue;
+            }
+            (Some(mod_info), None) => {
+                skip(format!("the file of {} was deleted", mod_info.name));
+                continue;
+            }
+            (Some(mod_info), Some(file_info)) if file_info.mod_id != mod_info.id => {
+                skip(format!(
+                    "the file belongs to project {}, not {}",
+                    file_info.mod_id, mod_info.name
+                ));
+                continue;
+            }
+            (Some(mod_info), Some(file_info)) => (mod_info, file_info),
+        };
 
         // Create mod data with available information
-        let file_info = &file_infos[index];
+        let content_type = mod_info
+            .class_id
+            .and_then(ContentType::from_class_id)
+            .unwrap_or_default();
+        let side = match determine_mod_side_cf(&mod_info.name, file_info, content_type) {
+            Ok(side) => side,
+            Err(err) => {
+                skip(format!("{:#}", err));
+                continue;
+            }
+        };
         let mod_data = ModData {
             project_id: file_entry.project_id,
             file_id: file_entry.file_id,
             name: mod_info.name.clone(),
-            slug: mod_info.slug.clone(),
-            side: determine_mod_side_cf(&mod_info.name, file_info)?,
-            file_name: Some(file_info.file_name.clone()),
+            slug: reference_store::project_slug(&mod_info.slug, &mod_info.name),
+            side,
+            file_name: reference_store::sanitize_file_name(&file_info.file_name),
             download_url: file_info.download_url.clone(),
+            content_type,
+            optional: !file_entry.required,
+            manual_download: curseforge::manual_download(mod_info, file_info),
         };
 
-        // Create JSON reference in mods directory
-        create_mod_reference(&mod_data, &mods_dir, &pb)?;
+        // Create JSON reference in the directory for its content type
+        match create_mod_reference(env, &mut store, &mod_data, pb.as_ref()) {
+            Ok(path) => references.push(path),
+            Err(err) => {
+                skip(format!("{:#}", err));
+                continue;
+            }
+        }
 
         pb.inc(1);
     }
-    pb.finish_with_message("All mod references created successfully");
+    pb.finish(format!(
+        "Created {} references, skipped {}",
+        references.len(),
+        skipped.len()
+    ));
 
     // Copy overrides content if it exists
     let overrides_dir = temp_dir.path().join("overrides");
     if overrides_dir.exists() && overrides_dir.is_dir() {
-        println!("Copying overrides...");
+        reporter.message("Copying overrides...");
         copy_overrides(&overrides_dir, env)?;
     }
 
-    println!("✅ Modpack imported successfully!");
-    println!("Name: {}", config.name);
-    println!("Version: {}", config.version);
-    println!("Minecraft Version: {}", config.minecraft.version);
-    println!(
-        "Mod Loader: {}-{}",
-        config.minecraft.mod_loaders[0].id, config.minecraft.mod_loaders[0].version
-    );
-
-    // Note to user about mod references
-    println!("\n⚠️  Note: Mod files have not been downloaded yet.");
-    println!("   References to the mods have been created, but the actual jar files");
-    println!("   will be downloaded on demand when you build or use the modpack.");
-
-    Ok(())
-}
-
-/// Extracts the zip file to the destination directory
-fn extract_zip(source: &Path, destination: &Path) -> Result<()> {
-    // Open the zip file
-    let file = File::open(source).context("Failed to open modpack file")?;
-    let mut archive = ZipArchive::new(file).context("Failed to read modpack zip file")?;
-
-    // Extract the zip file to the temporary directory
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i).context("Failed to access zip entry")?;
-        let outpath = destination.join(file.name());
-
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath).with_context(|| {
-                format!(
-                    "Failed to create directory for extraction: {}",
-                    outpath.display()
-                )
-            })?;
-        } else {
-            if let Some(p) = outpath.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(p).with_context(|| {
-                        format!(
-                            "Failed to create parent directory for extraction: {}",
-                            p.display()
-                        )
-                    })?;
-                }
-            }
-            let mut outfile = File::create(&outpath).with_context(|| {
-                format!(
-                    "Failed to create file for extraction: {}",
-                    outpath.display()
-                )
-            })?;
-            std::io::copy(&mut file, &mut outfile).context("Failed to extract file content")?;
-        }
-    }
-
-    Ok(())
+    Ok(ImportResult {
+        config,
+        references,
+        kept_pinned,
+        skipped,
+    })
 }
 
 /// Creates a mod reference JSON file without downloading the actual mod
-fn create_mod_reference(mod_data: &ModData, mods_dir: &Path, pb: &ProgressBar) -> Result<()> {
+fn create_mod_reference<E: utils::Env>(
+    env: &E,
+    store: &mut ReferenceStore,
+    mod_data: &ModData,
+    pb: &dyn Progress,
+) -> Result<PathBuf> {
     // Generate a filename if not available
     let filename = match &mod_data.file_name {
         Some(name) => name.clone(),
         None => format!("{}-{}.jar", mod_data.slug, mod_data.file_id),
     };
 
-    // Create the JSON reference file in the mods directory
-    let json_file_path = mods_dir.join(format!("{}.ex.json", mod_data.slug));
-
-    let json_data = config::Reference {
+    let reference = config::Reference {
+        schema_version: CURRENT_SCHEMA_VERSION,
         name: mod_data.name.clone(),
         filename,
-        side: mod_data.side.clone(),
+        side: mod_data.side,
         link: config::Link::CurseForge {
             project_id: mod_data.project_id,
             file_id: mod_data.file_id,
             download_url: mod_data.download_url.clone(),
         },
+        content_type: mod_data.content_type,
+        pinned: false,
+        pin_reason: None,
+        optional: mod_data.optional,
+        description: None,
+        tags: Vec::new(),
+        manual_download: mod_data.manual_download.clone(),
     };
-    let json_content =
-        serde_json::to_string_pretty(&json_data).context("Failed to serialize mod JSON data")?;
-    fs::write(&json_file_path, json_content).with_context(|| {
-        format!(
-            "Failed to write JSON reference file: {}",
-            json_file_path.display()
-        )
-    })?;
-
-    pb.println(format!(
+    let path = store.save(env, &mod_data.slug, reference)?.path.clone();
+
+    pb.println(&format!(
         "✓ Created reference for mod: {} ({})",
         mod_data.name, mod_data.project_id
     ));
 
-    Ok(())
+    Ok(path)
 }
 
 /// Copy the overrides content to the appropriate locations in the modpack
@@ -278,7 +410,8 @@ fn copy_overrides(overrides_dir: &Path, env: &impl utils::Env) -> Result<()> {
     let current_dir = env.current_dir()?;
 
     // Walk through the overrides directory
-    for entry in walkdir::WalkDir::new(overrides_dir) {
+    let mut entries = walkdir::WalkDir::new(overrides_dir).into_iter();
+    while let Some(entry) = entries.next() {
         let entry = entry.context("Failed to read override entry")?;
         let path = entry.path();
 
@@ -291,6 +424,16 @@ fn copy_overrides(overrides_dir: &Path, env: &impl utils::Env) -> Result<()> {
         let relative_path = path
             .strip_prefix(overrides_dir)
             .context("Failed to strip prefix from override path")?;
+        if is_protected_override(relative_path) {
+            env.reporter().warning(&format!(
+                "⚠️ Skipped override that would replace project files: {}",
+                relative_path.display()
+            ));
+            if entry.file_type().is_dir() {
+                entries.skip_current_dir();
+            }
+            continue;
+        }
         let target_path = current_dir.join(relative_path);
 
         // Create the directory or copy the file
@@ -315,3 +458,37 @@ fn copy_overrides(overrides_dir: &Path, env: &impl utils::Env) -> Result<()> {
 
     Ok(())
 }
+
+/// Top-level entries of the project an imported pack's overrides must not replace
+const PROTECTED_PATHS: [&str; 10] = [
+    ".git",
+    ".minepack",
+    "build",
+    "target",
+    "minepack.json",
+    "minepack.toml",
+    overrides::IGNORE_FILENAME,
+    ".github",
+    ".gitlab-ci.yml",
+    ".gitignore",
+];
+
+/// Whether an override would replace the project's own files: its configuration, git and CI
+/// setup, build output, or the references in a content directory
+fn is_protected_override(relative_path: &Path) -> bool {
+    let Some(first) = relative_path.components().next() else {
+        return false;
+    };
+    let first = first.as_os_str().to_string_lossy();
+    if PROTECTED_PATHS.contains(&first.as_ref()) {
+        return true;
+    }
+
+    let is_reference = relative_path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(".ex.json"));
+    is_reference
+        && ContentType::ALL
+            .iter()
+            .any(|content_type| first == content_type.directory())
+}
@@ -1,5 +1,4 @@
 use anyhow::{anyhow, Context, Result};
-use dialoguer::{Input, Select};
 
 use crate::models::config::{Minecraft, ModLoader, ModpackConfig};
 use crate::utils;
@@ -20,7 +19,9 @@ pub async fn run<E: utils::Env>(
         return Err(anyhow!(MinepackError::ModpackAlreadyExists));
     }
 
-    println!("📦 Creating a new Minecraft modpack...");
+    env.reporter()
+        .message("📦 Creating a new Minecraft modpack...");
+    let prompter = env.prompter();
 
     // If all options are provided, skip interactive mode
     let _non_interactive = name_opt.is_some() && author_opt.is_some();
@@ -29,28 +30,27 @@ pub async fn run<E: utils::Env>(
     let name = if let Some(name) = name_opt {
         name
     } else {
-        Input::new()
-            .with_prompt("Modpack name")
-            .interact_text()
+        prompter
+            .input("Modpack name", None)
             .context("Failed to get modpack name")?
     };
 
     let version = if let Some(version) = version_opt {
         version
     } else {
-        Input::new()
-            .with_prompt("Modpack version")
-            .default("1.0.0".to_string())
-            .interact_text()
+        prompter
+            .input("Modpack version", Some("1.0.0"))
             .context("Failed to get modpack version")?
     };
 
+    // Defaults for the prompts from the user configuration
+    let defaults = utils::load_user_config(env)?.defaults;
+
     let author = if let Some(author) = author_opt {
         author
     } else {
-        Input::new()
-            .with_prompt("Author")
-            .interact_text()
+        prompter
+            .input("Author", defaults.author.as_deref())
             .context("Failed to get author name")?
     };
 
@@ -61,10 +61,8 @@ pub async fn run<E: utils::Env>(
             Some(desc)
         }
     } else {
-        let desc: String = Input::new()
-            .with_prompt("Description (optional)")
-            .allow_empty(true)
-            .interact_text()
+        let desc = prompter
+            .input("Description (optional)", Some(""))
             .context("Failed to get description")?;
 
         if desc.is_empty() {
@@ -84,12 +82,17 @@ pub async fn run<E: utils::Env>(
             _ => return Err(anyhow!(MinepackError::InvalidModLoader)),
         }
     } else {
-        let mod_loader_options = &["Forge", "Fabric", "Quilt", "NeoForge"];
-        let mod_loader_index = Select::new()
-            .with_prompt("Select mod loader")
-            .items(mod_loader_options)
-            .default(0)
-            .interact()
+        let mod_loader_options = ["Forge", "Fabric", "Quilt", "NeoForge"].map(String::from);
+        let default_index = defaults
+            .loader
+            .and_then(|loader| {
+                mod_loader_options
+                    .iter()
+                    .position(|option| option.eq_ignore_ascii_case(&loader))
+            })
+            .unwrap_or(0);
+        let mod_loader_index = prompter
+            .select("Select mod loader", &mod_loader_options, default_index)
             .context("Failed to select mod loader")?;
 
         match mod_loader_index {
@@ -105,9 +108,8 @@ pub async fn run<E: utils::Env>(
     let mod_loader_version = if let Some(version) = loader_version_opt {
         version
     } else {
-        Input::new()
-            .with_prompt("Mod loader version")
-            .interact_text()
+        prompter
+            .input("Mod loader version", None)
             .context("Failed to get mod loader version")?
     };
 
@@ -122,10 +124,8 @@ pub async fn run<E: utils::Env>(
     let minecraft_version = if let Some(version) = minecraft_version_opt {
         version
     } else {
-        Input::new()
-            .with_prompt("Minecraft version")
-            .default("1.20.1".to_string())
-            .interact_text()
+        prompter
+            .input("Minecraft version", Some("1.20.1"))
             .context("Failed to get Minecraft version")?
     };
 
@@ -141,8 +141,10 @@ pub async fn run<E: utils::Env>(
     // Save the configuration file
     utils::save_config(env, &config)?;
 
-    println!("✅ Modpack initialized successfully!");
-    println!("Run 'minepack add <mod>' to add mods to your modpack.");
+    env.reporter()
+        .message("✅ Modpack initialized successfully!");
+    env.reporter()
+        .message("Run 'minepack add <mod>' to add mods to your modpack.");
 
     Ok(())
 }
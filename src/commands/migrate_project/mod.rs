@@ -0,0 +1,221 @@
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use similar::TextDiff;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::models::config::{ContentType, ModpackConfig, Reference};
+use crate::models::migration::{self, CURRENT_SCHEMA_VERSION};
+use crate::ui::Reporter;
+use crate::utils::errors::MinepackError;
+use crate::utils::reference_store;
+use crate::utils::{self, ConfigFormat};
+
+/// Options for the migrate-project command
+#[derive(Debug, Clone, Default)]
+pub struct MigrateOptions {
+    /// Only print the changes that would be made
+    pub dry_run: bool,
+    /// Format to store the modpack configuration in (json, toml)
+    pub format: Option<String>,
+}
+
+/// A project file that needs to be rewritten
+struct PendingChange {
+    path: PathBuf,
+    /// Where the file is written to, differs from `path` when the config format changes
+    new_path: PathBuf,
+    from_version: u32,
+    old_content: String,
+    new_content: String,
+}
+
+/// Upgrade the project files to the current schema version
+pub async fn run<E: utils::Env>(env: &E, options: MigrateOptions) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let target_format = options
+        .format
+        .as_deref()
+        .map(|format| {
+            ConfigFormat::from_str(&format.to_lowercase()).map_err(|_| {
+                anyhow!(MinepackError::InvalidFileFormat(format!(
+                    "unknown config format '{}', expected 'json' or 'toml'",
+                    format
+                )))
+            })
+        })
+        .transpose()?;
+
+    let mut changes = Vec::new();
+    if let Some(change) = migrate_config_file(env, target_format)? {
+        changes.push(change);
+    }
+    for content_type in ContentType::ALL {
+        let content_dir = utils::get_content_dir(env, content_type)?;
+        if content_dir.exists() {
+            migrate_reference_files(&content_dir, content_type, &mut changes)?;
+        }
+    }
+
+    if changes.is_empty() {
+        env.reporter().message(&format!(
+            "✅ The project is already at schema version {}",
+            CURRENT_SCHEMA_VERSION
+        ));
+        return Ok(());
+    }
+
+    let current_dir = env.current_dir()?;
+    for change in &changes {
+        let old_name = display_path(&current_dir, &change.path);
+        let new_name = display_path(&current_dir, &change.new_path);
+
+        if options.dry_run {
+            print_diff(
+                env.reporter(),
+                &change.old_content,
+                &change.new_content,
+                &old_name,
+                &new_name,
+            );
+            continue;
+        }
+
+        fs::write(&change.new_path, &change.new_content)
+            .with_context(|| format!("Failed to write file: {}", change.new_path.display()))?;
+        if change.new_path != change.path {
+            fs::remove_file(&change.path)
+                .with_context(|| format!("Failed to remove file: {}", change.path.display()))?;
+        }
+        env.reporter().message(&format!(
+            "✓ Migrated {} (schema version {} → {})",
+            new_name, change.from_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    if options.dry_run {
+        env.reporter().message(&format!("\n{} files would be changed. Run 'minepack migrate-project' without --dry-run to apply the changes.",
+            changes.len()));
+    } else {
+        env.reporter().message(&format!(
+            "✅ Migrated {} files to schema version {}",
+            changes.len(),
+            CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    Ok(())
+}
+
+/// Migrates the modpack configuration, converting it to `target_format` if given
+fn migrate_config_file<E: utils::Env>(
+    env: &E,
+    target_format: Option<ConfigFormat>,
+) -> Result<Option<PendingChange>> {
+    let path = utils::get_minepack_config_path(env)?;
+    let format = ConfigFormat::from_path(&path);
+    let target_format = target_format.unwrap_or(format);
+
+    let old_content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    let mut value = format
+        .parse(&old_content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    let from_version = migration::migrate_config(&mut value)
+        .with_context(|| format!("Failed to migrate config file: {}", path.display()))?;
+
+    if from_version == CURRENT_SCHEMA_VERSION && target_format == format {
+        return Ok(None);
+    }
+
+    let config: ModpackConfig = serde_json::from_value(value)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    let new_content = target_format
+        .serialize(&config)
+        .context("Failed to serialize modpack configuration")?;
+
+    Ok(Some(PendingChange {
+        new_path: path.with_file_name(target_format.filename()),
+        path,
+        from_version,
+        old_content,
+        new_content,
+    }))
+}
+
+/// Migrates all reference files in a content directory and its subdirectories
+fn migrate_reference_files(
+    content_dir: &Path,
+    content_type: ContentType,
+    changes: &mut Vec<PendingChange>,
+) -> Result<()> {
+    for path in reference_store::reference_files(content_dir)? {
+        let old_content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read reference file: {}", path.display()))?;
+        let mut value: serde_json::Value = serde_json::from_str(&old_content)
+            .with_context(|| format!("Failed to parse reference file: {}", path.display()))?;
+        let from_version = migration::migrate_reference(&mut value, content_type)
+            .with_context(|| format!("Failed to migrate reference file: {}", path.display()))?;
+        if from_version == CURRENT_SCHEMA_VERSION {
+            continue;
+        }
+
+        let reference: Reference = serde_json::from_value(value)
+            .with_context(|| format!("Failed to parse reference file: {}", path.display()))?;
+        let new_content = serde_json::to_string_pretty(&reference)
+            .context("Failed to serialize mod JSON data")?;
+
+        changes.push(PendingChange {
+            new_path: path.clone(),
+            path,
+            from_version,
+            old_content,
+            new_content,
+        });
+    }
+
+    Ok(())
+}
+
+/// Prints a colored unified diff of two versions of a file
+pub(crate) fn print_diff(
+    reporter: &dyn Reporter,
+    old_content: &str,
+    new_content: &str,
+    old_name: &str,
+    new_name: &str,
+) {
+    let diff = TextDiff::from_lines(old_content, new_content);
+    let unified = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(old_name, new_name)
+        .to_string();
+
+    for line in unified.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            reporter.message(&style(line).bold().to_string());
+        } else if line.starts_with('+') {
+            reporter.message(&style(line).green().to_string());
+        } else if line.starts_with('-') {
+            reporter.message(&style(line).red().to_string());
+        } else if line.starts_with("@@") {
+            reporter.message(&style(line).cyan().to_string());
+        } else {
+            reporter.message(line);
+        }
+    }
+}
+
+/// Returns the path relative to the project directory for display
+fn display_path(current_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(current_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -1,5 +1,15 @@
 pub mod add;
+pub mod auth;
 pub mod build;
+pub mod changelog;
+pub mod fetch_manual;
 pub mod import;
 pub mod init;
+pub mod migrate_project;
+pub mod pin;
+pub mod pull;
+pub mod release;
+pub mod remove;
 pub mod search;
+pub mod sync;
+pub mod unpin;
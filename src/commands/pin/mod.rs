@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Result};
+use console::style;
+
+use crate::utils;
+use crate::utils::errors::MinepackError;
+use crate::utils::reference_store::ReferenceStore;
+
+/// Pin a mod at its current file, or list the pinned mods if no mod is given
+pub fn run<E: utils::Env>(
+    env: &E,
+    mod_query: Option<String>,
+    reason: Option<String>,
+) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let Some(query) = mod_query else {
+        return list_pinned(env);
+    };
+
+    let mut store = ReferenceStore::load(env)?;
+    let stored = store.find(&query)?;
+    let (path, mut reference) = (stored.path.clone(), stored.reference.clone());
+    let was_pinned = reference.pinned;
+    reference.pinned = true;
+    if reason.is_some() {
+        reference.pin_reason = reason;
+    }
+    store.update(&path, reference.clone())?;
+
+    env.reporter().message(&format!(
+        "📌 {} {} at {} (file ID: {})",
+        if was_pinned {
+            "Updated pin of"
+        } else {
+            "Pinned"
+        },
+        reference.name,
+        reference.filename,
+        reference.file_id()
+    ));
+    if let Some(reason) = &reference.pin_reason {
+        env.reporter().message(&format!("   Reason: {}", reason));
+    }
+
+    Ok(())
+}
+
+fn list_pinned<E: utils::Env>(env: &E) -> Result<()> {
+    let pinned: Vec<_> = utils::read_references(env)?
+        .into_iter()
+        .filter(|(_, reference)| reference.pinned)
+        .collect();
+
+    if pinned.is_empty() {
+        env.reporter().message("No pinned mods.");
+        return Ok(());
+    }
+
+    env.reporter()
+        .message(&format!("{} pinned mods:", pinned.len()));
+    for (_, reference) in pinned {
+        env.reporter().message(&format!(
+            "  📌 {} ({}){}",
+            style(&reference.name).bold(),
+            reference.filename,
+            reference
+                .pin_reason
+                .as_ref()
+                .map(|reason| format!(": {}", reason))
+                .unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
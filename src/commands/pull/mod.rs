@@ -0,0 +1,326 @@
+//! Brings configuration changes made in a play-test instance back into the project.
+//!
+//! Files in the configuration directories of the instance are compared with the override
+//! files of the project. Each difference is shown and copied into the project once the user
+//! accepts it. Jars are only compared by name and reported, as mods are added through
+//! references.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::commands::build::{self, overrides, ExportFormat};
+use crate::commands::migrate_project::print_diff;
+use crate::commands::sync;
+use crate::models::config::{ContentType, Side};
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// Directories of the game directory that hold configuration, compared by default
+pub const DEFAULT_PULL_DIRS: [&str; 4] = ["config", "defaultconfigs", "kubejs", "scripts"];
+
+/// Options for the pull command
+#[derive(Debug, Clone, Default)]
+pub struct PullOptions {
+    /// A Prism Launcher or MultiMC instance, or a `.minecraft` directory
+    pub instance: PathBuf,
+    /// Directories of the game directory to compare (default: [`DEFAULT_PULL_DIRS`])
+    pub dirs: Vec<String>,
+    /// Accept every change without asking
+    pub yes: bool,
+    /// Only show the changes without touching the project
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    /// The file only exists in the instance
+    Added,
+    /// The file differs between the instance and the project
+    Modified,
+    /// A file placed by `minepack sync` was deleted from the instance
+    Removed,
+}
+
+/// A difference between a file in the instance and the project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PulledFile {
+    pub change: FileChange,
+    /// Path relative to the game directory
+    pub instance_path: PathBuf,
+    /// Path relative to the project directory
+    pub project_path: PathBuf,
+}
+
+/// The result of comparing an instance with the project
+#[derive(Debug, Clone, Default)]
+pub struct PullResult {
+    /// Changes copied into the project
+    pub applied: Vec<PulledFile>,
+    /// Changes the user declined or that were only shown
+    pub skipped: Vec<PulledFile>,
+    /// Jars in the instance's `mods/` without a reference
+    pub untracked_jars: Vec<String>,
+    /// Client mods of the references missing from the instance's `mods/`
+    pub missing_jars: Vec<String>,
+}
+
+pub async fn run<E: utils::Env>(env: &E, options: PullOptions) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let result = pull(env, &options)?;
+
+    let reporter = env.reporter();
+    for jar in &result.untracked_jars {
+        reporter.warning(&format!(
+            "⚠️ {} is in the instance but not in the modpack, add it with 'minepack add'",
+            jar
+        ));
+    }
+    for jar in &result.missing_jars {
+        reporter.warning(&format!(
+            "⚠️ {} is in the modpack but not in the instance, run 'minepack sync' to place it",
+            jar
+        ));
+    }
+
+    if result.applied.is_empty() && result.skipped.is_empty() {
+        reporter.message("✅ The instance has no configuration changes");
+    } else if options.dry_run {
+        reporter.message(&format!(
+            "🔍 Dry run, {} changes found, nothing was changed",
+            result.skipped.len()
+        ));
+    } else {
+        reporter.message(&format!(
+            "✅ Pulled {} changes into the project, skipped {}",
+            result.applied.len(),
+            result.skipped.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Compares the configuration directories of the instance with the project and copies the
+/// accepted changes into the project
+pub fn pull<E: utils::Env>(env: &E, options: &PullOptions) -> Result<PullResult> {
+    let config = utils::load_config(env)?;
+    let current_dir = env.current_dir()?;
+    let game_dir = sync::find_game_dir(&current_dir.join(&options.instance))?;
+    let manifest = sync::load_manifest(&game_dir)?;
+
+    let dirs: Vec<String> = if options.dirs.is_empty() {
+        DEFAULT_PULL_DIRS
+            .iter()
+            .map(|dir| dir.to_string())
+            .collect()
+    } else {
+        options.dirs.clone()
+    };
+    let in_dirs = |target: &str| {
+        dirs.iter()
+            .any(|dir| target.starts_with(&format!("{}/", dir.trim_end_matches('/'))))
+    };
+
+    // Where the files of the instance come from in the project. Client files replace common
+    // ones in an instance, so they are the ones to update.
+    let override_files = overrides::collect_overrides(env, &config, None)?;
+    let mut sources: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let (common, side_specific): (Vec<_>, Vec<_>) = override_files
+        .files
+        .iter()
+        .filter(|file| ExportFormat::MultiMC.override_sides().contains(&file.side))
+        .partition(|file| file.side == Side::Both);
+    for file in common.into_iter().chain(side_specific) {
+        let target = sync::manifest_path(&file.target);
+        if in_dirs(&target) {
+            sources.insert(target, file.source.clone());
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut seen = BTreeSet::new();
+    for dir in &dirs {
+        let instance_dir = game_dir.join(dir);
+        if !instance_dir.is_dir() {
+            continue;
+        }
+        for entry in WalkDir::new(&instance_dir).sort_by_file_name() {
+            let entry = entry.context("Failed to read directory entry")?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative_path = entry
+                .path()
+                .strip_prefix(&game_dir)
+                .context("Failed to strip prefix")?;
+            let target = sync::manifest_path(relative_path);
+            seen.insert(target.clone());
+
+            match sources.get(&target) {
+                Some(source) => {
+                    if fs::read(entry.path())? != fs::read(current_dir.join(source))? {
+                        changes.push(PulledFile {
+                            change: FileChange::Modified,
+                            instance_path: relative_path.to_path_buf(),
+                            project_path: source.clone(),
+                        });
+                    }
+                }
+                // Project files that aren't shipped, e.g. by .minepackignore, are left as they are
+                None if current_dir.join(relative_path).exists() => {}
+                // New files are shipped to both sides from the same path in the project
+                None => changes.push(PulledFile {
+                    change: FileChange::Added,
+                    instance_path: relative_path.to_path_buf(),
+                    project_path: relative_path.to_path_buf(),
+                }),
+            }
+        }
+    }
+
+    // Files missing from an instance that was never synced aren't deletions
+    for (target, source) in &sources {
+        if !seen.contains(target) && manifest.files.contains_key(target) {
+            changes.push(PulledFile {
+                change: FileChange::Removed,
+                instance_path: PathBuf::from(target),
+                project_path: source.clone(),
+            });
+        }
+    }
+
+    let mut result = PullResult::default();
+    for change in changes {
+        if options.dry_run {
+            show_change(env, &game_dir, &current_dir, &change)?;
+            result.skipped.push(change);
+            continue;
+        }
+
+        let accepted = options.yes || {
+            show_change(env, &game_dir, &current_dir, &change)?;
+            let prompt = match change.change {
+                FileChange::Added => {
+                    format!("Add {} to the project?", change.project_path.display())
+                }
+                FileChange::Modified => {
+                    format!("Update {} in the project?", change.project_path.display())
+                }
+                FileChange::Removed => {
+                    format!("Delete {} from the project?", change.project_path.display())
+                }
+            };
+            env.prompter()
+                .confirm(&prompt, change.change != FileChange::Removed)?
+        };
+        if !accepted {
+            result.skipped.push(change);
+            continue;
+        }
+
+        let project_path = current_dir.join(&change.project_path);
+        match change.change {
+            FileChange::Added | FileChange::Modified => {
+                if let Some(parent) = project_path.parent() {
+                    utils::ensure_dir_exists(parent)?;
+                }
+                fs::copy(game_dir.join(&change.instance_path), &project_path).with_context(
+                    || format!("Failed to copy file: {}", change.instance_path.display()),
+                )?;
+            }
+            FileChange::Removed => fs::remove_file(&project_path)
+                .with_context(|| format!("Failed to remove file: {}", project_path.display()))?,
+        }
+        result.applied.push(change);
+    }
+
+    (result.untracked_jars, result.missing_jars) = compare_jars(env, &game_dir)?;
+    Ok(result)
+}
+
+/// Prints a diff of a change, or a summary for files that aren't text
+fn show_change<E: utils::Env>(
+    env: &E,
+    game_dir: &Path,
+    current_dir: &Path,
+    change: &PulledFile,
+) -> Result<()> {
+    let read = |path: &Path| -> Result<Option<String>> {
+        if !path.exists() {
+            return Ok(Some(String::new()));
+        }
+        let content =
+            fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+        Ok(String::from_utf8(content).ok())
+    };
+    let project = read(&current_dir.join(&change.project_path))?;
+    let instance = read(&game_dir.join(&change.instance_path))?;
+    let project_name = change.project_path.display().to_string();
+    let instance_name = format!("instance/{}", change.instance_path.display());
+
+    match (project, instance) {
+        (Some(project), Some(instance)) => print_diff(
+            env.reporter(),
+            &project,
+            &instance,
+            &project_name,
+            &instance_name,
+        ),
+        _ => env.reporter().message(&format!(
+            "Binary files {} and {} differ",
+            project_name, instance_name
+        )),
+    }
+    Ok(())
+}
+
+/// Jars in the instance without a reference, and client mods missing from the instance
+fn compare_jars<E: utils::Env>(env: &E, game_dir: &Path) -> Result<(Vec<String>, Vec<String>)> {
+    let mod_entries = build::load_profile_entries(env, None)?;
+    let referenced: BTreeSet<&str> = mod_entries
+        .iter()
+        .filter(|entry| entry.content_type == ContentType::Mod)
+        .map(|entry| entry.version.as_str())
+        .collect();
+
+    let mut installed = BTreeSet::new();
+    let mods_dir = game_dir.join(ContentType::Mod.directory());
+    if mods_dir.is_dir() {
+        for entry in fs::read_dir(&mods_dir)
+            .with_context(|| format!("Failed to read directory: {}", mods_dir.display()))?
+        {
+            let file_name = entry?.file_name().to_string_lossy().to_string();
+            // Mods disabled in the launcher are still part of the instance
+            let file_name = file_name
+                .strip_suffix(".disabled")
+                .unwrap_or(&file_name)
+                .to_string();
+            if file_name.ends_with(".jar") {
+                installed.insert(file_name);
+            }
+        }
+    }
+
+    let untracked = installed
+        .iter()
+        .filter(|file_name| !referenced.contains(file_name.as_str()))
+        .cloned()
+        .collect();
+    let missing = mod_entries
+        .iter()
+        .filter(|entry| {
+            entry.content_type == ContentType::Mod
+                && entry.side != Side::Server
+                && !installed.contains(&entry.version)
+        })
+        .map(|entry| entry.version.clone())
+        .collect();
+    Ok((untracked, missing))
+}
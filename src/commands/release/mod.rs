@@ -0,0 +1,465 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::api::curseforge::upload::{CurseforgeUploadClient, UploadMetadata};
+use crate::api::modrinth::schema::{CreateVersionRequest, Index, VersionType};
+use crate::api::modrinth::{self, ModrinthClient};
+use crate::commands::build::{self, BuildOptions, BuildResult, ExportFormat};
+use crate::commands::changelog::{self, ChangelogOptions};
+use crate::models::config::{ModpackConfig, ReleaseType};
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// Which part of the version a release increments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl FromStr for VersionBump {
+    type Err = MinepackError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "major" => Ok(VersionBump::Major),
+            "minor" => Ok(VersionBump::Minor),
+            "patch" => Ok(VersionBump::Patch),
+            _ => Err(MinepackError::InvalidVersionBump(s.to_string())),
+        }
+    }
+}
+
+/// Options for the release command
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseOptions {
+    /// Export formats to build, `release.formats` or CurseForge if empty
+    pub formats: Vec<String>,
+    /// Release type of the upload (alpha, beta, release), `release.release_type` if not set
+    pub release_type: Option<String>,
+    /// File with the changelog of the release
+    pub changelog: Option<PathBuf>,
+    /// Generate the changelog by comparing with this version, see the changelog command
+    pub changelog_from: Option<String>,
+    /// Only bump the version and build, without uploading
+    pub no_upload: bool,
+}
+
+/// The result of a release
+#[derive(Debug, Clone)]
+pub struct ReleaseResult {
+    pub previous_version: String,
+    pub version: String,
+    pub builds: Vec<BuildResult>,
+    /// ID of the file uploaded to Curseforge
+    pub uploaded_file_id: Option<u32>,
+    /// ID of the version created on Modrinth
+    pub modrinth_version_id: Option<String>,
+}
+
+/// Bump the version, build the modpack and publish it on Curseforge and Modrinth
+pub async fn run<E: utils::Env>(env: &E, bump: String, options: ReleaseOptions) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let bump = VersionBump::from_str(&bump)?;
+    let result = release(env, bump, &options).await?;
+
+    env.reporter().message(&format!(
+        "✅ Released version {} (was {})",
+        result.version, result.previous_version
+    ));
+    for build in &result.builds {
+        env.reporter()
+            .message(&format!("  📦 {}", build.output_path.display()));
+    }
+    if let Some(file_id) = result.uploaded_file_id {
+        env.reporter().message(&format!(
+            "  ☁️ Uploaded to Curseforge (file ID: {})",
+            file_id
+        ));
+    }
+    if let Some(version_id) = &result.modrinth_version_id {
+        env.reporter().message(&format!(
+            "  ☁️ Published on Modrinth (version ID: {})",
+            version_id
+        ));
+    }
+
+    Ok(())
+}
+
+/// Bumps the version in the modpack configuration, builds the modpack and publishes it to
+/// the configured Curseforge and Modrinth projects. The version is set back if a build fails
+/// or nothing could be published.
+pub async fn release<E: utils::Env>(
+    env: &E,
+    bump: VersionBump,
+    options: &ReleaseOptions,
+) -> Result<ReleaseResult> {
+    let mut config = utils::load_config(env)?;
+    let settings = config.release.clone().unwrap_or_default();
+
+    let previous_version = config.version.clone();
+    let version = bump_version(&previous_version, bump)?;
+
+    let release_type = match &options.release_type {
+        Some(release_type) => ReleaseType::from_str(&release_type.to_lowercase())
+            .map_err(|_| anyhow!(MinepackError::InvalidReleaseType(release_type.clone())))?,
+        None => settings.release_type,
+    };
+
+    // Check everything needed for the uploads before touching the project
+    let (curseforge, modrinth) = if options.no_upload {
+        (None, None)
+    } else {
+        let curseforge = match settings.curseforge_project_id {
+            Some(project_id) => {
+                let token = get_upload_token(env)?;
+                let client = match &settings.upload_url {
+                    Some(url) => CurseforgeUploadClient::with_base_url(&token, url)?,
+                    None => CurseforgeUploadClient::with_token(env, &token)?,
+                };
+                Some((project_id, client))
+            }
+            None => None,
+        };
+        let modrinth = match &settings.modrinth_project_id {
+            Some(project_id) => {
+                let client = match &settings.modrinth_api_url {
+                    Some(url) => {
+                        ModrinthClient::with_base_url(&ModrinthClient::get_token(env)?, url)?
+                    }
+                    None => ModrinthClient::new(env)?,
+                };
+                // Versions are created with the project ID only, and a wrong slug or token
+                // should fail before the build
+                let project = client.get_project(project_id).await?;
+                Some((project.id, client))
+            }
+            None => None,
+        };
+        if curseforge.is_none() && modrinth.is_none() {
+            return Err(anyhow!(MinepackError::ReleaseProjectNotSet));
+        }
+        (curseforge, modrinth)
+    };
+
+    let format_names = if options.formats.is_empty() {
+        settings.formats.clone()
+    } else {
+        options.formats.clone()
+    };
+    let mut formats = Vec::new();
+    for name in &format_names {
+        let format = ExportFormat::from_str(name)?;
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    // The archives that get uploaded are always built
+    if curseforge.is_some() && !formats.contains(&ExportFormat::CurseForge) {
+        formats.push(ExportFormat::CurseForge);
+    }
+    if modrinth.is_some() && !formats.contains(&ExportFormat::Modrinth) {
+        formats.push(ExportFormat::Modrinth);
+    }
+    if formats.is_empty() {
+        formats.push(ExportFormat::CurseForge);
+    }
+
+    let changelog = match (&options.changelog, &options.changelog_from) {
+        (Some(path), _) => {
+            let path = env.current_dir()?.join(path);
+            fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read changelog: {}", path.display()))?
+        }
+        (None, Some(from)) => {
+            let changelog = changelog::changelog(
+                env,
+                &ChangelogOptions {
+                    from: from.clone(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            changelog::render_markdown(&changelog)
+        }
+        (None, None) => format!("{} {}", config.name, version),
+    };
+
+    env.reporter().message(&format!(
+        "🚀 Releasing {} {} (was {})",
+        config.name, version, previous_version
+    ));
+    config.version = version.clone();
+    utils::save_config(env, &config)?;
+
+    let mut builds = Vec::new();
+    for format in &formats {
+        match build::build(env, *format, &BuildOptions::default()).await {
+            Ok(build) => builds.push(build),
+            Err(err) => return Err(restore_version(env, config, previous_version, err)),
+        }
+    }
+    let archive = |format: ExportFormat| {
+        builds
+            .iter()
+            .find(|build| build.format == format)
+            .map(|build| build.output_path.clone())
+            .ok_or_else(|| anyhow!("The {} archive wasn't built", format.label()))
+    };
+
+    // Modrinth rejects the pack if it downloads files from other hosts, so nothing is uploaded
+    if modrinth.is_some() {
+        if let Err(err) = archive(ExportFormat::Modrinth).and_then(|path| check_downloads(&path)) {
+            return Err(restore_version(env, config, previous_version, err));
+        }
+    }
+
+    let mut uploaded_file_id = None;
+    if let Some((project_id, client)) = curseforge {
+        let uploaded = match archive(ExportFormat::CurseForge) {
+            Ok(path) => {
+                upload_to_curseforge(
+                    env,
+                    &config,
+                    &client,
+                    project_id,
+                    &path,
+                    release_type,
+                    &changelog,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        match uploaded {
+            Ok(file_id) => uploaded_file_id = Some(file_id),
+            Err(err) => return Err(restore_version(env, config, previous_version, err)),
+        }
+    }
+
+    let mut modrinth_version_id = None;
+    if let Some((project_id, client)) = modrinth {
+        let published = match archive(ExportFormat::Modrinth) {
+            Ok(path) => {
+                publish_to_modrinth(
+                    env,
+                    &config,
+                    &client,
+                    &project_id,
+                    &path,
+                    release_type,
+                    &changelog,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        match published {
+            Ok(version_id) => modrinth_version_id = Some(version_id),
+            // The release is already public on Curseforge, so the version stays
+            Err(err) if uploaded_file_id.is_some() => {
+                return Err(err.context(format!(
+                    "Version {} was uploaded to Curseforge, but not published on Modrinth",
+                    version
+                )))
+            }
+            Err(err) => return Err(restore_version(env, config, previous_version, err)),
+        }
+    }
+
+    Ok(ReleaseResult {
+        previous_version,
+        version,
+        builds,
+        uploaded_file_id,
+        modrinth_version_id,
+    })
+}
+
+/// Sets the version in the modpack configuration back after a failed release
+fn restore_version<E: utils::Env>(
+    env: &E,
+    mut config: ModpackConfig,
+    previous_version: String,
+    err: anyhow::Error,
+) -> anyhow::Error {
+    config.version = previous_version;
+    match utils::save_config(env, &config) {
+        Ok(()) => err,
+        Err(save_err) => err.context(format!("Failed to set the version back: {:#}", save_err)),
+    }
+}
+
+/// Uploads the CurseForge zip, returns the ID of the new file
+async fn upload_to_curseforge<E: utils::Env>(
+    env: &E,
+    config: &ModpackConfig,
+    client: &CurseforgeUploadClient,
+    project_id: u32,
+    archive: &Path,
+    release_type: ReleaseType,
+    changelog: &str,
+) -> Result<u32> {
+    let game_versions = match config.release.as_ref() {
+        Some(release) if !release.game_version_ids.is_empty() => release.game_version_ids.clone(),
+        _ => {
+            let loader = config
+                .minecraft
+                .mod_loaders
+                .first()
+                .map(|loader| loader.id.as_str());
+            let ids = client
+                .find_game_version_ids(&config.minecraft.version, loader)
+                .await
+                .context("Failed to look up Curseforge game versions")?;
+            if ids.is_empty() {
+                return Err(anyhow!(MinepackError::GameVersionNotFound(
+                    config.minecraft.version.clone()
+                )));
+            }
+            ids
+        }
+    };
+
+    env.reporter().message(&format!(
+        "☁️ Uploading {} to Curseforge project {}",
+        archive.display(),
+        project_id
+    ));
+    let metadata = UploadMetadata {
+        changelog: changelog.to_string(),
+        changelog_type: "markdown".to_string(),
+        display_name: Some(format!("{} {}", config.name, config.version)),
+        game_versions,
+        release_type,
+    };
+    client.upload_file(project_id, &metadata, archive).await
+}
+
+/// Creates a version of the Modrinth project with the `.mrpack`, returns the version ID
+async fn publish_to_modrinth<E: utils::Env>(
+    env: &E,
+    config: &ModpackConfig,
+    client: &ModrinthClient,
+    project_id: &str,
+    archive: &Path,
+    release_type: ReleaseType,
+    changelog: &str,
+) -> Result<String> {
+    env.reporter().message(&format!(
+        "☁️ Publishing {} to Modrinth project {}",
+        archive.display(),
+        project_id
+    ));
+    let request = CreateVersionRequest {
+        project_id: project_id.to_string(),
+        name: format!("{} {}", config.name, config.version),
+        version_number: config.version.clone(),
+        changelog: Some(changelog.to_string()),
+        dependencies: Vec::new(),
+        game_versions: vec![config.minecraft.version.clone()],
+        version_type: match release_type {
+            ReleaseType::Alpha => VersionType::Alpha,
+            ReleaseType::Beta => VersionType::Beta,
+            ReleaseType::Release => VersionType::Release,
+        },
+        loaders: config
+            .minecraft
+            .mod_loaders
+            .iter()
+            .map(|loader| loader.id.clone())
+            .collect(),
+        featured: false,
+        file_parts: vec!["file".to_string()],
+        primary_file: Some("file".to_string()),
+    };
+    let version = client.create_version(&request, archive).await?;
+    Ok(version.id)
+}
+
+/// Fails if the `.mrpack` downloads files from hosts Modrinth doesn't accept
+fn check_downloads(archive: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let mut archive = zip::ZipArchive::new(file).context("Failed to read the .mrpack")?;
+    let index: Index = serde_json::from_reader(
+        archive
+            .by_name("modrinth.index.json")
+            .context("The .mrpack has no modrinth.index.json")?,
+    )
+    .context("Failed to parse modrinth.index.json")?;
+
+    let rejected: Vec<&str> = index
+        .files
+        .iter()
+        .filter(|file| {
+            file.downloads
+                .iter()
+                .any(|url| !modrinth::is_accepted_download(url))
+        })
+        .map(|file| file.path.as_str())
+        .collect();
+    if !rejected.is_empty() {
+        return Err(anyhow!(MinepackError::ModrinthDownloadsNotAccepted(
+            rejected.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn get_upload_token<E: utils::Env>(env: &E) -> Result<String> {
+    // First try environment variable
+    if let Some(token) = env.var("CURSEFORGE_UPLOAD_TOKEN") {
+        return Ok(token);
+    }
+
+    // Then try the user configuration
+    if let Some(token) = utils::load_user_config(env)?.curseforge.upload_token {
+        return Ok(token);
+    }
+
+    Err(anyhow!(MinepackError::UploadTokenNotFound))
+}
+
+/// Increments a `MAJOR.MINOR.PATCH` version, resetting the parts after the incremented one
+pub fn bump_version(version: &str, bump: VersionBump) -> Result<String> {
+    let invalid = || anyhow!(MinepackError::InvalidVersion(version.to_string()));
+
+    let parts = version
+        .split('.')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+    let [major, minor, patch] = parts[..] else {
+        return Err(invalid());
+    };
+
+    let (major, minor, patch) = match bump {
+        VersionBump::Major => (major + 1, 0, 0),
+        VersionBump::Minor => (major, minor + 1, 0),
+        VersionBump::Patch => (major, minor, patch + 1),
+    };
+    Ok(format!("{}.{}.{}", major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_version() {
+        assert_eq!(bump_version("1.2.3", VersionBump::Major).unwrap(), "2.0.0");
+        assert_eq!(bump_version("1.2.3", VersionBump::Minor).unwrap(), "1.3.0");
+        assert_eq!(bump_version("1.2.3", VersionBump::Patch).unwrap(), "1.2.4");
+        assert_eq!(bump_version("0.9.9", VersionBump::Patch).unwrap(), "0.9.10");
+        assert!(bump_version("1.2", VersionBump::Patch).is_err());
+        assert!(bump_version("1.2.3-beta", VersionBump::Patch).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::models::config::Reference;
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// A reference deleted by the remove command
+#[derive(Debug, Clone)]
+pub struct RemovedReference {
+    pub path: PathBuf,
+    pub reference: Reference,
+}
+
+/// Remove a mod, resource pack, etc. from the modpack
+pub async fn run<E: utils::Env>(env: &E, mod_query: String, yes: bool) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let (_, reference) = utils::find_reference(env, &mod_query)?;
+    let confirm = yes
+        || env
+            .prompter()
+            .confirm(
+                &format!(
+                    "Remove {} ({}) from the modpack?",
+                    reference.name, reference.filename
+                ),
+                true,
+            )
+            .context("Failed to confirm removal")?;
+    if !confirm {
+        return Ok(());
+    }
+
+    let removed = remove(env, &mod_query)?;
+    env.reporter().message(&format!(
+        "✅ Removed {} from {}/",
+        removed.reference.name,
+        removed.reference.content_type.directory()
+    ));
+
+    Ok(())
+}
+
+/// Deletes the reference matching `query` (slug, name or project ID)
+pub fn remove<E: utils::Env>(env: &E, query: &str) -> Result<RemovedReference> {
+    let (path, reference) = utils::find_reference(env, query)?;
+    fs::remove_file(&path)
+        .with_context(|| format!("Failed to remove reference file: {}", path.display()))?;
+
+    Ok(RemovedReference { path, reference })
+}
@@ -1,65 +1,330 @@
 use anyhow::{anyhow, Context, Result};
-use console::style;
+use console::{measure_text_width, pad_str, style, truncate_str, Alignment, Term};
+use std::str::FromStr;
 
+use crate::api::curseforge::schema::{
+    Mod as CurseForgeModInfo, ModsSearchSortField, SearchModsRequestQuery, SortOrder,
+};
 use crate::api::curseforge::CurseforgeClient;
+use crate::models::config::ContentType;
+use crate::utils;
 use crate::utils::errors::MinepackError;
-use crate::{api, utils};
 
-pub async fn run<E: utils::Env>(env: &E, query: &str) -> Result<()> {
+/// The maximum page size accepted by the Curseforge API
+const MAX_PAGE_SIZE: u32 = 50;
+
+/// The Curseforge API only returns results where index + pageSize <= 10,000
+const MAX_RESULT_WINDOW: u32 = 10_000;
+
+const ID_COLUMN_WIDTH: usize = 8;
+const DOWNLOADS_COLUMN_WIDTH: usize = 10;
+const MIN_NAME_COLUMN_WIDTH: usize = 16;
+const MAX_NAME_COLUMN_WIDTH: usize = 40;
+const MIN_SUMMARY_COLUMN_WIDTH: usize = 10;
+
+/// Options for the search command
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// Page number, starting from 1
+    pub page: u32,
+    /// Number of results per page
+    pub page_size: u32,
+    /// Sort field (e.g. "popularity", "downloads", "name")
+    pub sort: Option<String>,
+    /// Sort order ("asc" or "desc")
+    pub order: Option<String>,
+    /// Curseforge category ID to filter by
+    pub category_id: Option<u32>,
+    /// Curseforge author ID to filter by
+    pub author_id: Option<u32>,
+    /// Don't filter the results by the modpack's primary mod loader
+    pub any_loader: bool,
+    /// Content type to search for (mod, resourcepack, shaderpack, datapack, world)
+    pub content_type: Option<String>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            page_size: 20,
+            sort: None,
+            order: None,
+            category_id: None,
+            author_id: None,
+            any_loader: false,
+            content_type: None,
+        }
+    }
+}
+
+pub async fn run<E: utils::Env>(env: &E, query: &str, options: SearchOptions) -> Result<()> {
     // Check if we're in a modpack directory
     if !utils::modpack_exists(env) {
         return Err(anyhow!(MinepackError::NoModpackFound));
     }
 
     let config = utils::load_config(env)?;
-    let client = CurseforgeClient::new().context("Failed to initialize Curseforge API client")?;
 
-    println!("🔍 Searching for mods matching '{}'...", query);
+    // Validate the options before talking to the API
+    let sort_field = options
+        .sort
+        .as_deref()
+        .map(|sort| {
+            ModsSearchSortField::from_str(&sort.to_lowercase())
+                .map_err(|_| anyhow!(MinepackError::InvalidSortField(sort.to_string())))
+        })
+        .transpose()?;
+    let sort_order = options
+        .order
+        .as_deref()
+        .map(|order| {
+            SortOrder::from_str(&order.to_lowercase())
+                .map_err(|_| anyhow!(MinepackError::InvalidSortOrder(order.to_string())))
+        })
+        .transpose()?;
+    let (index, page_size) = page_window(options.page, options.page_size)?;
+    let content_type = match &options.content_type {
+        Some(content_type) => ContentType::from_str(&content_type.to_lowercase())
+            .map_err(|_| anyhow!(MinepackError::InvalidContentType(content_type.clone())))?,
+        None => ContentType::Mod,
+    };
+
+    // Only mods are tied to a mod loader
+    let mod_loader = if options.any_loader || content_type != ContentType::Mod {
+        None
+    } else {
+        config
+            .minecraft
+            .mod_loaders
+            .iter()
+            .find(|m| m.primary)
+            .cloned()
+    };
+
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
+
+    env.reporter().message(&format!(
+        "🔍 Searching for {}s matching '{}'...",
+        content_type, query
+    ));
 
     // Search for mods with the given query, filtered by the configured Minecraft version
-    let mods = client
-        .search_mods(&api::curseforge::schema::SearchModsRequestQuery {
+    let response = client
+        .search_mods_paginated(&SearchModsRequestQuery {
+            class_id: Some(content_type.class_id()),
             search_filter: Some(query.to_string()),
             game_version: Some(config.minecraft.version.clone()),
+            mod_loader_type: mod_loader.map(|m| m.into()),
+            category_id: options.category_id,
+            author_id: options.author_id,
+            sort_field,
+            sort_order,
+            index: Some(index),
+            page_size: Some(page_size),
             ..Default::default()
         })
         .await
         .context("Failed to search for mods")?;
 
-    if mods.is_empty() {
+    if response.data.is_empty() {
+        if options.page > 1 {
+            return Err(anyhow!(MinepackError::InvalidPage(format!(
+                "page {} is beyond the last page of results for '{}'",
+                options.page, query
+            ))));
+        }
         return Err(anyhow!(MinepackError::NoModsFound(query.to_string())));
     }
 
-    // Display mod information in a formatted table
-    println!("\n{} results found:", mods.len());
-    println!(
-        "{:<8} {:<40} {:<15} {}",
-        style("ID").bold(),
-        style("Name").bold(),
-        style("Downloads").bold(),
-        style("Summary").bold()
-    );
-
-    // Show top 10 results
-    for mod_info in mods.iter().take(10) {
-        println!(
-            "{:<8} {:<40} {:<15} {}",
-            mod_info.id,
-            if mod_info.name.len() > 38 {
-                format!("{}...", &mod_info.name[0..35])
-            } else {
-                mod_info.name.clone()
-            },
-            format!("{}K", mod_info.download_count / 1000),
-            if mod_info.summary.len() > 50 {
-                format!("{}...", &mod_info.summary[0..47])
-            } else {
-                mod_info.summary.clone()
-            }
-        );
+    let total_count = response.pagination.total_count;
+    let total_pages = total_count.div_ceil(page_size as u64).max(1);
+    env.reporter().message(&format!(
+        "\n{} results found (page {} of {}):",
+        total_count, options.page, total_pages
+    ));
+
+    let (_, term_width) = Term::stdout().size();
+    for line in render_table(&response.data, term_width as usize) {
+        env.reporter().message(&line);
     }
 
-    println!("\nTo add a mod, use 'minepack add <mod_id>' or 'minepack add <search_term>'");
+    if (options.page as u64) < total_pages {
+        env.reporter().message(&format!(
+            "\nUse '--page {}' to see the next page of results.",
+            options.page + 1
+        ));
+    }
+    env.reporter().message(&format!(
+        "\nTo add a {}, use 'minepack add <search_term>{}' or 'minepack add <url>'",
+        content_type,
+        match content_type {
+            ContentType::Mod => String::new(),
+            _ => format!(" --type {}", content_type.as_str()),
+        }
+    ));
 
     Ok(())
 }
+
+/// Converts a 1-based page number into the index and page size expected by the Curseforge API
+fn page_window(page: u32, page_size: u32) -> Result<(u32, u32)> {
+    if page == 0 {
+        return Err(anyhow!(MinepackError::InvalidPage(
+            "page numbers start at 1".to_string()
+        )));
+    }
+    if page_size == 0 || page_size > MAX_PAGE_SIZE {
+        return Err(anyhow!(MinepackError::InvalidPage(format!(
+            "page size must be between 1 and {}",
+            MAX_PAGE_SIZE
+        ))));
+    }
+
+    let index = (page - 1)
+        .checked_mul(page_size)
+        .filter(|index| {
+            index
+                .checked_add(page_size)
+                .is_some_and(|end| end <= MAX_RESULT_WINDOW)
+        })
+        .ok_or_else(|| {
+            anyhow!(MinepackError::InvalidPage(format!(
+                "the Curseforge API only returns the first {} results",
+                MAX_RESULT_WINDOW
+            )))
+        })?;
+
+    Ok((index, page_size))
+}
+
+/// Renders the search results as a table that fits into the given terminal width
+fn render_table(mods: &[CurseForgeModInfo], term_width: usize) -> Vec<String> {
+    // Three spaces separate the four columns
+    let fixed_width = ID_COLUMN_WIDTH + DOWNLOADS_COLUMN_WIDTH + 3;
+    let flexible_width = term_width
+        .saturating_sub(fixed_width)
+        .max(MIN_NAME_COLUMN_WIDTH + MIN_SUMMARY_COLUMN_WIDTH);
+
+    let longest_name = mods
+        .iter()
+        .map(|m| measure_text_width(&m.name))
+        .max()
+        .unwrap_or(0);
+    let name_width = longest_name
+        .clamp(MIN_NAME_COLUMN_WIDTH, MAX_NAME_COLUMN_WIDTH)
+        .min(flexible_width - MIN_SUMMARY_COLUMN_WIDTH);
+    let summary_width = flexible_width - name_width;
+
+    let mut lines = vec![format!(
+        "{} {} {} {}",
+        style(pad_str("ID", ID_COLUMN_WIDTH, Alignment::Left, None)).bold(),
+        style(pad_str("Name", name_width, Alignment::Left, None)).bold(),
+        style(pad_str(
+            "Downloads",
+            DOWNLOADS_COLUMN_WIDTH,
+            Alignment::Left,
+            None
+        ))
+        .bold(),
+        style("Summary").bold()
+    )];
+
+    for mod_info in mods {
+        lines.push(format!(
+            "{} {} {} {}",
+            pad_str(
+                &mod_info.id.to_string(),
+                ID_COLUMN_WIDTH,
+                Alignment::Left,
+                None
+            ),
+            pad_str(&mod_info.name, name_width, Alignment::Left, Some("…")),
+            pad_str(
+                &format_download_count(mod_info.download_count),
+                DOWNLOADS_COLUMN_WIDTH,
+                Alignment::Left,
+                None
+            ),
+            truncate_str(&single_line(&mod_info.summary), summary_width, "…")
+        ));
+    }
+
+    lines
+}
+
+/// Collapses line breaks so that a summary doesn't break the table layout
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Formats a download count in a compact human readable form (e.g. 1.2M, 34.5K)
+fn format_download_count(count: u64) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.1}K", count as f64 / 1_000.0),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::curseforge::schema::GetModResponse;
+
+    #[test]
+    fn test_page_window() {
+        assert_eq!(page_window(1, 20).unwrap(), (0, 20));
+        assert_eq!(page_window(3, 50).unwrap(), (100, 50));
+        assert!(page_window(0, 20).is_err());
+        assert!(page_window(1, 51).is_err());
+        assert!(page_window(201, 50).is_err());
+        // index + page size would overflow a u32
+        assert!(page_window(85_899_346, 50).is_err());
+        assert!(page_window(u32::MAX, 50).is_err());
+    }
+
+    #[test]
+    fn test_format_download_count() {
+        assert_eq!(format_download_count(999), "999");
+        assert_eq!(format_download_count(12_345), "12.3K");
+        assert_eq!(format_download_count(4_560_000), "4.6M");
+    }
+
+    #[test]
+    fn test_render_table_with_multibyte_names() {
+        let template = serde_json::from_str::<GetModResponse>(include_str!(
+            "../../../tests/mocks/api.curseforge.com/v1/mods/1030830/index.json"
+        ))
+        .unwrap()
+        .data;
+        let mods = [
+            "工業化と自動化のための巨大なテクノロジーモッド",
+            "🚀🌍 Space Exploration 🛰️✨ and more rockets",
+            "Ünïcödé Ãççéñtš",
+        ]
+        .into_iter()
+        .map(|name| CurseForgeModInfo {
+            name: name.to_string(),
+            summary: "日本語の説明文です。🚀 絵文字も含まれています。".to_string(),
+            ..template.clone()
+        })
+        .collect::<Vec<_>>();
+
+        let term_width = 60;
+        let lines = render_table(&mods, term_width);
+        assert_eq!(lines.len(), mods.len() + 1);
+        for line in &lines {
+            assert!(
+                measure_text_width(line) <= term_width,
+                "{:?} doesn't fit into {} columns",
+                line,
+                term_width
+            );
+        }
+        // Names longer than their column are truncated on a character boundary
+        assert!(lines[1].contains("工業化と自動化"));
+        assert!(lines[1].contains('…'));
+    }
+}
@@ -0,0 +1,362 @@
+//! Brings a local launcher instance in line with the modpack without rebuilding it.
+//!
+//! The files minepack placed into the instance are recorded in a manifest in its game
+//! directory, with a hash of their content. On the next sync, files that are no longer part
+//! of the modpack are removed only if they are listed there, so mods, worlds and settings
+//! added by the player are left alone. Files the player changed since they were placed are
+//! neither replaced nor removed.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::api::curseforge::CurseforgeClient;
+use crate::commands::build::manual::ManualFile;
+use crate::commands::build::{self, overrides, ExportFormat};
+use crate::models::config::{ContentType, ModEntry, ModpackConfig, Side};
+use crate::utils;
+use crate::utils::errors::MinepackError;
+
+/// The files owned by minepack, in the game directory of the instance
+pub const MANIFEST_FILENAME: &str = ".minepack-sync.json";
+
+/// Options for the sync command
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /// A Prism Launcher or MultiMC instance, or a `.minecraft` directory
+    pub instance: PathBuf,
+    /// Name of the build profile in the modpack configuration
+    pub profile: Option<String>,
+    /// Only report the changes without touching the instance
+    pub dry_run: bool,
+}
+
+/// What a sync changed in the instance, paths are relative to its game directory
+#[derive(Debug, Clone, Default)]
+pub struct SyncResult {
+    /// The directory the files were placed in
+    pub game_dir: PathBuf,
+    pub added: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /// Files the player changed or placed that sync would replace or remove, they are left
+    /// alone
+    pub modified: Vec<PathBuf>,
+    /// Number of files that were already up to date
+    pub unchanged: usize,
+    /// Files that weren't placed because they have to be downloaded by hand
+    pub manual_downloads: Vec<ManualFile>,
+}
+
+/// Records the files minepack placed into an instance
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncManifest {
+    pub name: String,
+    pub version: String,
+    /// Files relative to the game directory, with `/` as separator, and the SHA-256 of the
+    /// content sync placed there
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// World archives that were unpacked into `saves/`. Worlds are never replaced or
+    /// removed, so the player's progress is kept.
+    #[serde(default)]
+    pub worlds: BTreeSet<String>,
+}
+
+pub async fn run<E: utils::Env>(env: &E, options: SyncOptions) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let result = sync(env, &options).await?;
+
+    let reporter = env.reporter();
+    for (symbol, paths) in [
+        ("+", &result.added),
+        ("~", &result.updated),
+        ("-", &result.removed),
+        ("!", &result.modified),
+    ] {
+        for path in paths {
+            reporter.message(&format!("  {} {}", symbol, path.display()));
+        }
+    }
+    for file in &result.manual_downloads {
+        reporter.warning(&format!(
+            "⚠️ {} ({}) has to be downloaded by hand from {}, then run 'minepack fetch-manual'",
+            file.name, file.file_name, file.page_url
+        ));
+    }
+    let summary = format!(
+        "{} added, {} updated, {} removed, {} unchanged",
+        result.added.len(),
+        result.updated.len(),
+        result.removed.len(),
+        result.unchanged
+    );
+    if !result.modified.is_empty() {
+        reporter.warning(&format!(
+            "⚠️ {} files were changed in the instance and are left alone (marked with !)",
+            result.modified.len()
+        ));
+    }
+    if options.dry_run {
+        reporter.message(&format!("🔍 Dry run, nothing was changed: {}", summary));
+    } else {
+        reporter.message(&format!(
+            "✅ Synced {}: {}",
+            result.game_dir.display(),
+            summary
+        ));
+    }
+
+    Ok(())
+}
+
+/// Places the client files of the modpack into the instance and removes the files of earlier
+/// syncs that are no longer part of it
+pub async fn sync<E: utils::Env>(env: &E, options: &SyncOptions) -> Result<SyncResult> {
+    let config = utils::load_config(env)?;
+    let profile = build::find_profile(&config, options.profile.as_deref())?;
+
+    let instance = env.current_dir()?.join(&options.instance);
+    let game_dir = find_game_dir(&instance)?;
+    check_instance_versions(env, &instance, &config);
+    let manifest = load_manifest(&game_dir)?;
+
+    env.reporter().message(&format!(
+        "🔄 Syncing {} {} into {}",
+        config.name,
+        config.version,
+        game_dir.display()
+    ));
+
+    // An instance is a client, so server-only mods are left out
+    let mod_entries = build::load_profile_entries(env, profile)?;
+    let client_entries: Vec<&ModEntry> = mod_entries
+        .iter()
+        .filter(|entry| entry.side != Side::Server)
+        .collect();
+
+    let client =
+        CurseforgeClient::new(env).context("Failed to initialize Curseforge API client")?;
+    let pb = env.reporter().progress(client_entries.len() as u64);
+    let pb = pb.as_ref();
+    let manual = build::prefetch_mod_files(env, &client, &client_entries, pb).await?;
+    let data_pack_worlds =
+        build::data_pack_worlds(env, &client, &config, &client_entries, &manual, pb).await?;
+
+    // Target path in the game directory -> source file
+    let mut wanted: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut worlds = Vec::new();
+    for mod_entry in &client_entries {
+        pb.inc(1);
+        if build::is_manual(&manual, mod_entry) {
+            continue;
+        }
+        let cache_path = build::cache_mod_file(env, &client, mod_entry, pb).await?;
+        if mod_entry.content_type == ContentType::World {
+            worlds.push((mod_entry.version.clone(), cache_path));
+            continue;
+        }
+
+        for dir in build::install_dirs(mod_entry, &data_pack_worlds) {
+            let target = format!("{}/{}", dir, mod_entry.version);
+            // Optional mods are placed disabled, unless the player enabled them after an earlier sync
+            let disabled = format!("{}.disabled", target);
+            let enabled_by_player = game_dir.join(&target).exists()
+                && (manifest.files.contains_key(&target) || manifest.files.contains_key(&disabled));
+            let target = if mod_entry.content_type == ContentType::Mod
+                && !mod_entry.required
+                && !enabled_by_player
+            {
+                disabled
+            } else {
+                target
+            };
+            wanted.insert(target, cache_path.clone());
+        }
+    }
+    pb.finish(format!("Prepared {} files", client_entries.len()));
+
+    // Files for both sides first, so that client-specific files replace them
+    let current_dir = env.current_dir()?;
+    let override_files = overrides::collect_overrides(env, &config, profile)?;
+    let (common, side_specific): (Vec<_>, Vec<_>) = override_files
+        .files
+        .iter()
+        .filter(|file| ExportFormat::MultiMC.override_sides().contains(&file.side))
+        .partition(|file| file.side == Side::Both);
+    for file in common.into_iter().chain(side_specific) {
+        wanted.insert(manifest_path(&file.target), current_dir.join(&file.source));
+    }
+
+    let mut result = SyncResult {
+        game_dir: game_dir.clone(),
+        manual_downloads: manual.into_iter().map(ManualFile::from_entry).collect(),
+        ..Default::default()
+    };
+    let mut synced_files = BTreeMap::new();
+    for (target, source) in &wanted {
+        let target_path = game_dir.join(target);
+        let hash = file_hash(source)?;
+        if target_path.exists() {
+            let current = file_hash(&target_path)?;
+            if current == hash {
+                result.unchanged += 1;
+                synced_files.insert(target.clone(), hash);
+                continue;
+            }
+            // Only replace what an earlier sync placed and the player didn't change since
+            if manifest.files.get(target) != Some(&current) {
+                result.modified.push(PathBuf::from(target));
+                if let Some(recorded) = manifest.files.get(target) {
+                    synced_files.insert(target.clone(), recorded.clone());
+                }
+                continue;
+            }
+            result.updated.push(PathBuf::from(target));
+        } else {
+            result.added.push(PathBuf::from(target));
+        }
+
+        if !options.dry_run {
+            if let Some(parent) = target_path.parent() {
+                utils::ensure_dir_exists(parent)?;
+            }
+            fs::copy(source, &target_path)
+                .with_context(|| format!("Failed to copy file: {}", source.display()))?;
+        }
+        synced_files.insert(target.clone(), hash);
+    }
+
+    // Only files placed by an earlier sync and unchanged since are removed, changed files are
+    // left to the player
+    for (target, recorded) in &manifest.files {
+        if wanted.contains_key(target) {
+            continue;
+        }
+        let target_path = game_dir.join(target);
+        if !target_path.is_file() {
+            continue;
+        }
+        if file_hash(&target_path)? != *recorded {
+            result.modified.push(PathBuf::from(target));
+            continue;
+        }
+        result.removed.push(PathBuf::from(target));
+        if !options.dry_run {
+            fs::remove_file(&target_path)
+                .with_context(|| format!("Failed to remove file: {}", target_path.display()))?;
+        }
+    }
+
+    let mut synced_worlds = manifest.worlds.clone();
+    for (archive_name, cache_path) in worlds {
+        if synced_worlds.contains(&archive_name) {
+            continue;
+        }
+        result.added.push(Path::new("saves").join(&archive_name));
+        if !options.dry_run {
+            build::extract_world(&cache_path, &game_dir.join(ContentType::World.directory()))
+                .with_context(|| format!("Failed to extract world: {}", archive_name))?;
+        }
+        synced_worlds.insert(archive_name);
+    }
+
+    if !options.dry_run {
+        save_manifest(
+            &game_dir,
+            &SyncManifest {
+                name: config.name.clone(),
+                version: config.version.clone(),
+                files: synced_files,
+                worlds: synced_worlds,
+            },
+        )?;
+    }
+
+    Ok(result)
+}
+
+/// The game directory of an instance. Prism Launcher and MultiMC instances keep it in
+/// `.minecraft` or `minecraft` next to their `instance.cfg`, any other directory is used as
+/// the game directory itself.
+pub fn find_game_dir(instance: &Path) -> Result<PathBuf> {
+    if !instance.is_dir() {
+        return Err(anyhow!(MinepackError::InstanceNotFound(
+            instance.display().to_string()
+        )));
+    }
+
+    if instance.join("instance.cfg").exists() {
+        for name in [".minecraft", "minecraft"] {
+            if instance.join(name).is_dir() {
+                return Ok(instance.join(name));
+            }
+        }
+        return Ok(instance.join(".minecraft"));
+    }
+    Ok(instance.to_path_buf())
+}
+
+pub fn load_manifest(game_dir: &Path) -> Result<SyncManifest> {
+    let path = game_dir.join(MANIFEST_FILENAME);
+    if !path.exists() {
+        return Ok(SyncManifest::default());
+    }
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save_manifest(game_dir: &Path, manifest: &SyncManifest) -> Result<()> {
+    utils::ensure_dir_exists(game_dir)?;
+    let content = serde_json::to_string_pretty(manifest)?;
+    fs::write(game_dir.join(MANIFEST_FILENAME), content)
+        .with_context(|| format!("Failed to write {}", MANIFEST_FILENAME))
+}
+
+/// Warns if the Minecraft version of a Prism Launcher or MultiMC instance differs from the
+/// modpack, the launcher components are left to the player
+fn check_instance_versions<E: utils::Env>(env: &E, instance: &Path, config: &ModpackConfig) {
+    let Ok(content) = fs::read_to_string(instance.join("mmc-pack.json")) else {
+        return;
+    };
+    let Ok(pack) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return;
+    };
+    let minecraft_version = pack["components"].as_array().and_then(|components| {
+        components
+            .iter()
+            .find(|component| component["uid"] == "net.minecraft")
+            .and_then(|component| component["version"].as_str())
+    });
+    if let Some(version) = minecraft_version {
+        if version != config.minecraft.version {
+            env.reporter().warning(&format!(
+                "⚠️ The instance uses Minecraft {}, but the modpack is made for {}",
+                version, config.minecraft.version
+            ));
+        }
+    }
+}
+
+/// A relative path with `/` as separator, as stored in the manifest
+pub(crate) fn manifest_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The SHA-256 of a file as recorded in the manifest
+fn file_hash(path: &Path) -> Result<String> {
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&content)))
+}
@@ -0,0 +1,31 @@
+use anyhow::{anyhow, Result};
+
+use crate::utils;
+use crate::utils::errors::MinepackError;
+use crate::utils::reference_store::ReferenceStore;
+
+/// Unpin a mod so that it is included in bulk updates again
+pub fn run<E: utils::Env>(env: &E, mod_query: String) -> Result<()> {
+    // Check if we're in a modpack directory
+    if !utils::modpack_exists(env) {
+        return Err(anyhow!(MinepackError::NoModpackFound));
+    }
+
+    let mut store = ReferenceStore::load(env)?;
+    let stored = store.find(&mod_query)?;
+    let (path, mut reference) = (stored.path.clone(), stored.reference.clone());
+    if !reference.pinned {
+        env.reporter()
+            .message(&format!("{} is not pinned.", reference.name));
+        return Ok(());
+    }
+
+    reference.pinned = false;
+    reference.pin_reason = None;
+    store.update(&path, reference.clone())?;
+
+    env.reporter()
+        .message(&format!("✅ Unpinned {}", reference.name));
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
 pub mod api;
 pub mod commands;
 pub mod models;
+pub mod modpack;
+pub mod ui;
 pub mod utils;
@@ -1,11 +1,7 @@
-mod api;
-mod commands;
-mod models;
-mod utils;
-
 use clap::{Parser, Subcommand};
 use console::style;
-use utils::Env;
+use minepack::commands;
+use minepack::utils::{self, Env};
 
 #[derive(Parser)]
 #[command(name = "minepack")]
@@ -47,27 +43,207 @@ enum Commands {
         #[arg(long)]
         loader_version: Option<String>,
     },
-    /// Add a mod to the modpack
+    /// Add a mod, resource pack, shader pack, data pack or world to the modpack
     Add {
-        /// URL of the mod
+        /// Name or Curseforge URL of the project
         #[arg(value_name = "MOD")]
         mod_query: Option<String>,
 
         #[arg(long, short, default_value_t = false)]
         yes: bool,
+
+        /// Content type to search for (mod, resourcepack, shaderpack, datapack, world)
+        #[arg(long = "type", value_name = "TYPE")]
+        content_type: Option<String>,
     },
     /// Search for mods on Curseforge
     Search {
         /// Search query
         #[arg(value_name = "QUERY")]
         query: String,
+
+        /// Page of results to show, starting from 1
+        #[arg(long, default_value_t = 1)]
+        page: u32,
+
+        /// Number of results per page (max 50)
+        #[arg(long, default_value_t = 20)]
+        page_size: u32,
+
+        /// Sort field (featured, popularity, last-updated, name, author, downloads, category, game-version, rating)
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Sort order (asc, desc)
+        #[arg(long)]
+        order: Option<String>,
+
+        /// Only show mods in the given Curseforge category ID
+        #[arg(long)]
+        category: Option<u32>,
+
+        /// Only show mods by the given Curseforge author ID
+        #[arg(long)]
+        author: Option<u32>,
+
+        /// Show mods for any mod loader instead of only the modpack's loader
+        #[arg(long, default_value_t = false)]
+        any_loader: bool,
+
+        /// Content type to search for (mod, resourcepack, shaderpack, datapack, world)
+        #[arg(long = "type", value_name = "TYPE")]
+        content_type: Option<String>,
     },
     /// Build the modpack
     Build {
-        /// Export format (multimc, curseforge, modrinth)
+        /// Export format (multimc, curseforge, modrinth, server)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Build profile from minepack.json to build a variant of the modpack
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Compare two versions of the modpack and write release notes
+    Changelog {
+        /// Git revision, manifest.json, modrinth.index.json or built modpack to compare from
+        #[arg(long)]
+        from: String,
+
+        /// Version to compare to, same kinds as --from (default: the current project)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Output format (markdown, json)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Include the Curseforge changelogs of updated files
+        #[arg(long, default_value_t = false)]
+        upstream: bool,
+
+        /// Write the changelog to a file instead of printing it
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Pin a mod at its current file so that updates skip it, or list pinned mods
+    Pin {
+        /// Slug, name or project ID of the mod
+        #[arg(value_name = "MOD")]
+        mod_query: Option<String>,
+
+        /// Why the mod is pinned
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Unpin a mod
+    Unpin {
+        /// Slug, name or project ID of the mod
+        #[arg(value_name = "MOD")]
+        mod_query: String,
+    },
+    /// Bump the version, build the modpack and publish it on Curseforge and Modrinth
+    Release {
+        /// Part of the version to bump (major, minor, patch)
+        #[arg(value_name = "BUMP")]
+        bump: String,
+
+        /// Export format to build, can be repeated (default: release.formats or curseforge)
+        #[arg(long = "format")]
+        formats: Vec<String>,
+
+        /// Release type of the upload (alpha, beta, release)
+        #[arg(long)]
+        release_type: Option<String>,
+
+        /// File with the changelog of the release
+        #[arg(long, conflicts_with = "changelog_from")]
+        changelog: Option<std::path::PathBuf>,
+
+        /// Generate the changelog by comparing with a git revision or built modpack
+        #[arg(long)]
+        changelog_from: Option<String>,
+
+        /// Only bump the version and build, without uploading
+        #[arg(long, default_value_t = false)]
+        no_upload: bool,
+    },
+    /// Put files that have to be downloaded by hand into the cache
+    FetchManual {
+        /// Keep scanning this directory (e.g. ~/Downloads) until every file was found
+        #[arg(long, value_name = "DIR", conflicts_with = "dir")]
+        watch: Option<std::path::PathBuf>,
+
+        /// Scan this directory once
+        #[arg(long, value_name = "DIR")]
+        dir: Option<std::path::PathBuf>,
+    },
+    /// Bring a local launcher instance in line with the modpack
+    Sync {
+        /// Prism Launcher or MultiMC instance, or a .minecraft directory
+        #[arg(long)]
+        instance: std::path::PathBuf,
+
+        /// Build profile from minepack.json to sync a variant of the modpack
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Print the changes without touching the instance
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// Copy configuration changes from a local launcher instance back into the project
+    Pull {
+        /// Prism Launcher or MultiMC instance, or a .minecraft directory
+        #[arg(long)]
+        instance: std::path::PathBuf,
+
+        /// Directory of the game directory to compare, can be repeated (default: config, defaultconfigs, kubejs, scripts)
+        #[arg(long = "dir")]
+        dirs: Vec<String>,
+
+        /// Accept every change without asking
+        #[arg(long, short, default_value_t = false)]
+        yes: bool,
+
+        /// Print the changes without touching the project
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// Upgrade the project files to the current schema version
+    MigrateProject {
+        /// Print the changes as a diff without writing them
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Store the modpack configuration as json or toml
         #[arg(long)]
         format: Option<String>,
     },
+    /// Store an API key for Curseforge or Modrinth in the user configuration
+    Auth {
+        /// Service the key is for (curseforge, modrinth)
+        #[arg(value_name = "SERVICE")]
+        service: String,
+
+        /// API key, prompted for if not given
+        #[arg(long)]
+        key: Option<String>,
+
+        /// Save the key without checking it against the API
+        #[arg(long, default_value_t = false)]
+        no_verify: bool,
+    },
+    /// Remove a mod, resource pack, shader pack, data pack or world from the modpack
+    Remove {
+        /// Slug, name or project ID of the mod
+        #[arg(value_name = "MOD")]
+        mod_query: String,
+
+        /// Skip confirmation prompts
+        #[arg(long, short, default_value_t = false)]
+        yes: bool,
+    },
     /// Import a CurseForge modpack
     Import {
         /// Path to the CurseForge modpack zip file
@@ -108,9 +284,147 @@ async fn main() {
             )
             .await
         }
-        Commands::Add { mod_query, yes } => commands::add::run(&env, mod_query, yes).await,
-        Commands::Search { query } => commands::search::run(&env, &query).await,
-        Commands::Build { format } => commands::build::run(&env, format).await,
+        Commands::Add {
+            mod_query,
+            yes,
+            content_type,
+        } => {
+            commands::add::run(
+                &env,
+                mod_query,
+                yes,
+                commands::add::AddOptions { content_type },
+            )
+            .await
+        }
+        Commands::Search {
+            query,
+            page,
+            page_size,
+            sort,
+            order,
+            category,
+            author,
+            any_loader,
+            content_type,
+        } => {
+            commands::search::run(
+                &env,
+                &query,
+                commands::search::SearchOptions {
+                    page,
+                    page_size,
+                    sort,
+                    order,
+                    category_id: category,
+                    author_id: author,
+                    any_loader,
+                    content_type,
+                },
+            )
+            .await
+        }
+        Commands::Build { format, profile } => {
+            commands::build::run(&env, format, commands::build::BuildOptions { profile }).await
+        }
+        Commands::Changelog {
+            from,
+            to,
+            format,
+            upstream,
+            output,
+        } => {
+            commands::changelog::run(
+                &env,
+                commands::changelog::ChangelogOptions {
+                    from,
+                    to,
+                    format,
+                    upstream,
+                    output,
+                },
+            )
+            .await
+        }
+        Commands::Pin { mod_query, reason } => commands::pin::run(&env, mod_query, reason),
+        Commands::Remove { mod_query, yes } => commands::remove::run(&env, mod_query, yes).await,
+        Commands::Unpin { mod_query } => commands::unpin::run(&env, mod_query),
+        Commands::Release {
+            bump,
+            formats,
+            release_type,
+            changelog,
+            changelog_from,
+            no_upload,
+        } => {
+            commands::release::run(
+                &env,
+                bump,
+                commands::release::ReleaseOptions {
+                    formats,
+                    release_type,
+                    changelog,
+                    changelog_from,
+                    no_upload,
+                },
+            )
+            .await
+        }
+        Commands::FetchManual { watch, dir } => {
+            commands::fetch_manual::run(
+                &env,
+                commands::fetch_manual::FetchManualOptions {
+                    watch: watch.is_some(),
+                    dir: watch.or(dir),
+                    ..Default::default()
+                },
+            )
+            .await
+        }
+        Commands::Sync {
+            instance,
+            profile,
+            dry_run,
+        } => {
+            commands::sync::run(
+                &env,
+                commands::sync::SyncOptions {
+                    instance,
+                    profile,
+                    dry_run,
+                },
+            )
+            .await
+        }
+        Commands::Pull {
+            instance,
+            dirs,
+            yes,
+            dry_run,
+        } => {
+            commands::pull::run(
+                &env,
+                commands::pull::PullOptions {
+                    instance,
+                    dirs,
+                    yes,
+                    dry_run,
+                },
+            )
+            .await
+        }
+        Commands::MigrateProject { dry_run, format } => {
+            commands::migrate_project::run(
+                &env,
+                commands::migrate_project::MigrateOptions { dry_run, format },
+            )
+            .await
+        }
+        Commands::Auth {
+            service,
+            key,
+            no_verify,
+        } => commands::auth::run(&env, service, key, !no_verify).await,
         Commands::Import { path, yes } => commands::import::run(&env, path, yes).await,
     };
 
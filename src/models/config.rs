@@ -1,12 +1,41 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::migration::CURRENT_SCHEMA_VERSION;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModpackConfig {
+    /// Version of the project file format, see `models::migration`
+    #[serde(default)]
+    pub schema_version: u32,
     pub name: String,
     pub version: String,
     pub author: String,
     pub description: Option<String>,
     pub minecraft: Minecraft,
+    /// Which worlds the data packs are installed into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datapacks: Option<DataPacks>,
+    /// Curseforge API and CDN settings for this project, override the user configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curseforge: Option<Curseforge>,
+    /// Which project files are shipped with the modpack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<Overrides>,
+    /// Variants of the modpack, built with `minepack build --profile <name>`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Attribution files shipped with every export format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credits: Option<Credits>,
+    /// How `minepack release` builds and publishes the modpack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<Release>,
+    /// How reference files in subdirectories of the content directories are treated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub references: Option<References>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,17 +68,180 @@ pub struct ModEntry {
     pub version: String,
     pub download_url: String,
     pub required: bool,
+    /// What the content adds, see `Reference::description`
+    pub description: Option<String>,
+    pub content_type: ContentType,
+    pub side: Side,
+    pub tags: Vec<String>,
+    pub manual_download: Option<ManualDownload>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Reference {
+    /// Version of the reference file format, see `models::migration`
+    #[serde(default)]
+    pub schema_version: u32,
     pub name: String,
     pub filename: String,
     pub side: Side,
     pub link: Link,
+    #[serde(default)]
+    pub content_type: ContentType,
+    /// Pinned references are held at their current file and skipped by bulk updates
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Why the reference is pinned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin_reason: Option<String>,
+    /// Optional content can be turned on and off by players, it's installed but disabled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    /// What the content adds, shown to players choosing optional content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-form labels (e.g. `performance`) that build profiles select references by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Set if the author disallows third-party distribution of the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manual_download: Option<ManualDownload>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// A file that launchers and minepack can't download, it has to be downloaded by hand
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ManualDownload {
+    /// The page the file is downloaded from
+    pub page_url: String,
+    /// Curseforge fingerprint of the file, `fetch-manual` recognizes downloads by it
+    pub fingerprint: u64,
+}
+
+impl Reference {
+    pub fn project_id(&self) -> u32 {
+        match self.link {
+            Link::CurseForge { project_id, .. } => project_id,
+        }
+    }
+
+    pub fn file_id(&self) -> u32 {
+        match self.link {
+            Link::CurseForge { file_id, .. } => file_id,
+        }
+    }
+}
+
+/// The kind of content a reference points to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentType {
+    #[default]
+    #[serde(rename = "mod")]
+    Mod,
+    #[serde(rename = "resourcepack")]
+    ResourcePack,
+    #[serde(rename = "shaderpack")]
+    ShaderPack,
+    #[serde(rename = "datapack")]
+    DataPack,
+    #[serde(rename = "world")]
+    World,
+}
+
+impl ContentType {
+    pub const ALL: [ContentType; 5] = [
+        ContentType::Mod,
+        ContentType::ResourcePack,
+        ContentType::ShaderPack,
+        ContentType::DataPack,
+        ContentType::World,
+    ];
+
+    /// The identifier used for this content type in reference files and on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Mod => "mod",
+            ContentType::ResourcePack => "resourcepack",
+            ContentType::ShaderPack => "shaderpack",
+            ContentType::DataPack => "datapack",
+            ContentType::World => "world",
+        }
+    }
+
+    /// The Curseforge class ID of this content type
+    pub fn class_id(&self) -> u32 {
+        match self {
+            ContentType::Mod => 6,
+            ContentType::ResourcePack => 12,
+            ContentType::ShaderPack => 6552,
+            ContentType::DataPack => 6945,
+            ContentType::World => 17,
+        }
+    }
+
+    pub fn from_class_id(class_id: u32) -> Option<Self> {
+        ContentType::ALL
+            .into_iter()
+            .find(|content_type| content_type.class_id() == class_id)
+    }
+
+    /// The URL path segment used by curseforge.com for this content type
+    pub fn curseforge_url_segment(&self) -> &'static str {
+        match self {
+            ContentType::Mod => "mc-mods",
+            ContentType::ResourcePack => "texture-packs",
+            ContentType::ShaderPack => "shaders",
+            ContentType::DataPack => "data-packs",
+            ContentType::World => "worlds",
+        }
+    }
+
+    pub fn from_curseforge_url_segment(segment: &str) -> Option<Self> {
+        ContentType::ALL
+            .into_iter()
+            .find(|content_type| content_type.curseforge_url_segment() == segment)
+    }
+
+    /// The directory used for this content type in the project, the cache and the game instance.
+    /// Minecraft only loads data packs from the `datapacks` directory of each world, so they
+    /// are installed there instead, see `DataPacks`.
+    pub fn directory(&self) -> &'static str {
+        match self {
+            ContentType::Mod => "mods",
+            ContentType::ResourcePack => "resourcepacks",
+            ContentType::ShaderPack => "shaderpacks",
+            ContentType::DataPack => "datapacks",
+            ContentType::World => "saves",
+        }
+    }
+}
+
+impl FromStr for ContentType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mod" | "mods" => Ok(ContentType::Mod),
+            "resourcepack" | "resourcepacks" | "texturepack" => Ok(ContentType::ResourcePack),
+            "shaderpack" | "shaderpacks" | "shader" | "shaders" => Ok(ContentType::ShaderPack),
+            "datapack" | "datapacks" => Ok(ContentType::DataPack),
+            "world" | "worlds" => Ok(ContentType::World),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentType::Mod => write!(f, "mod"),
+            ContentType::ResourcePack => write!(f, "resource pack"),
+            ContentType::ShaderPack => write!(f, "shader pack"),
+            ContentType::DataPack => write!(f, "data pack"),
+            ContentType::World => write!(f, "world"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Side {
     #[serde(rename = "both")]
     Both,
@@ -70,8 +262,167 @@ pub enum Link {
     },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Curseforge {}
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Overrides {
+    /// Gitignore-style patterns of the files to ship, all files are shipped if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+}
+
+/// Selects the references and override files that go into a variant of the modpack
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Profile {
+    /// Only references with one of these tags are included, all references if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_tags: Vec<String>,
+    /// References with one of these tags are left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+    /// Only references and override files for these sides are included, all sides if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sides: Vec<Side>,
+    /// Gitignore-style patterns of the override files to include, all files if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_overrides: Vec<String>,
+    /// Gitignore-style patterns of the override files to leave out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_overrides: Vec<String>,
+}
+
+impl Profile {
+    /// Whether a reference with the given tags and side is part of this profile
+    pub fn includes(&self, tags: &[String], side: Side) -> bool {
+        let has_tag = |wanted: &Vec<String>| tags.iter().any(|tag| wanted.contains(tag));
+
+        (self.include_tags.is_empty() || has_tag(&self.include_tags))
+            && !has_tag(&self.exclude_tags)
+            && self.includes_side(side)
+    }
+
+    /// Whether content for the given side is part of this profile, content for both sides is
+    /// part of every profile
+    pub fn includes_side(&self, side: Side) -> bool {
+        self.sides.is_empty() || side == Side::Both || self.sides.contains(&side)
+    }
+}
+
+/// Which attribution files are written into the game directory of the modpack
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Credits {
+    /// Write a `CREDITS.md` table of the projects and their authors
+    #[serde(default)]
+    pub markdown: bool,
+    /// Write a `modlist.html` like the one in CurseForge packs
+    #[serde(default)]
+    pub html: bool,
+}
+
+/// Settings for the reference files
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct References {
+    /// Tag references with the names of the subdirectories they are in, e.g. `library` for
+    /// `mods/library/jei.ex.json`
+    #[serde(default)]
+    pub folder_tags: bool,
+}
+
+/// Settings of `minepack release`
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Release {
+    /// Curseforge project the CurseForge zip is uploaded to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curseforge_project_id: Option<u32>,
+    /// Base URL of the Curseforge upload API, e.g. a local stand-in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_url: Option<String>,
+    /// ID or slug of the Modrinth project the `.mrpack` is published to, slugs are resolved to
+    /// the project ID before publishing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modrinth_project_id: Option<String>,
+    /// Base URL of the Modrinth API used for publishing, e.g. a local stand-in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modrinth_api_url: Option<String>,
+    #[serde(default)]
+    pub release_type: ReleaseType,
+    /// Export formats built for a release, CurseForge if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<String>,
+    /// Curseforge game version IDs of the upload, looked up from the Minecraft version if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub game_version_ids: Vec<u32>,
+}
+
+/// How stable a released file is
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseType {
+    Alpha,
+    Beta,
+    #[default]
+    Release,
+}
+
+impl FromStr for ReleaseType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alpha" => Ok(ReleaseType::Alpha),
+            "beta" => Ok(ReleaseType::Beta),
+            "release" => Ok(ReleaseType::Release),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ReleaseType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseType::Alpha => write!(f, "alpha"),
+            ReleaseType::Beta => write!(f, "beta"),
+            ReleaseType::Release => write!(f, "release"),
+        }
+    }
+}
+
+/// Where minepack reaches Curseforge, e.g. a caching proxy
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Curseforge {
+    /// Base URL of the Curseforge API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// CDN hosts (or base URLs) files are downloaded from, tried in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cdn_hosts: Vec<String>,
+}
+
+impl Curseforge {
+    /// Uses the settings of `self`, falling back to `other` for the ones that aren't set
+    pub fn or(self, other: Curseforge) -> Curseforge {
+        Curseforge {
+            api_url: self.api_url.or(other.api_url),
+            cdn_hosts: if self.cdn_hosts.is_empty() {
+                other.cdn_hosts
+            } else {
+                self.cdn_hosts
+            },
+        }
+    }
+}
+
+/// Minecraft has no global data packs, each world loads the ones in its `datapacks` directory.
+/// Data packs are installed into every world shipped with the modpack and the worlds listed here,
+/// and into the world of the server pack.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct DataPacks {
+    /// Names of world directories in `saves/` of the client, e.g. a world shipped in the overrides
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worlds: Vec<String>,
+    /// The world directory of the server pack, `level-name` of the shipped `server.properties`
+    /// or `world` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_world: Option<String>,
+}
 
 impl ModpackConfig {
     pub fn new(
@@ -82,11 +433,19 @@ impl ModpackConfig {
         minecraft: Minecraft,
     ) -> Self {
         Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
             name,
             version,
             author,
             description,
             minecraft,
+            datapacks: None,
+            curseforge: None,
+            overrides: None,
+            profiles: BTreeMap::new(),
+            credits: None,
+            release: None,
+            references: None,
         }
     }
 }
@@ -0,0 +1,192 @@
+//! Upgrades project files written by older versions of minepack.
+//!
+//! Migrations operate on the raw JSON value so that they can deal with fields
+//! the current structs no longer (or don't yet) understand. Each migration
+//! upgrades a file by exactly one schema version; files without a
+//! `schema_version` field are version 0.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+
+use crate::models::config::ContentType;
+use crate::utils::errors::MinepackError;
+
+/// The schema version written by this version of minepack
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+type ConfigMigration = fn(&mut Map<String, Value>) -> Result<()>;
+type ReferenceMigration = fn(&mut Map<String, Value>, ContentType) -> Result<()>;
+
+/// Migrations for `minepack.json`, indexed by the version they upgrade from
+const CONFIG_MIGRATIONS: [ConfigMigration; CURRENT_SCHEMA_VERSION as usize] = [config_v0_to_v1];
+
+/// Migrations for `*.ex.json` references, indexed by the version they upgrade from
+const REFERENCE_MIGRATIONS: [ReferenceMigration; CURRENT_SCHEMA_VERSION as usize] =
+    [reference_v0_to_v1];
+
+/// Returns the schema version of a parsed project file
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|version| version as u32)
+        .unwrap_or(0)
+}
+
+/// Upgrades a parsed `minepack.json` to the current schema version.
+/// Returns the version the file was at before the migration.
+pub fn migrate_config(value: &mut Value) -> Result<u32> {
+    let version = check_version(value)?;
+    let object = as_object(value)?;
+    for migration in &CONFIG_MIGRATIONS[version as usize..] {
+        migration(object)?;
+    }
+    object.insert("schema_version".to_string(), json!(CURRENT_SCHEMA_VERSION));
+    Ok(version)
+}
+
+/// Upgrades a parsed reference file to the current schema version.
+/// `dir_content_type` is the content type of the directory the reference was found in.
+/// Returns the version the file was at before the migration.
+pub fn migrate_reference(value: &mut Value, dir_content_type: ContentType) -> Result<u32> {
+    let version = check_version(value)?;
+    let object = as_object(value)?;
+    for migration in &REFERENCE_MIGRATIONS[version as usize..] {
+        migration(object, dir_content_type)?;
+    }
+    object.insert("schema_version".to_string(), json!(CURRENT_SCHEMA_VERSION));
+    Ok(version)
+}
+
+fn check_version(value: &Value) -> Result<u32> {
+    let version = schema_version(value);
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(anyhow!(MinepackError::UnsupportedSchemaVersion(
+            version,
+            CURRENT_SCHEMA_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+fn as_object(value: &mut Value) -> Result<&mut Map<String, Value>> {
+    value.as_object_mut().ok_or_else(|| {
+        anyhow!(MinepackError::InvalidFileFormat(
+            "expected a JSON object".to_string()
+        ))
+    })
+}
+
+/// Version 0 configs may have mod loaders without a version or primary flag
+fn config_v0_to_v1(config: &mut Map<String, Value>) -> Result<()> {
+    let Some(mod_loaders) = config
+        .get_mut("minecraft")
+        .and_then(|minecraft| minecraft.get_mut("mod_loaders"))
+        .and_then(Value::as_array_mut)
+    else {
+        return Ok(());
+    };
+
+    let has_primary = mod_loaders
+        .iter()
+        .any(|loader| loader.get("primary").is_some());
+    for (index, loader) in mod_loaders.iter_mut().enumerate() {
+        if let Some(loader) = loader.as_object_mut() {
+            loader.entry("version").or_insert_with(|| json!(""));
+            // Without any primary flag the first loader was treated as the primary one
+            loader
+                .entry("primary")
+                .or_insert_with(|| json!(!has_primary && index == 0));
+        }
+    }
+
+    Ok(())
+}
+
+/// Version 0 references may lack a side, a link type and a content type
+fn reference_v0_to_v1(
+    reference: &mut Map<String, Value>,
+    dir_content_type: ContentType,
+) -> Result<()> {
+    reference.entry("side").or_insert_with(|| json!("both"));
+    if let Some(link) = reference.get_mut("link").and_then(Value::as_object_mut) {
+        link.entry("type").or_insert_with(|| json!("curseforge"));
+    }
+    reference
+        .entry("content_type")
+        .or_insert_with(|| json!(dir_content_type.as_str()));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::{ModpackConfig, Reference, Side};
+
+    #[test]
+    fn test_migrate_v0_reference() {
+        let mut value = json!({
+            "name": "Faithful",
+            "filename": "Faithful.zip",
+            "link": { "project_id": 237645, "file_id": 4750000 }
+        });
+
+        assert_eq!(
+            migrate_reference(&mut value, ContentType::ResourcePack).unwrap(),
+            0
+        );
+        assert_eq!(schema_version(&value), CURRENT_SCHEMA_VERSION);
+
+        let reference: Reference = serde_json::from_value(value).unwrap();
+        assert_eq!(reference.side, Side::Both);
+        assert_eq!(reference.content_type, ContentType::ResourcePack);
+        assert_eq!(reference.project_id(), 237645);
+    }
+
+    #[test]
+    fn test_migrate_v0_config() {
+        let mut value = json!({
+            "name": "Test",
+            "version": "1.0.0",
+            "author": "Test Author",
+            "minecraft": {
+                "version": "1.21.1",
+                "mod_loaders": [{ "id": "fabric" }, { "id": "forge" }]
+            }
+        });
+
+        migrate_config(&mut value).unwrap();
+
+        let config: ModpackConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.schema_version, CURRENT_SCHEMA_VERSION);
+        assert!(config.minecraft.mod_loaders[0].primary);
+        assert!(!config.minecraft.mod_loaders[1].primary);
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let original = json!({
+            "schema_version": CURRENT_SCHEMA_VERSION,
+            "name": "Sodium",
+            "filename": "sodium.jar",
+            "side": "client",
+            "link": { "type": "curseforge", "project_id": 394468, "file_id": 5000000 },
+            "content_type": "mod"
+        });
+        let mut value = original.clone();
+
+        assert_eq!(
+            migrate_reference(&mut value, ContentType::Mod).unwrap(),
+            CURRENT_SCHEMA_VERSION
+        );
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut value = json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1 });
+        assert!(migrate_config(&mut value).is_err());
+        assert!(migrate_reference(&mut value, ContentType::Mod).is_err());
+    }
+}
@@ -1,2 +1,4 @@
 pub mod config;
+pub mod migration;
 pub mod mod_info;
+pub mod user_config;
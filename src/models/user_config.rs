@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::models::config::Curseforge;
+
+/// Number of parallel downloads used when `concurrency` isn't configured
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// User-level settings shared by all modpacks, stored in `<config dir>/minepack/config.toml`
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct UserConfig {
+    /// Directory used to cache downloaded files instead of the project's `.minepack/cache`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    /// Number of files downloaded in parallel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub curseforge: CurseforgeSettings,
+    #[serde(default)]
+    pub modrinth: ApiCredentials,
+    #[serde(default)]
+    pub defaults: Defaults,
+}
+
+impl UserConfig {
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1)
+    }
+
+    pub fn credentials(&self, service: ApiService) -> &ApiCredentials {
+        match service {
+            ApiService::Curseforge => &self.curseforge.credentials,
+            ApiService::Modrinth => &self.modrinth,
+        }
+    }
+
+    pub fn credentials_mut(&mut self, service: ApiService) -> &mut ApiCredentials {
+        match service {
+            ApiService::Curseforge => &mut self.curseforge.credentials,
+            ApiService::Modrinth => &mut self.modrinth,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ApiCredentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+/// The `[curseforge]` table holds the API key next to the API and CDN settings
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct CurseforgeSettings {
+    #[serde(flatten)]
+    pub credentials: ApiCredentials,
+    /// API token for uploading files with `minepack release`, different from the API key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_token: Option<String>,
+    #[serde(flatten)]
+    pub endpoints: Curseforge,
+}
+
+/// Defaults used when creating a new modpack
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Defaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Mod loader (forge, fabric, quilt, neoforge)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<String>,
+}
+
+/// The services minepack can store API keys for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiService {
+    Curseforge,
+    Modrinth,
+}
+
+impl FromStr for ApiService {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "curseforge" | "cf" => Ok(ApiService::Curseforge),
+            "modrinth" | "mr" => Ok(ApiService::Modrinth),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ApiService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiService::Curseforge => write!(f, "Curseforge"),
+            ApiService::Modrinth => write!(f, "Modrinth"),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! A typed API for working with a modpack project from other Rust programs.
+//!
+//! ```no_run
+//! use minepack::commands::add::{AddOptions, AddOutcome};
+//! use minepack::modpack::Modpack;
+//! use minepack::utils::{Env, RealEnv};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let env = RealEnv::new();
+//! let modpack = Modpack::open(&env)?;
+//! if let AddOutcome::Added(references) = modpack.add("sodium", true, AddOptions::default()).await? {
+//!     println!("Added {} references", references.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Prompts and progress go through the [`Prompter`](crate::ui::Prompter) and
+//! [`Reporter`](crate::ui::Reporter) of the [`Env`].
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+use crate::commands::add::{self, AddOptions, AddOutcome};
+use crate::commands::build::{self, BuildOptions, BuildResult, ExportFormat};
+use crate::commands::changelog::{self, Changelog, ChangelogOptions};
+use crate::commands::fetch_manual::{self, FetchManualOptions, FetchManualResult};
+use crate::commands::import::{self, ImportResult};
+use crate::commands::pull::{self, PullOptions, PullResult};
+use crate::commands::release::{self, ReleaseOptions, ReleaseResult, VersionBump};
+use crate::commands::remove::{self, RemovedReference};
+use crate::commands::sync::{self, SyncOptions, SyncResult};
+use crate::models::config::{ModpackConfig, Reference};
+use crate::utils::errors::MinepackError;
+use crate::utils::reference_store::ReferenceStore;
+use crate::utils::{self, Env};
+
+/// A modpack project in the directory of an [`Env`]
+pub struct Modpack<'a, E: Env> {
+    env: &'a E,
+    config: ModpackConfig,
+}
+
+impl<'a, E: Env> Modpack<'a, E> {
+    /// Opens the modpack in the env's current directory
+    pub fn open(env: &'a E) -> Result<Self> {
+        if !utils::modpack_exists(env) {
+            return Err(anyhow!(MinepackError::NoModpackFound));
+        }
+
+        let config = utils::load_config(env)?;
+        Ok(Self { env, config })
+    }
+
+    /// Imports a CurseForge modpack zip into the env's current directory.
+    /// Fails if a modpack already exists there unless `overwrite` is set.
+    pub async fn import(
+        env: &'a E,
+        modpack_path: &Path,
+        overwrite: bool,
+    ) -> Result<(Self, ImportResult)> {
+        if utils::modpack_exists(env) && !overwrite {
+            return Err(anyhow!(MinepackError::ModpackAlreadyExists));
+        }
+
+        let result = import::import(env, modpack_path).await?;
+        Ok((Self::open(env)?, result))
+    }
+
+    pub fn config(&self) -> &ModpackConfig {
+        &self.config
+    }
+
+    /// All references of the modpack together with their file paths
+    pub fn references(&self) -> Result<Vec<(PathBuf, Reference)>> {
+        utils::read_references(self.env)
+    }
+
+    /// The references indexed by slug, project ID and file ID
+    pub fn reference_store(&self) -> Result<ReferenceStore> {
+        ReferenceStore::load(self.env)
+    }
+
+    /// Adds a project by name or Curseforge URL, see [`add::add`]
+    pub async fn add(&self, query: &str, yes: bool, options: AddOptions) -> Result<AddOutcome> {
+        add::add(self.env, query, yes, options).await
+    }
+
+    /// Removes the reference matching a slug, name or project ID
+    pub fn remove(&self, query: &str) -> Result<RemovedReference> {
+        remove::remove(self.env, query)
+    }
+
+    /// Builds the modpack into the build directory
+    pub async fn build(&self, format: ExportFormat, options: BuildOptions) -> Result<BuildResult> {
+        build::build(self.env, format, &options).await
+    }
+
+    /// Puts files that have to be downloaded by hand into the cache, see
+    /// [`fetch_manual::fetch_manual`]
+    pub async fn fetch_manual(&self, options: &FetchManualOptions) -> Result<FetchManualResult> {
+        fetch_manual::fetch_manual(self.env, options).await
+    }
+
+    /// Brings a local launcher instance in line with the modpack, see [`sync::sync`]
+    pub async fn sync(&self, options: &SyncOptions) -> Result<SyncResult> {
+        sync::sync(self.env, options).await
+    }
+
+    /// Copies configuration changes from a local launcher instance into the project, see
+    /// [`pull::pull`]
+    pub fn pull(&self, options: &PullOptions) -> Result<PullResult> {
+        pull::pull(self.env, options)
+    }
+
+    /// Compares two versions of the modpack, see [`changelog::changelog`]
+    pub async fn changelog(&self, options: &ChangelogOptions) -> Result<Changelog> {
+        changelog::changelog(self.env, options).await
+    }
+
+    /// Bumps the version, builds and uploads the modpack, see [`release::release`]
+    pub async fn release(
+        &mut self,
+        bump: VersionBump,
+        options: &ReleaseOptions,
+    ) -> Result<ReleaseResult> {
+        let result = release::release(self.env, bump, options).await?;
+        self.config = utils::load_config(self.env)?;
+        Ok(result)
+    }
+}
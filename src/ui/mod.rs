@@ -0,0 +1,53 @@
+//! User interaction used by the commands.
+//!
+//! Commands never talk to the terminal directly. Prompts go through a [`Prompter`] and
+//! messages and progress through a [`Reporter`], both supplied by the [`Env`](crate::utils::Env).
+//! The CLI uses the terminal implementations, embedders can provide their own. Tests and
+//! other non-interactive callers can answer prompts with a [`ScriptedPrompter`].
+
+mod scripted;
+mod terminal;
+
+use anyhow::Result;
+
+pub use scripted::{Answer, ScriptedPrompter};
+pub use terminal::{TerminalProgress, TerminalPrompter, TerminalReporter};
+
+/// Asks the user for decisions
+pub trait Prompter: Send + Sync {
+    /// Asks for a line of text, `default` is used when the answer is empty
+    fn input(&self, prompt: &str, default: Option<&str>) -> Result<String>;
+
+    /// Asks the user to pick one of `items`, returns the index of the selected item
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize>;
+
+    /// Asks a yes/no question
+    fn confirm(&self, prompt: &str, default: bool) -> Result<bool>;
+
+    /// Asks for a secret such as an API key without echoing it
+    fn password(&self, prompt: &str) -> Result<String>;
+}
+
+/// Receives messages and progress updates
+pub trait Reporter: Send + Sync {
+    /// A regular status message
+    fn message(&self, message: &str);
+
+    /// Something went wrong, but the command can continue
+    fn warning(&self, message: &str);
+
+    /// Starts tracking the progress of `len` steps
+    fn progress(&self, len: u64) -> Box<dyn Progress>;
+}
+
+/// Progress of a long running step, see [`Reporter::progress`]
+pub trait Progress: Send + Sync {
+    fn set_message(&self, message: String);
+
+    fn inc(&self, delta: u64);
+
+    /// Reports a message without disturbing the progress display
+    fn println(&self, message: &str);
+
+    fn finish(&self, message: String);
+}
@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::Prompter;
+use crate::utils::errors::MinepackError;
+
+/// A prepared answer for a [`ScriptedPrompter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    /// Answers an input or password prompt
+    Text(String),
+    /// Answers a selection with the index of the item
+    Select(usize),
+    /// Answers a yes/no question
+    Confirm(bool),
+    /// Accepts the default of any prompt
+    Default,
+}
+
+/// Answers prompts from a prepared list instead of asking the user.
+///
+/// Every prompt takes the next answer. A prompt without an answer left, or with an answer of
+/// the wrong kind, fails, so a `ScriptedPrompter` without answers never blocks on input.
+#[derive(Debug, Default)]
+pub struct ScriptedPrompter {
+    answers: Mutex<VecDeque<Answer>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: impl IntoIterator<Item = Answer>) -> Self {
+        Self {
+            answers: Mutex::new(answers.into_iter().collect()),
+        }
+    }
+
+    /// Number of answers that haven't been used yet
+    pub fn remaining(&self) -> usize {
+        self.answers.lock().unwrap().len()
+    }
+
+    fn next_answer(&self, prompt: &str) -> Result<Answer> {
+        self.answers
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| anyhow!(MinepackError::PromptUnavailable(prompt.to_string())))
+    }
+}
+
+fn unexpected(prompt: &str, answer: Answer) -> anyhow::Error {
+    anyhow!(MinepackError::PromptUnavailable(format!(
+        "{} (scripted answer {:?} doesn't fit)",
+        prompt, answer
+    )))
+}
+
+impl Prompter for ScriptedPrompter {
+    fn input(&self, prompt: &str, default: Option<&str>) -> Result<String> {
+        match self.next_answer(prompt)? {
+            Answer::Text(text) => Ok(text),
+            Answer::Default => default
+                .map(str::to_string)
+                .ok_or_else(|| unexpected(prompt, Answer::Default)),
+            answer => Err(unexpected(prompt, answer)),
+        }
+    }
+
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+        match self.next_answer(prompt)? {
+            Answer::Select(index) if index < items.len() => Ok(index),
+            Answer::Default => Ok(default),
+            answer => Err(unexpected(prompt, answer)),
+        }
+    }
+
+    fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        match self.next_answer(prompt)? {
+            Answer::Confirm(confirmed) => Ok(confirmed),
+            Answer::Default => Ok(default),
+            answer => Err(unexpected(prompt, answer)),
+        }
+    }
+
+    fn password(&self, prompt: &str) -> Result<String> {
+        match self.next_answer(prompt)? {
+            Answer::Text(text) => Ok(text),
+            answer => Err(unexpected(prompt, answer)),
+        }
+    }
+}
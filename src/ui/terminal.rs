@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use console::style;
+use dialoguer::{Confirm, Input, Password, Select};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use super::{Progress, Prompter, Reporter};
+
+/// Prompts on the terminal using dialoguer
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn input(&self, prompt: &str, default: Option<&str>) -> Result<String> {
+        let mut input = Input::<String>::new()
+            .with_prompt(prompt)
+            .allow_empty(default.is_some());
+        if let Some(default) = default.filter(|default| !default.is_empty()) {
+            input = input.default(default.to_string());
+        }
+        input.interact_text().context("Failed to read input")
+    }
+
+    fn select(&self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+        Select::new()
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()
+            .context("Failed to read selection")
+    }
+
+    fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        Confirm::new()
+            .with_prompt(prompt)
+            .default(default)
+            .interact()
+            .context("Failed to read confirmation")
+    }
+
+    fn password(&self, prompt: &str) -> Result<String> {
+        Password::new()
+            .with_prompt(prompt)
+            .interact()
+            .context("Failed to read password")
+    }
+}
+
+/// Prints messages to the terminal and shows progress bars
+pub struct TerminalReporter;
+
+impl Reporter for TerminalReporter {
+    fn message(&self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn warning(&self, message: &str) {
+        eprintln!("{}", style(message).yellow());
+    }
+
+    fn progress(&self, len: u64) -> Box<dyn Progress> {
+        let pb = ProgressBar::new(len);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+                )
+                .expect("progress bar template is valid")
+                .progress_chars("#>-"),
+        );
+        Box::new(TerminalProgress(pb))
+    }
+}
+
+/// An indicatif progress bar
+pub struct TerminalProgress(pub ProgressBar);
+
+impl Progress for TerminalProgress {
+    fn set_message(&self, message: String) {
+        self.0.set_message(message);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.0.inc(delta);
+    }
+
+    fn println(&self, message: &str) {
+        self.0.println(message);
+    }
+
+    fn finish(&self, message: String) {
+        self.0.finish_with_message(message);
+    }
+}
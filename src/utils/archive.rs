@@ -0,0 +1,225 @@
+//! Extraction of zip archives that come from untrusted sources, such as modpacks and worlds
+//! shared by strangers.
+//!
+//! Entries must stay inside the destination directory, symbolic links are rejected, and the
+//! number of entries, the extracted size and the compression ratio are limited to stop zip
+//! bombs. Sizes are counted while extracting, as the sizes declared in the archive can lie.
+
+use anyhow::{anyhow, Context, Result};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::utils::errors::MinepackError;
+
+/// Entries below this size aren't checked for their compression ratio, small files of
+/// repeated content compress well legitimately
+const MIN_RATIO_CHECKED_SIZE: u64 = 1024 * 1024;
+
+/// Limits for extracting an archive
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    pub max_entries: usize,
+    /// Total size of the extracted files in bytes
+    pub max_total_size: u64,
+    /// Largest allowed ratio between the extracted and the compressed size of an entry
+    pub max_compression_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 100_000,
+            max_total_size: 4 * 1024 * 1024 * 1024,
+            max_compression_ratio: 1000,
+        }
+    }
+}
+
+/// Extracts the zip archive at `source` into `destination`, refusing archives that break
+/// `limits` or contain entries that could write outside of `destination`
+pub fn extract_zip(source: &Path, destination: &Path, limits: &ExtractLimits) -> Result<()> {
+    let file = File::open(source)
+        .with_context(|| format!("Failed to open archive: {}", source.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read archive: {}", source.display()))?;
+    let unsafe_archive = |reason: String| {
+        anyhow!(MinepackError::UnsafeArchive(
+            source.display().to_string(),
+            reason
+        ))
+    };
+
+    if archive.len() > limits.max_entries {
+        return Err(unsafe_archive(format!(
+            "it has {} entries, more than {}",
+            archive.len(),
+            limits.max_entries
+        )));
+    }
+
+    let mut total_size: u64 = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).context("Failed to access zip entry")?;
+        let name = entry.name().to_string();
+        let relative_path = entry
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| unsafe_archive(format!("'{}' points outside the archive", name)))?;
+        if entry
+            .unix_mode()
+            .is_some_and(|mode| mode & 0o170000 == 0o120000)
+        {
+            return Err(unsafe_archive(format!("'{}' is a symbolic link", name)));
+        }
+
+        let outpath = destination.join(&relative_path);
+        if entry.is_dir() {
+            fs::create_dir_all(&outpath).with_context(|| {
+                format!(
+                    "Failed to create directory for extraction: {}",
+                    outpath.display()
+                )
+            })?;
+            continue;
+        }
+
+        if entry.size() >= MIN_RATIO_CHECKED_SIZE
+            && entry.size() / entry.compressed_size().max(1) > limits.max_compression_ratio
+        {
+            return Err(unsafe_archive(format!(
+                "'{}' is compressed more than {}:1",
+                name, limits.max_compression_ratio
+            )));
+        }
+
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create parent directory for extraction: {}",
+                    parent.display()
+                )
+            })?;
+        }
+        let mut outfile = File::create(&outpath).with_context(|| {
+            format!(
+                "Failed to create file for extraction: {}",
+                outpath.display()
+            )
+        })?;
+
+        // Reads one byte more than allowed to notice archives that exceed the limit
+        let remaining = limits.max_total_size - total_size;
+        let mut buffer = [0; 64 * 1024];
+        let mut reader = (&mut entry).take(remaining + 1);
+        loop {
+            let read = reader
+                .read(&mut buffer)
+                .with_context(|| format!("Failed to extract {}", name))?;
+            if read == 0 {
+                break;
+            }
+            total_size += read as u64;
+            if total_size > limits.max_total_size {
+                drop(outfile);
+                let _ = fs::remove_file(&outpath);
+                return Err(unsafe_archive(format!(
+                    "its content is larger than {} bytes",
+                    limits.max_total_size
+                )));
+            }
+            outfile
+                .write_all(&buffer[..read])
+                .with_context(|| format!("Failed to write {}", outpath.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    fn write_archive(path: &Path, entries: &[(&str, &[u8])], options: FileOptions) {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        fs::write(path, zip.finish().unwrap().into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("pack.zip");
+        let destination = dir.path().join("out");
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let limits = ExtractLimits::default();
+
+        write_archive(
+            &archive,
+            &[
+                ("manifest.json", b"{}"),
+                ("overrides/config/a.toml", b"a = 1"),
+            ],
+            options,
+        );
+        extract_zip(&archive, &destination, &limits).unwrap();
+        assert_eq!(
+            fs::read_to_string(destination.join("overrides/config/a.toml")).unwrap(),
+            "a = 1"
+        );
+
+        // Zip slip
+        for name in ["../evil.txt", "overrides/../../evil.txt", "/etc/evil.txt"] {
+            write_archive(&archive, &[(name, b"evil")], options);
+            let error = extract_zip(&archive, &destination, &limits).unwrap_err();
+            assert!(
+                error.to_string().contains("points outside the archive"),
+                "{}",
+                error
+            );
+        }
+        assert!(!dir.path().join("evil.txt").exists());
+
+        // Symbolic links
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.add_symlink("link", "/etc/passwd", options).unwrap();
+        fs::write(&archive, zip.finish().unwrap().into_inner()).unwrap();
+        let error = extract_zip(&archive, &destination, &limits).unwrap_err();
+        assert!(error.to_string().contains("symbolic link"), "{}", error);
+
+        // Zip bombs
+        let zeros = vec![0; 2 * 1024 * 1024];
+        write_archive(&archive, &[("zeros.bin", &zeros)], options);
+        let error = extract_zip(&archive, &destination, &limits).unwrap_err();
+        assert!(
+            error.to_string().contains("compressed more than"),
+            "{}",
+            error
+        );
+        let small_limits = ExtractLimits {
+            max_total_size: 4,
+            ..limits
+        };
+        write_archive(&archive, &[("a", b"abc"), ("b", b"def")], options);
+        let error = extract_zip(&archive, &destination, &small_limits).unwrap_err();
+        assert!(
+            error.to_string().contains("larger than 4 bytes"),
+            "{}",
+            error
+        );
+        let few_entries = ExtractLimits {
+            max_entries: 1,
+            ..limits
+        };
+        let error = extract_zip(&archive, &destination, &few_entries).unwrap_err();
+        assert!(error.to_string().contains("2 entries"), "{}", error);
+    }
+}
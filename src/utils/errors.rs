@@ -11,6 +11,15 @@ pub enum MinepackError {
     #[error("Failed to access Curseforge API: {0}")]
     CurseforgeApiError(String),
 
+    #[error("Failed to access Modrinth API: {0}")]
+    ModrinthApiError(String),
+
+    #[error("Modrinth project not found: {0}. Check release.modrinth_project_id and that your token can access the project")]
+    ModrinthProjectNotFound(String),
+
+    #[error("Modrinth only accepts packs downloading files from cdn.modrinth.com, github.com, raw.githubusercontent.com or gitlab.com, but these are downloaded from other hosts: {0}. Set curseforge.cdn_hosts to a mirror on one of them, or leave Modrinth out of the release")]
+    ModrinthDownloadsNotAccepted(String),
+
     #[error("No compatible files found for Minecraft version {0}")]
     NoCompatibleModFiles(String),
 
@@ -20,27 +29,105 @@ pub enum MinepackError {
     #[error("Invalid mod loader selected")]
     InvalidModLoader,
 
+    #[error("Invalid sort field: {0}. Expected one of: featured, popularity, last-updated, name, author, downloads, category, game-version, early-access, featured-released, released, rating")]
+    InvalidSortField(String),
+
+    #[error("Invalid sort order: {0}. Expected 'asc' or 'desc'")]
+    InvalidSortOrder(String),
+
+    #[error("Invalid page: {0}")]
+    InvalidPage(String),
+
+    #[error("Invalid content type: {0}. Expected one of: mod, resourcepack, shaderpack, datapack, world")]
+    InvalidContentType(String),
+
     #[error("Invalid export format selected")]
     InvalidExportFormat,
 
-    #[error("Not a valid CurseForge URL. Expected format: https://www.curseforge.com/minecraft/mc-mods/[mod-name] or https://www.curseforge.com/minecraft/mc-mods/[mod-name]/files/[file-id]")]
+    #[error("Not a valid CurseForge URL. Expected format: https://www.curseforge.com/minecraft/[mc-mods|texture-packs|shaders|data-packs|worlds]/[project-name] or https://www.curseforge.com/minecraft/[mc-mods|texture-packs|shaders|data-packs|worlds]/[project-name]/files/[file-id]")]
     InvalidCurseforgeModUrl,
 
     #[error(
-        "Curseforge API key not found. Please set the CURSEFORGE_API_KEY environment variable"
+        "Curseforge API key not found. Run 'minepack auth curseforge' or set the CURSEFORGE_API_KEY environment variable"
     )]
-    #[allow(dead_code)]
     ApiKeyNotFound,
 
+    #[error("The {0} API rejected the API key")]
+    InvalidApiKey(String),
+
+    #[error("Unknown service: {0}. Expected 'curseforge' or 'modrinth'")]
+    UnknownApiService(String),
+
+    #[error("No reference found matching '{0}'")]
+    ReferenceNotFound(String),
+
+    #[error("Invalid reference file {0}: {1}")]
+    InvalidReference(String, String),
+
+    #[error("{1} and {2} both reference Curseforge project {0}, remove one of them")]
+    DuplicateReference(u32, String, String),
+
+    #[error("The reference name '{0}' is taken in {1}, rename or remove the existing reference")]
+    ReferenceSlugTaken(String, String),
+
     #[error("Failed to download mod: {0}")]
     ModDownloadError(String),
 
+    #[error("The author of {0} disallows third-party distribution, download it by hand and run 'minepack fetch-manual'")]
+    ManualDownloadRequired(String),
+
     #[error("File not found: {0}")]
     FileNotFound(String),
 
     #[error("Invalid file format: {0}")]
     InvalidFileFormat(String),
 
+    #[error("Refusing to extract {0}: {1}")]
+    UnsafeArchive(String, String),
+
+    #[error("This project file was written by a newer version of minepack (schema version {0}, this version supports up to {1}). Please update minepack.")]
+    UnsupportedSchemaVersion(u32, u32),
+
+    #[error("Can't ask for input in non-interactive mode: {0}")]
+    PromptUnavailable(String),
+
+    #[error("Unknown build profile '{0}'. Available profiles: {1}")]
+    UnknownProfile(String, String),
+
+    #[error("Invalid changelog format: {0}. Expected 'markdown' or 'json'")]
+    InvalidChangelogFormat(String),
+
+    #[error("'{0}' is neither a file nor a git revision of the project")]
+    UnknownChangelogSource(String),
+
+    #[error("Can't bump version '{0}', expected MAJOR.MINOR.PATCH")]
+    InvalidVersion(String),
+
+    #[error("Invalid version bump: {0}. Expected 'major', 'minor' or 'patch'")]
+    InvalidVersionBump(String),
+
+    #[error("Invalid release type: {0}. Expected 'alpha', 'beta' or 'release'")]
+    InvalidReleaseType(String),
+
+    #[error("No project to upload to. Set release.curseforge_project_id or release.modrinth_project_id in the modpack configuration or use --no-upload")]
+    ReleaseProjectNotSet,
+
+    #[error(
+        "Modrinth token not found. Run 'minepack auth modrinth' or set the MODRINTH_TOKEN environment variable"
+    )]
+    ModrinthTokenNotFound,
+
+    #[error(
+        "Curseforge upload token not found. Set curseforge.upload_token in the user configuration or the CURSEFORGE_UPLOAD_TOKEN environment variable"
+    )]
+    UploadTokenNotFound,
+
+    #[error("No Curseforge game version found for Minecraft {0}. Set release.game_version_ids in the modpack configuration")]
+    GameVersionNotFound(String),
+
+    #[error("No instance or game directory found at {0}")]
+    InstanceNotFound(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -56,6 +143,9 @@ pub enum MinepackError {
     #[error("Request error: {0}")]
     RequestError(#[from] reqwest::Error),
 
+    #[error("Invalid world name: '{0}'. Expected the name of a world directory")]
+    InvalidWorldName(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
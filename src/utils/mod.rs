@@ -1,4 +1,6 @@
+pub mod archive;
 pub mod errors;
+pub mod reference_store;
 
 use anyhow::{anyhow, Context, Result};
 use dotenvy::dotenv;
@@ -7,15 +9,121 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use crate::models::config::ModpackConfig;
+use crate::models::user_config::UserConfig;
+use crate::ui::{Prompter, Reporter, TerminalPrompter, TerminalReporter};
 use crate::utils::errors::MinepackError;
 use crate::{api, models};
 
 const CONFIG_FILENAME: &str = "minepack.json";
+const TOML_CONFIG_FILENAME: &str = "minepack.toml";
+
+/// The file formats a modpack configuration can be stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    pub fn filename(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => CONFIG_FILENAME,
+            ConfigFormat::Toml => TOML_CONFIG_FILENAME,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    /// Parses a configuration file into a JSON value so that it can be migrated
+    pub fn parse(&self, content: &str) -> Result<serde_json::Value> {
+        match self {
+            ConfigFormat::Json => Ok(serde_json::from_str(content)?),
+            ConfigFormat::Toml => {
+                let value: toml::Value = toml::from_str(content)?;
+                Ok(serde_json::to_value(value)?)
+            }
+        }
+    }
+
+    pub fn serialize(&self, config: &ModpackConfig) -> Result<String> {
+        match self {
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(config)?),
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(config)?),
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            _ => Err(()),
+        }
+    }
+}
+
+pub const USER_CONFIG_FILENAME: &str = "config.toml";
 
 pub trait Env {
     fn new() -> Self;
 
     fn current_dir(&self) -> std::io::Result<PathBuf>;
+
+    /// Reads an environment variable
+    fn var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+
+    /// The user's home directory
+    fn home_dir(&self) -> Option<PathBuf> {
+        dirs::home_dir()
+    }
+
+    /// Directory holding the user-level configuration
+    fn user_config_dir(&self) -> Option<PathBuf> {
+        default_user_config_dir()
+    }
+
+    /// Directory caching downloaded files
+    fn cache_dir(&self) -> Result<PathBuf>
+    where
+        Self: Sized,
+    {
+        default_cache_dir(self)
+    }
+
+    /// Base URL of the Curseforge API
+    fn curseforge_api_url(&self) -> String {
+        api::curseforge::CURSEFORGE_API_URL_PROD.to_string()
+    }
+
+    /// Base URL of the Curseforge upload API
+    fn curseforge_upload_url(&self) -> String {
+        api::curseforge::upload::CURSEFORGE_UPLOAD_URL_PROD.to_string()
+    }
+
+    /// Base URL of the Modrinth API
+    fn modrinth_api_url(&self) -> String {
+        api::modrinth::MODRINTH_API_URL_PROD.to_string()
+    }
+
+    /// Used by the commands to ask the user for decisions
+    fn prompter(&self) -> &dyn Prompter {
+        &TerminalPrompter
+    }
+
+    /// Used by the commands to report messages and progress
+    fn reporter(&self) -> &dyn Reporter {
+        &TerminalReporter
+    }
 }
 
 pub struct RealEnv {}
@@ -32,15 +140,43 @@ impl Env for RealEnv {
     }
 }
 
+/// An isolated environment for tests, talking to the mock API server.
+///
+/// The project and the user configuration live in temporary directories, environment
+/// variables only come from [`MockEnv::with_var`] and prompts are answered from a script.
 #[cfg(feature = "mock")]
 pub struct MockEnv {
     pub tempdir: assert_fs::TempDir,
+    pub user_config_dir: assert_fs::TempDir,
+    pub prompter: crate::ui::ScriptedPrompter,
+    vars: std::collections::HashMap<String, String>,
+    cache_dir: Option<PathBuf>,
+    mock_server_url: String,
 }
 
 #[cfg(feature = "mock")]
 impl MockEnv {
+    /// Answers the prompts of the commands with the given answers, in order
+    pub fn with_answers(mut self, answers: impl IntoIterator<Item = crate::ui::Answer>) -> Self {
+        self.prompter = crate::ui::ScriptedPrompter::new(answers);
+        self
+    }
+
+    /// Sets an environment variable visible to the commands
+    pub fn with_var(mut self, key: &str, value: &str) -> Self {
+        self.vars.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Caches downloaded files in the given directory instead of the project
+    pub fn with_cache_dir(mut self, cache_dir: &Path) -> Self {
+        self.cache_dir = Some(cache_dir.to_path_buf());
+        self
+    }
+
     #[allow(dead_code)]
     pub fn close(self) -> Result<(), assert_fs::fixture::FixtureError> {
+        self.user_config_dir.close()?;
         self.tempdir.close()
     }
 }
@@ -48,19 +184,134 @@ impl MockEnv {
 #[cfg(feature = "mock")]
 impl Env for MockEnv {
     fn new() -> Self {
+        let mock_server_url = std::env::var("MOCK_SERVER_URL")
+            .unwrap_or_else(|_| "http://127.0.0.1:25569".to_string());
+
         Self {
             tempdir: assert_fs::TempDir::new().unwrap(),
+            user_config_dir: assert_fs::TempDir::new().unwrap(),
+            prompter: crate::ui::ScriptedPrompter::default(),
+            // The mock server doesn't check the API key
+            vars: [("CURSEFORGE_API_KEY".to_string(), "mock-api-key".to_string())].into(),
+            cache_dir: None,
+            mock_server_url,
         }
     }
 
     fn current_dir(&self) -> std::io::Result<PathBuf> {
         Ok(self.tempdir.path().to_path_buf())
     }
+
+    fn var(&self, key: &str) -> Option<String> {
+        self.vars.get(key).cloned()
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        Some(self.user_config_dir.path().to_path_buf())
+    }
+
+    fn user_config_dir(&self) -> Option<PathBuf> {
+        Some(self.user_config_dir.path().to_path_buf())
+    }
+
+    fn cache_dir(&self) -> Result<PathBuf> {
+        match &self.cache_dir {
+            Some(cache_dir) => Ok(cache_dir.clone()),
+            None => default_cache_dir(self),
+        }
+    }
+
+    fn curseforge_api_url(&self) -> String {
+        format!("{}/api.curseforge.com/v1", self.mock_server_url)
+    }
+
+    fn curseforge_upload_url(&self) -> String {
+        format!("{}/minecraft.curseforge.com", self.mock_server_url)
+    }
+
+    fn modrinth_api_url(&self) -> String {
+        format!("{}/api.modrinth.com/v2", self.mock_server_url)
+    }
+
+    fn prompter(&self) -> &dyn Prompter {
+        &self.prompter
+    }
 }
 
+/// Returns `minepack/` inside the platform's configuration directory (e.g. `~/.config/minepack`)
+pub fn default_user_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("minepack"))
+}
+
+pub fn get_user_config_path<E: Env>(env: &E) -> Result<PathBuf> {
+    env.user_config_dir()
+        .map(|dir| dir.join(USER_CONFIG_FILENAME))
+        .ok_or_else(|| {
+            anyhow!(MinepackError::Unknown(
+                "Could not determine the user configuration directory".to_string()
+            ))
+        })
+}
+
+/// Loads the user configuration, returning the defaults if it doesn't exist
+pub fn load_user_config<E: Env>(env: &E) -> Result<UserConfig> {
+    load_user_config_from(&get_user_config_path(env)?)
+}
+
+pub fn load_user_config_from(path: &Path) -> Result<UserConfig> {
+    if !path.exists() {
+        return Ok(UserConfig::default());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read user config file: {}", path.display()))?;
+    toml::from_str(&content)
+        .with_context(|| format!("Failed to parse user config file: {}", path.display()))
+}
+
+/// Saves the user configuration. The file holds API keys, so it is only readable by the user.
+pub fn save_user_config<E: Env>(env: &E, config: &UserConfig) -> Result<PathBuf> {
+    let path = get_user_config_path(env)?;
+    if let Some(parent) = path.parent() {
+        ensure_dir_exists(parent)?;
+    }
+
+    let content =
+        toml::to_string_pretty(config).with_context(|| "Failed to serialize user configuration")?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Failed to create user config file: {}", path.display()))?;
+    // The mode only applies to new files, tighten the permissions of existing ones as well
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to set permissions of: {}", path.display()))?;
+    }
+    file.write_all(content.as_bytes())
+        .with_context(|| "Failed to write to user config file")?;
+
+    Ok(path)
+}
+
+/// Returns the path of the modpack configuration, `minepack.toml` is used if it exists and
+/// `minepack.json` doesn't
 pub fn get_minepack_config_path<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
     let current_dir = env.current_dir()?;
-    Ok(current_dir.join(CONFIG_FILENAME))
+    let json_path = current_dir.join(CONFIG_FILENAME);
+    let toml_path = current_dir.join(TOML_CONFIG_FILENAME);
+    if !json_path.exists() && toml_path.exists() {
+        return Ok(toml_path);
+    }
+    Ok(json_path)
 }
 
 pub fn load_config<E: Env>(env: &E) -> Result<ModpackConfig> {
@@ -71,14 +322,56 @@ pub fn load_config<E: Env>(env: &E) -> Result<ModpackConfig> {
 
     let config_content = fs::read_to_string(&config_path)
         .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
-    let config: ModpackConfig = serde_json::from_str(&config_content)
+    let mut value = ConfigFormat::from_path(&config_path)
+        .parse(&config_content)
         .with_context(|| "Failed to parse modpack configuration")?;
+
+    // Older configurations are upgraded in memory, `minepack migrate-project` writes them back
+    let version = models::migration::migrate_config(&mut value)
+        .with_context(|| format!("Failed to migrate config file: {}", config_path.display()))?;
+    if version < models::migration::CURRENT_SCHEMA_VERSION {
+        env.reporter().warning(&format!(
+            "⚠️  {} uses schema version {}, run 'minepack migrate-project' to upgrade it",
+            config_path.display(),
+            version
+        ));
+    }
+
+    let config: ModpackConfig =
+        serde_json::from_value(value).with_context(|| "Failed to parse modpack configuration")?;
     Ok(config)
 }
 
+/// Curseforge API and CDN settings of the project, falling back to the user configuration
+pub fn load_curseforge_settings<E: Env>(env: &E) -> Result<models::config::Curseforge> {
+    let user_settings = load_user_config(env)?.curseforge.endpoints;
+
+    let config_path = get_minepack_config_path(env)?;
+    if !config_path.exists() {
+        return Ok(user_settings);
+    }
+
+    // Only the `curseforge` table is read, so that API clients can be created without
+    // repeating the migration warnings of `load_config`
+    let config_content = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
+    let value = ConfigFormat::from_path(&config_path)
+        .parse(&config_content)
+        .with_context(|| "Failed to parse modpack configuration")?;
+    let Some(project_settings) = value.get("curseforge") else {
+        return Ok(user_settings);
+    };
+    let project_settings: models::config::Curseforge =
+        serde_json::from_value(project_settings.clone())
+            .with_context(|| "Failed to parse the curseforge settings of the modpack")?;
+
+    Ok(project_settings.or(user_settings))
+}
+
 pub fn save_config<E: Env>(env: &E, config: &ModpackConfig) -> Result<()> {
     let config_path = get_minepack_config_path(env)?;
-    let config_content = serde_json::to_string_pretty(config)
+    let config_content = ConfigFormat::from_path(&config_path)
+        .serialize(config)
         .with_context(|| "Failed to serialize modpack configuration")?;
     let mut file = File::create(&config_path)
         .with_context(|| format!("Failed to create config file: {}", config_path.display()))?;
@@ -111,6 +404,15 @@ pub fn get_mods_dir<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
     Ok(current_dir.join("mods"))
 }
 
+/// Returns the project directory holding the references of the given content type
+pub fn get_content_dir<E: Env>(
+    env: &E,
+    content_type: models::config::ContentType,
+) -> anyhow::Result<PathBuf> {
+    let current_dir = env.current_dir()?;
+    Ok(current_dir.join(content_type.directory()))
+}
+
 pub fn get_config_dir<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
     let current_dir = env.current_dir()?;
     Ok(current_dir.join("config"))
@@ -121,7 +423,21 @@ pub fn get_minepack_dir<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
     Ok(current_dir.join(".minepack"))
 }
 
+/// Returns the download cache supplied by the environment
 pub fn get_minepack_cache_dir<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
+    env.cache_dir()
+}
+
+/// Returns the default download cache, either the `cache_dir` from the user configuration or
+/// `.minepack/cache` in the project
+pub fn default_cache_dir<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
+    if let Some(cache_dir) = load_user_config(env)?.cache_dir {
+        // Allow `~/...` in the user configuration
+        if let (Ok(relative), Some(home_dir)) = (cache_dir.strip_prefix("~"), env.home_dir()) {
+            return Ok(home_dir.join(relative));
+        }
+        return Ok(cache_dir);
+    }
     get_minepack_dir(env).map(|path| path.join("cache"))
 }
 
@@ -129,6 +445,14 @@ pub fn get_minepack_cache_mods_dir<E: Env>(env: &E) -> anyhow::Result<PathBuf> {
     get_minepack_cache_dir(env).map(|path| path.join("mods"))
 }
 
+/// Returns the cache directory for downloaded files of the given content type
+pub fn get_minepack_cache_content_dir<E: Env>(
+    env: &E,
+    content_type: models::config::ContentType,
+) -> anyhow::Result<PathBuf> {
+    get_minepack_cache_dir(env).map(|path| path.join(content_type.directory()))
+}
+
 pub fn create_modpack_structure<E: Env>(env: &E) -> Result<()> {
     ensure_dir_exists(&get_mods_dir(env)?)?;
     ensure_dir_exists(&get_config_dir(env)?)?;
@@ -138,10 +462,31 @@ pub fn create_modpack_structure<E: Env>(env: &E) -> Result<()> {
     Ok(())
 }
 
+/// Reads all reference files (`*.ex.json`) from the content directories, see
+/// [`reference_store::ReferenceStore`]
+pub fn read_references<E: Env>(env: &E) -> Result<Vec<(PathBuf, models::config::Reference)>> {
+    Ok(reference_store::ReferenceStore::load(env)?
+        .into_references()
+        .into_iter()
+        .map(|stored| (stored.path, stored.reference))
+        .collect())
+}
+
+/// Finds a reference by its slug (file name without `.ex.json`), name or project ID
+pub fn find_reference<E: Env>(
+    env: &E,
+    query: &str,
+) -> Result<(PathBuf, models::config::Reference)> {
+    let store = reference_store::ReferenceStore::load(env)?;
+    let stored = store.find(query)?;
+    Ok((stored.path.clone(), stored.reference.clone()))
+}
+
 /// Determines which side (client/server/both) the mod is meant for
 pub fn determine_mod_side_cf(
     mod_name: &str,
     file: &api::curseforge::schema::File,
+    content_type: models::config::ContentType,
 ) -> Result<models::config::Side> {
     // This is a very simple heuristic and can be improved
     // For better accuracy, this could be enhanced to read the mod's metadata
@@ -149,6 +494,14 @@ pub fn determine_mod_side_cf(
 
     use models::config::Side;
 
+    // Resource packs and shaders are only ever loaded by the client
+    if matches!(
+        content_type,
+        models::config::ContentType::ResourcePack | models::config::ContentType::ShaderPack
+    ) {
+        return Ok(Side::Client);
+    }
+
     // Check if the mod is a server pack
     if file.is_server_pack.unwrap_or(false) {
         return Ok(Side::Server);
@@ -0,0 +1,529 @@
+//! Loads, validates, indexes and saves the reference files (`*.ex.json`) of a modpack.
+//!
+//! References are kept in the directory of their content type and named after their slug,
+//! e.g. `mods/sodium.ex.json`. They can be organized into subdirectories of any depth, such
+//! as `mods/library/jei.ex.json`. Every command reads them through [`ReferenceStore`], so a
+//! malformed file is reported with its path instead of being misread.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::config::{ContentType, Reference};
+use crate::models::migration;
+use crate::utils::errors::MinepackError;
+use crate::utils::{self, Env};
+
+/// The file name extension of reference files
+pub const REFERENCE_EXTENSION: &str = ".ex.json";
+
+/// A reference and the file it was loaded from
+#[derive(Debug, Clone)]
+pub struct StoredReference {
+    pub path: PathBuf,
+    /// The file name without `.ex.json`
+    pub slug: String,
+    /// The subdirectories of the content directory the file is in, outermost first
+    pub folders: Vec<String>,
+    pub reference: Reference,
+}
+
+/// The references of a modpack, indexed by slug, project ID and file ID
+#[derive(Debug, Default)]
+pub struct ReferenceStore {
+    references: Vec<StoredReference>,
+    by_slug: HashMap<String, usize>,
+    by_project_id: HashMap<u32, usize>,
+    by_file_id: HashMap<u32, usize>,
+}
+
+impl ReferenceStore {
+    /// Loads the references from the content directories and their subdirectories, in the
+    /// order of [`ContentType::ALL`] and then by path, see [`reference_files`]
+    pub fn load<E: Env>(env: &E) -> Result<Self> {
+        let mut store = Self::default();
+
+        for content_type in ContentType::ALL {
+            let content_dir = utils::get_content_dir(env, content_type)?;
+            if !content_dir.exists() {
+                continue;
+            }
+
+            for path in reference_files(&content_dir)? {
+                let content = fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read reference file: {}", path.display())
+                })?;
+                let reference =
+                    parse_reference(&path.display().to_string(), &content, content_type)?;
+                let folders = folders_of(&content_dir, &path);
+                store.insert(path, folders, reference)?;
+            }
+        }
+
+        Ok(store)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StoredReference> {
+        self.references.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.references.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    pub fn get_by_slug(&self, slug: &str) -> Option<&StoredReference> {
+        self.by_slug.get(slug).map(|&index| &self.references[index])
+    }
+
+    pub fn get_by_project_id(&self, project_id: u32) -> Option<&StoredReference> {
+        self.by_project_id
+            .get(&project_id)
+            .map(|&index| &self.references[index])
+    }
+
+    pub fn get_by_file_id(&self, file_id: u32) -> Option<&StoredReference> {
+        self.by_file_id
+            .get(&file_id)
+            .map(|&index| &self.references[index])
+    }
+
+    /// Finds a reference by its slug, name (ignoring case) or project ID
+    pub fn find(&self, query: &str) -> Result<&StoredReference> {
+        let query_lower = query.to_lowercase();
+
+        self.get_by_slug(query)
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|stored| stored.reference.name.to_lowercase() == query_lower)
+            })
+            .or_else(|| {
+                query
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(|project_id| self.get_by_project_id(project_id))
+            })
+            .ok_or_else(|| anyhow!(MinepackError::ReferenceNotFound(query.to_string())))
+    }
+
+    /// Writes a reference to `<slug>.ex.json` in the directory of its content type. A
+    /// reference to the same project replaces the existing one, which keeps its file name
+    /// and subdirectory unless the content type changed. The slug is sanitized, and if another project
+    /// already uses it, the project ID is appended.
+    pub fn save<E: Env>(
+        &mut self,
+        env: &E,
+        slug: &str,
+        reference: Reference,
+    ) -> Result<&StoredReference> {
+        let content_dir = utils::get_content_dir(env, reference.content_type)?;
+        let project_id = reference.project_id();
+        let existing = self
+            .get_by_project_id(project_id)
+            .map(|stored| (stored.path.clone(), stored.folders.clone()));
+        let (path, folders) = match existing {
+            Some((path, folders)) if path.starts_with(&content_dir) => (path, folders),
+            existing => {
+                let slug = sanitize_slug(slug);
+                let free_slug = self.free_slug(&slug, project_id, &content_dir)?;
+                if free_slug != slug {
+                    env.reporter().warning(&format!(
+                        "⚠️ The name '{}' is already used by another reference, saving {} as {}{}",
+                        slug, reference.name, free_slug, REFERENCE_EXTENSION
+                    ));
+                }
+                if let Some((path, _)) = existing {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("Failed to remove file: {}", path.display()))?;
+                }
+                let path = content_dir.join(format!("{}{}", free_slug, REFERENCE_EXTENSION));
+                (path, Vec::new())
+            }
+        };
+
+        utils::ensure_dir_exists(&content_dir)?;
+        let index = self.write(&path, folders, reference)?;
+        Ok(&self.references[index])
+    }
+
+    /// Writes a changed reference back to the file it was loaded from
+    pub fn update(&mut self, path: &Path, reference: Reference) -> Result<()> {
+        let folders = self
+            .references
+            .iter()
+            .find(|stored| stored.path == path)
+            .map(|stored| stored.folders.clone())
+            .unwrap_or_default();
+        self.write(path, folders, reference).map(|_| ())
+    }
+
+    /// Writes the reference to `path` and puts it in place of the references with the same
+    /// path or project, returning its index
+    fn write(&mut self, path: &Path, folders: Vec<String>, reference: Reference) -> Result<usize> {
+        validate(&reference).map_err(|reason| {
+            anyhow!(MinepackError::InvalidReference(
+                path.display().to_string(),
+                reason
+            ))
+        })?;
+        write_reference(path, &reference)?;
+
+        let project_id = reference.project_id();
+        let replaced = |stored: &StoredReference| {
+            stored.path == path || stored.reference.project_id() == project_id
+        };
+        let index = self
+            .references
+            .iter()
+            .position(replaced)
+            .unwrap_or(self.references.len());
+        self.references.retain(|stored| !replaced(stored));
+        // Everything before the first replaced reference is kept, so it goes back to its place
+        self.references.insert(
+            index,
+            StoredReference {
+                path: path.to_path_buf(),
+                slug: slug_of(path).unwrap_or_default(),
+                folders,
+                reference,
+            },
+        );
+        self.reindex();
+        Ok(index)
+    }
+
+    /// `slug`, or `slug-<project ID>` if another project or an unknown file has it already.
+    /// Slugs are unique across content directories, so that commands can find references
+    /// by them.
+    fn free_slug(&self, slug: &str, project_id: u32, content_dir: &Path) -> Result<String> {
+        let candidates = [slug.to_string(), format!("{}-{}", slug, project_id)];
+        candidates
+            .iter()
+            .find(|candidate| {
+                let path = content_dir.join(format!("{}{}", candidate, REFERENCE_EXTENSION));
+                let owned_by_other = self.references.iter().any(|stored| {
+                    (stored.slug == **candidate || stored.path == path)
+                        && stored.reference.project_id() != project_id
+                });
+                let unknown_file =
+                    path.exists() && !self.references.iter().any(|stored| stored.path == path);
+                !owned_by_other && !unknown_file
+            })
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(MinepackError::ReferenceSlugTaken(
+                    slug.to_string(),
+                    content_dir.display().to_string()
+                ))
+            })
+    }
+
+    pub fn into_references(self) -> Vec<StoredReference> {
+        self.references
+    }
+
+    fn insert(&mut self, path: PathBuf, folders: Vec<String>, reference: Reference) -> Result<()> {
+        if let Some(existing) = self.get_by_project_id(reference.project_id()) {
+            return Err(anyhow!(MinepackError::DuplicateReference(
+                reference.project_id(),
+                existing.path.display().to_string(),
+                path.display().to_string(),
+            )));
+        }
+
+        let slug = slug_of(&path).unwrap_or_default();
+        self.references.push(StoredReference {
+            path,
+            slug,
+            folders,
+            reference,
+        });
+        self.index(self.references.len() - 1);
+        Ok(())
+    }
+
+    fn reindex(&mut self) {
+        self.by_slug.clear();
+        self.by_project_id.clear();
+        self.by_file_id.clear();
+        for index in 0..self.references.len() {
+            self.index(index);
+        }
+    }
+
+    /// References found first win, later ones can only be reached by iterating
+    fn index(&mut self, index: usize) {
+        let stored = &self.references[index];
+        self.by_slug.entry(stored.slug.clone()).or_insert(index);
+        self.by_project_id
+            .entry(stored.reference.project_id())
+            .or_insert(index);
+        self.by_file_id
+            .entry(stored.reference.file_id())
+            .or_insert(index);
+    }
+}
+
+/// Parses a reference file, migrating it from older schema versions. `name` identifies the
+/// file in errors, `dir_content_type` is the content type of the directory it was found in.
+pub fn parse_reference(
+    name: &str,
+    content: &str,
+    dir_content_type: ContentType,
+) -> Result<Reference> {
+    let invalid =
+        |reason: String| anyhow!(MinepackError::InvalidReference(name.to_string(), reason));
+
+    let mut value: serde_json::Value =
+        serde_json::from_str(content).map_err(|err| invalid(err.to_string()))?;
+    migration::migrate_reference(&mut value, dir_content_type)
+        .map_err(|err| invalid(format!("{:#}", err)))?;
+    let reference: Reference = serde_json::from_value(value).map_err(|err| {
+        // Errors of the file as written carry the line and column, if it fails the same way
+        match serde_json::from_str::<Reference>(content) {
+            Err(located) => invalid(located.to_string()),
+            Ok(_) => invalid(err.to_string()),
+        }
+    })?;
+    validate(&reference).map_err(invalid)?;
+    Ok(reference)
+}
+
+/// Checks the fields serde can't, returning what is wrong with the reference
+pub fn validate(reference: &Reference) -> std::result::Result<(), String> {
+    if reference.name.trim().is_empty() {
+        return Err("name is empty".to_string());
+    }
+    if sanitize_file_name(&reference.filename).as_deref() != Some(reference.filename.as_str()) {
+        return Err(format!(
+            "filename '{}' is not a plain file name",
+            reference.filename
+        ));
+    }
+    if reference.project_id() == 0 {
+        return Err("link.project_id must not be 0".to_string());
+    }
+    if reference.file_id() == 0 {
+        return Err("link.file_id must not be 0".to_string());
+    }
+    Ok(())
+}
+
+/// The slug for the reference of a project: its Curseforge slug, or its name if it has none
+pub fn project_slug(slug: &str, name: &str) -> String {
+    sanitize_slug(if slug.trim().is_empty() { name } else { slug })
+}
+
+/// Turns a name into a slug that is a valid file name on every platform: lowercase ASCII
+/// letters, digits, `-`, `_` and `.`, without leading or trailing separators
+pub fn sanitize_slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches(|c| c == '-' || c == '.');
+
+    match slug {
+        "" => "reference".to_string(),
+        slug if is_reserved_name(slug) => format!("_{}", slug),
+        slug => slug.to_string(),
+    }
+}
+
+/// Makes a file name from the API safe to use as a path: only the last path component is
+/// kept, and characters Windows doesn't allow in file names are replaced with `_`. `None`
+/// if no usable name is left.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // Windows drops trailing dots and spaces, which would turn `..` into the parent directory
+    let name = name.trim_start().trim_end_matches(['.', ' ']);
+
+    match name {
+        "" => None,
+        name if is_reserved_name(name) => Some(format!("_{}", name)),
+        name => Some(name.to_string()),
+    }
+}
+
+/// Windows doesn't allow files named after devices, whatever their extension
+fn is_reserved_name(name: &str) -> bool {
+    const RESERVED: [&str; 22] = [
+        "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+        "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+    ];
+    let stem = name.split('.').next().unwrap_or_default().to_lowercase();
+    RESERVED.contains(&stem.as_str())
+}
+
+/// The reference files in a content directory and its subdirectories, sorted by path.
+/// Hidden directories are skipped.
+pub fn reference_files(content_dir: &Path) -> Result<Vec<PathBuf>> {
+    let walker = WalkDir::new(content_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            !entry.file_type().is_dir() || !entry.file_name().to_string_lossy().starts_with('.')
+        });
+
+    let mut paths = Vec::new();
+    for entry in walker {
+        let entry = entry
+            .with_context(|| format!("Failed to read directory: {}", content_dir.display()))?;
+        if entry.file_type().is_file() && slug_of(entry.path()).is_some() {
+            paths.push(entry.into_path());
+        }
+    }
+    Ok(paths)
+}
+
+/// The subdirectories between `content_dir` and the reference file at `path`
+fn folders_of(content_dir: &Path, path: &Path) -> Vec<String> {
+    path.parent()
+        .and_then(|parent| parent.strip_prefix(content_dir).ok())
+        .map(|relative| {
+            relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The slug of a reference file, `None` if it isn't one
+pub fn slug_of(path: &Path) -> Option<String> {
+    path.file_name()?
+        .to_str()?
+        .strip_suffix(REFERENCE_EXTENSION)
+        .map(str::to_string)
+}
+
+fn write_reference(path: &Path, reference: &Reference) -> Result<()> {
+    let json_content =
+        serde_json::to_string_pretty(reference).context("Failed to serialize mod JSON data")?;
+    fs::write(path, json_content)
+        .with_context(|| format!("Failed to write JSON reference file: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        let reference = parse_reference(
+            "mods/sodium.ex.json",
+            r#"{
+                "name": "Sodium",
+                "filename": "sodium.jar",
+                "side": "client",
+                "link": { "type": "curseforge", "project_id": 394468, "file_id": 5000000 }
+            }"#,
+            ContentType::Mod,
+        )
+        .unwrap();
+        assert_eq!(reference.project_id(), 394468);
+        assert_eq!(reference.file_id(), 5000000);
+
+        let error = parse_reference(
+            "mods/broken.ex.json",
+            r#"{ "name": "Broken", "filename": "broken.jar", "side": "both", "link": { "type": "curseforge", "project_id": "oops", "file_id": 1 } }"#,
+            ContentType::Mod,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.starts_with("Invalid reference file mods/broken.ex.json: "));
+        assert!(error.contains("line 1"), "{}", error);
+
+        let error = parse_reference(
+            "mods/zero.ex.json",
+            r#"{ "name": "Zero", "filename": "zero.jar", "side": "both", "link": { "type": "curseforge", "project_id": 0, "file_id": 1 } }"#,
+            ContentType::Mod,
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(
+            error,
+            "Invalid reference file mods/zero.ex.json: link.project_id must not be 0"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_slug() {
+        assert_eq!(sanitize_slug("sodium"), "sodium");
+        assert_eq!(sanitize_slug("Xaero's Minimap"), "xaero-s-minimap");
+        assert_eq!(
+            sanitize_slug("  JEI: Just Enough Items!  "),
+            "jei-just-enough-items"
+        );
+        assert_eq!(sanitize_slug("../../etc/passwd"), "etc-passwd");
+        assert_eq!(sanitize_slug("C:\\mods\\a.b"), "c-mods-a.b");
+        assert_eq!(sanitize_slug("Ünïcødé ☃"), "n-c-d");
+        assert_eq!(sanitize_slug("☃"), "reference");
+        assert_eq!(sanitize_slug("CON"), "_con");
+        assert_eq!(sanitize_slug("nul.txt"), "_nul.txt");
+        assert_eq!(project_slug("", "Iron Chests"), "iron-chests");
+        assert_eq!(project_slug("iron-chests", "Iron Chests"), "iron-chests");
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(
+            sanitize_file_name("fabric-api-0.66.3+1.19.3.jar").as_deref(),
+            Some("fabric-api-0.66.3+1.19.3.jar")
+        );
+        assert_eq!(
+            sanitize_file_name("Xaeros Minimap [1.21].jar").as_deref(),
+            Some("Xaeros Minimap [1.21].jar")
+        );
+        assert_eq!(
+            sanitize_file_name("../../.bashrc").as_deref(),
+            Some(".bashrc")
+        );
+        assert_eq!(
+            sanitize_file_name("..\\..\\mods\\evil.jar").as_deref(),
+            Some("evil.jar")
+        );
+        assert_eq!(
+            sanitize_file_name("C:evil?.jar").as_deref(),
+            Some("C_evil_.jar")
+        );
+        assert_eq!(sanitize_file_name("NUL.jar").as_deref(), Some("_NUL.jar"));
+        assert_eq!(sanitize_file_name(".."), None);
+        assert_eq!(sanitize_file_name("mods/"), None);
+        assert_eq!(sanitize_file_name(" . "), None);
+
+        let error = parse_reference(
+            "mods/evil.ex.json",
+            r#"{ "name": "Evil", "filename": "../evil.jar", "side": "both", "link": { "type": "curseforge", "project_id": 1, "file_id": 1 } }"#,
+            ContentType::Mod,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.ends_with("filename '../evil.jar' is not a plain file name"),
+            "{}",
+            error
+        );
+    }
+}
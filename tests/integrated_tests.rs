@@ -7,7 +7,8 @@ mod tests {
 
     // Import the necessary modules from the main application
     use minepack::commands;
-    use minepack::models::config::ModpackConfig;
+    use minepack::models::config::{ContentType, ModpackConfig};
+    use minepack::models::migration::CURRENT_SCHEMA_VERSION;
     use minepack::utils;
     use minepack::utils::MockEnv;
 
@@ -98,8 +99,8 @@ mod tests {
             read_config.minecraft.mod_loaders[0].version, "1.21.1-71.0.14",
             "Mod loader version doesn't match"
         );
-        assert_eq!(
-            read_config.minecraft.mod_loaders[0].primary, true,
+        assert!(
+            read_config.minecraft.mod_loaders[0].primary,
             "Mod loader is not primary"
         );
 
@@ -156,7 +157,13 @@ mod tests {
         );
 
         // Run the add command programmatically
-        let add_result = commands::add::run(&env, Some(mod_url), true).await;
+        let add_result = commands::add::run(
+            &env,
+            Some(mod_url),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await;
 
         // Assert that the add command succeeded
         assert!(add_result.is_ok(), "Add command failed: {:?}", add_result);
@@ -179,7 +186,7 @@ mod tests {
                     .path()
                     .extension()
                     .and_then(|ext| ext.to_str())
-                    .map_or(false, |ext| ext == "json")
+                    .is_some_and(|ext| ext == "json")
             });
 
         assert!(has_mod_files, "No mod JSON files found in mods directory");
@@ -291,7 +298,12 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
         );
         println!("BUILD_FORMAT_TEST - Build directory: {:?}", build_dir);
 
-        let build_result = commands::build::run(&env, Some("curseforge".to_string())).await;
+        let build_result = commands::build::run(
+            &env,
+            Some("curseforge".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await;
 
         // Assert that the build command succeeded
         assert!(
@@ -374,8 +386,8 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
             manifest.minecraft.mod_loaders[0].id, "fabric-0.14.21",
             "Manifest mod loader ID doesn't match"
         );
-        assert_eq!(
-            manifest.minecraft.mod_loaders[0].primary, true,
+        assert!(
+            manifest.minecraft.mod_loaders[0].primary,
             "Manifest mod loader is not primary"
         );
 
@@ -411,6 +423,109 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
         Ok(())
     }
 
+    /// Test to verify that resource packs are placed into the resourcepacks directory of a MultiMC instance
+    #[tokio::test]
+    async fn test_build_multimc_with_resource_pack() -> Result<()> {
+        // Set up isolated test environment
+        let env = MockEnv::new();
+
+        let init_result = commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.20.1".to_string()),
+            Some("0.14.21".to_string()),
+        )
+        .await;
+        assert!(
+            init_result.is_ok(),
+            "Init command failed: {:?}",
+            init_result
+        );
+
+        // Create a mod reference and a resource pack reference
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        fs::write(
+            mods_dir.join("test-mod.ex.json"),
+            r#"{
+                "name": "Test Mod",
+                "filename": "test-mod-1.0.0.jar",
+                "side": "both",
+                "link": { "type": "curseforge", "project_id": 123456, "file_id": 7890123 }
+            }"#,
+        )
+        .context("Failed to write mock mod JSON file")?;
+
+        let resourcepacks_dir = utils::get_content_dir(&env, ContentType::ResourcePack)?;
+        fs::create_dir_all(&resourcepacks_dir)?;
+        fs::write(
+            resourcepacks_dir.join("test-pack.ex.json"),
+            r#"{
+                "name": "Test Pack",
+                "filename": "test-pack-1.0.0.zip",
+                "side": "client",
+                "link": { "type": "curseforge", "project_id": 234567, "file_id": 8901234 },
+                "content_type": "resourcepack"
+            }"#,
+        )
+        .context("Failed to write mock resource pack JSON file")?;
+
+        // Put both files into the cache so that nothing has to be downloaded
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        fs::write(mod_cache_dir.join("test-mod-1.0.0.jar"), "mock jar file")?;
+        let pack_cache_dir =
+            utils::get_minepack_cache_content_dir(&env, ContentType::ResourcePack)?;
+        fs::create_dir_all(&pack_cache_dir)?;
+        fs::write(
+            pack_cache_dir.join("test-pack-1.0.0.zip"),
+            "mock resource pack",
+        )?;
+
+        let build_result = commands::build::run(
+            &env,
+            Some("multimc".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await;
+        assert!(
+            build_result.is_ok(),
+            "Build command failed: {:?}",
+            build_result
+        );
+
+        let zip_path = env
+            .current_dir()?
+            .join("build")
+            .join("Test Modpack-1.0.0-MultiMC.zip");
+        let zipfile = fs::File::open(&zip_path).context("Failed to open the output ZIP file")?;
+        let mut archive =
+            zip::ZipArchive::new(zipfile).context("Failed to read the ZIP archive")?;
+        let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+        println!("BUILD_MULTIMC_TEST - Archive entries: {:?}", names);
+
+        assert!(
+            names.contains(&"Test Modpack/.minecraft/mods/test-mod-1.0.0.jar".to_string()),
+            "Mod wasn't placed into the mods directory"
+        );
+        assert!(
+            names
+                .contains(&"Test Modpack/.minecraft/resourcepacks/test-pack-1.0.0.zip".to_string()),
+            "Resource pack wasn't placed into the resourcepacks directory"
+        );
+        let mut pack =
+            archive.by_name("Test Modpack/.minecraft/resourcepacks/test-pack-1.0.0.zip")?;
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut pack, &mut content)?;
+        assert_eq!(content, "mock resource pack");
+
+        env.close()?;
+        Ok(())
+    }
+
     /// Test to verify importing a modpack from a CurseForge zip file without modlist.html
     #[tokio::test]
     async fn test_import_curseforge_modpack() -> Result<()> {
@@ -518,8 +633,8 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
             config.minecraft.mod_loaders[0].version, "43.2.0",
             "Mod loader version doesn't match"
         );
-        assert_eq!(
-            config.minecraft.mod_loaders[0].primary, true,
+        assert!(
+            config.minecraft.mod_loaders[0].primary,
             "Mod loader is not primary"
         );
 
@@ -668,7 +783,13 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
         );
 
         // Run the add command with yes flag to auto-accept dependencies
-        let add_result = commands::add::run(&env, Some(mod_url), true).await;
+        let add_result = commands::add::run(
+            &env,
+            Some(mod_url),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await;
 
         // Assert that the add command succeeded
         assert!(add_result.is_ok(), "Add command failed: {:?}", add_result);
@@ -752,6 +873,98 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
         Ok(())
     }
 
+    /// Test to verify that pinned mods are recorded and skipped when they are added again
+    #[tokio::test]
+    async fn test_pin_and_unpin_mod() -> Result<()> {
+        // Set up isolated test environment
+        let env = MockEnv::new();
+
+        let init_result = commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await;
+        assert!(
+            init_result.is_ok(),
+            "Init command failed: {:?}",
+            init_result
+        );
+
+        let mod_url =
+            "https://www.curseforge.com/minecraft/mc-mods/oritech/files/6332315".to_string();
+        let add_result = commands::add::run(
+            &env,
+            Some(mod_url.clone()),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await;
+        assert!(add_result.is_ok(), "Add command failed: {:?}", add_result);
+
+        // Pin the mod with a reason
+        let pin_result = commands::pin::run(
+            &env,
+            Some("oritech".to_string()),
+            Some("Newer versions break our world".to_string()),
+        );
+        assert!(pin_result.is_ok(), "Pin command failed: {:?}", pin_result);
+
+        let reference_path = env.current_dir()?.join("mods").join("oritech.ex.json");
+        let pinned_content = fs::read_to_string(&reference_path)?;
+        let pinned: serde_json::Value = serde_json::from_str(&pinned_content)?;
+        assert_eq!(pinned["pinned"], serde_json::Value::Bool(true));
+        assert_eq!(
+            pinned["pin_reason"].as_str(),
+            Some("Newer versions break our world")
+        );
+
+        // Adding the mod again must not touch the pinned reference
+        fs::write(
+            &reference_path,
+            pinned_content.replace("6332315", "6332316"),
+        )?;
+        let add_again_result = commands::add::run(
+            &env,
+            Some(mod_url),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await;
+        assert!(
+            add_again_result.is_ok(),
+            "Add command failed: {:?}",
+            add_again_result
+        );
+        let after_add: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&reference_path)?)?;
+        assert_eq!(
+            after_add["link"]["file_id"].as_u64(),
+            Some(6332316),
+            "Pinned reference was overwritten"
+        );
+
+        // Unpinning removes the pin and its reason
+        let unpin_result = commands::unpin::run(&env, "Oritech".to_string());
+        assert!(
+            unpin_result.is_ok(),
+            "Unpin command failed: {:?}",
+            unpin_result
+        );
+        let unpinned: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&reference_path)?)?;
+        assert!(unpinned.get("pinned").is_none());
+        assert!(unpinned.get("pin_reason").is_none());
+
+        env.close()?;
+        Ok(())
+    }
+
     // Helper function to create a zip file from a directory
     fn create_zip_from_dir(src_dir: &Path, dst_file: &Path) -> Result<()> {
         let file = fs::File::create(dst_file).context("Failed to create zip file")?;
@@ -802,4 +1015,2544 @@ java_arguments=-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200
 
         Ok(())
     }
+
+    /// Test to verify that data packs are installed into the worlds, where Minecraft loads them
+    #[tokio::test]
+    async fn test_data_packs_are_installed_into_worlds() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::commands::sync::{self, SyncOptions};
+        use minepack::models::config::DataPacks;
+        use std::io::{Read, Write};
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        for (content_type, slug, filename, project_id) in [
+            (ContentType::DataPack, "terralith", "terralith-2.5.zip", 100),
+            (ContentType::World, "adventure", "adventure-1.0.zip", 200),
+        ] {
+            let dir = utils::get_content_dir(&env, content_type)?;
+            fs::create_dir_all(&dir)?;
+            fs::write(
+                dir.join(format!("{}.ex.json", slug)),
+                format!(
+                    r#"{{
+                        "name": "{}",
+                        "filename": "{}",
+                        "side": "both",
+                        "content_type": "{}",
+                        "link": {{ "type": "curseforge", "project_id": {}, "file_id": {} }}
+                    }}"#,
+                    slug,
+                    filename,
+                    content_type.as_str(),
+                    project_id,
+                    project_id + 1
+                ),
+            )?;
+        }
+        let data_pack_cache_dir =
+            utils::get_minepack_cache_content_dir(&env, ContentType::DataPack)?;
+        fs::create_dir_all(&data_pack_cache_dir)?;
+        fs::write(
+            data_pack_cache_dir.join("terralith-2.5.zip"),
+            "mock data pack",
+        )?;
+        let world_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::World)?;
+        fs::create_dir_all(&world_cache_dir)?;
+        let mut world =
+            zip::ZipWriter::new(fs::File::create(world_cache_dir.join("adventure-1.0.zip"))?);
+        world.start_file("Adventure/level.dat", zip::write::FileOptions::default())?;
+        world.write_all(b"mock level")?;
+        world.finish()?;
+
+        let mut config = utils::load_config(&env)?;
+        config.datapacks = Some(DataPacks {
+            worlds: vec!["Creative".to_string()],
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+
+        let build_dir = env.current_dir()?.join("build");
+        commands::build::run(&env, Some("multimc".to_string()), BuildOptions::default()).await?;
+        let archive = zip::ZipArchive::new(fs::File::open(
+            build_dir.join("Test Modpack-1.0.0-MultiMC.zip"),
+        )?)?;
+        let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+        for path in [
+            "Test Modpack/.minecraft/saves/Adventure/level.dat",
+            "Test Modpack/.minecraft/saves/Adventure/datapacks/terralith-2.5.zip",
+            "Test Modpack/.minecraft/saves/Creative/datapacks/terralith-2.5.zip",
+        ] {
+            assert!(names.contains(&path.to_string()), "{} is missing", path);
+        }
+        assert!(!names
+            .iter()
+            .any(|name| name.contains(".minecraft/datapacks/")));
+
+        commands::build::run(&env, Some("modrinth".to_string()), BuildOptions::default()).await?;
+        let mut archive =
+            zip::ZipArchive::new(fs::File::open(build_dir.join("Test Modpack-1.0.0.mrpack"))?)?;
+        let mut index = String::new();
+        archive
+            .by_name("modrinth.index.json")?
+            .read_to_string(&mut index)?;
+        let index: serde_json::Value = serde_json::from_str(&index)?;
+        let paths: Vec<&str> = index["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|file| file["path"].as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "saves/Adventure/datapacks/terralith-2.5.zip",
+                "saves/Creative/datapacks/terralith-2.5.zip"
+            ]
+        );
+
+        let instance = tempfile::tempdir()?;
+        sync::sync(
+            &env,
+            &SyncOptions {
+                instance: instance.path().to_path_buf(),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert!(instance
+            .path()
+            .join("saves/Adventure/datapacks/terralith-2.5.zip")
+            .exists());
+        assert!(instance
+            .path()
+            .join("saves/Creative/datapacks/terralith-2.5.zip")
+            .exists());
+        assert!(!instance.path().join("datapacks").exists());
+
+        // Servers load the data packs of their level directory, `world` unless configured
+        async fn server_names(env: &MockEnv) -> Result<Vec<String>> {
+            let server =
+                commands::build::build(env, ExportFormat::Server, &BuildOptions::default()).await?;
+            let archive = zip::ZipArchive::new(fs::File::open(&server.output_path)?)?;
+            Ok(archive.file_names().map(|name| name.to_string()).collect())
+        }
+        let names = server_names(&env).await?;
+        assert!(names.contains(&"world/datapacks/terralith-2.5.zip".to_string()));
+        assert!(!names.iter().any(|name| name.starts_with("datapacks/")));
+
+        let server_overrides = env.current_dir()?.join("server-overrides");
+        fs::create_dir_all(&server_overrides)?;
+        fs::write(
+            server_overrides.join("server.properties"),
+            "# Minecraft server properties\nlevel-name=survival\nmotd=Test\n",
+        )?;
+        let names = server_names(&env).await?;
+        assert!(names.contains(&"survival/datapacks/terralith-2.5.zip".to_string()));
+        assert!(!names.iter().any(|name| name.starts_with("world/")));
+
+        config.datapacks = Some(DataPacks {
+            server_world: Some("hardcore".to_string()),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        assert!(server_names(&env)
+            .await?
+            .contains(&"hardcore/datapacks/terralith-2.5.zip".to_string()));
+
+        // A world name that leaves saves/ is refused
+        config.datapacks = Some(DataPacks {
+            worlds: vec!["../evil".to_string()],
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let error =
+            commands::build::run(&env, Some("multimc".to_string()), BuildOptions::default())
+                .await
+                .unwrap_err();
+        assert!(
+            error.to_string().contains("Invalid world name"),
+            "{}",
+            error
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_project() -> Result<()> {
+        // Set up isolated test environment
+        let env = MockEnv::new();
+        let project_dir = env.current_dir()?;
+
+        // Write a project in the unversioned format
+        let config_path = project_dir.join("minepack.json");
+        let old_config = r#"{
+  "name": "Old Modpack",
+  "version": "1.0.0",
+  "author": "Test Author",
+  "minecraft": {
+    "version": "1.21.1",
+    "mod_loaders": [{ "id": "fabric", "version": "0.15.1" }]
+  }
+}"#;
+        fs::write(&config_path, old_config)?;
+        fs::create_dir_all(project_dir.join("resourcepacks"))?;
+        let reference_path = project_dir.join("resourcepacks").join("faithful.ex.json");
+        let old_reference = r#"{
+  "name": "Faithful",
+  "filename": "Faithful.zip",
+  "link": { "project_id": 237645, "file_id": 4750000 }
+}"#;
+        fs::write(&reference_path, old_reference)?;
+
+        // Old projects are still readable
+        let config = utils::load_config(&env)?;
+        assert_eq!(config.schema_version, CURRENT_SCHEMA_VERSION);
+        assert!(config.minecraft.mod_loaders[0].primary);
+        let references = utils::read_references(&env)?;
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].1.content_type, ContentType::ResourcePack);
+
+        // A dry run doesn't touch any files
+        println!("Running migrate-project --dry-run...");
+        let dry_run_result = commands::migrate_project::run(
+            &env,
+            commands::migrate_project::MigrateOptions {
+                dry_run: true,
+                format: None,
+            },
+        )
+        .await;
+        assert!(
+            dry_run_result.is_ok(),
+            "Dry run failed: {:?}",
+            dry_run_result
+        );
+        assert_eq!(fs::read_to_string(&config_path)?, old_config);
+        assert_eq!(fs::read_to_string(&reference_path)?, old_reference);
+
+        // Migrating writes the current schema version
+        println!("Running migrate-project...");
+        let migrate_result = commands::migrate_project::run(&env, Default::default()).await;
+        assert!(
+            migrate_result.is_ok(),
+            "Migration failed: {:?}",
+            migrate_result
+        );
+        let config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&config_path)?)?;
+        assert_eq!(config["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(config["minecraft"]["mod_loaders"][0]["primary"], true);
+        let reference: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&reference_path)?)?;
+        assert_eq!(reference["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(reference["side"], "both");
+        assert_eq!(reference["link"]["type"], "curseforge");
+        assert_eq!(reference["content_type"], "resourcepack");
+
+        // Converting the configuration to TOML
+        println!("Converting the configuration to TOML...");
+        let convert_result = commands::migrate_project::run(
+            &env,
+            commands::migrate_project::MigrateOptions {
+                dry_run: false,
+                format: Some("toml".to_string()),
+            },
+        )
+        .await;
+        assert!(
+            convert_result.is_ok(),
+            "Conversion failed: {:?}",
+            convert_result
+        );
+        assert!(!config_path.exists(), "minepack.json was not removed");
+        assert!(project_dir.join("minepack.toml").exists());
+        let config = utils::load_config(&env)?;
+        assert_eq!(config.name, "Old Modpack");
+
+        // Files written by a newer minepack are rejected instead of misread
+        fs::write(
+            &reference_path,
+            old_reference.replacen('{', "{\n  \"schema_version\": 999,", 1),
+        )?;
+        assert!(
+            utils::read_references(&env).is_err(),
+            "A reference with a newer schema version was accepted"
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_auth_stores_api_key() -> Result<()> {
+        // Set up isolated test environment
+        let env = MockEnv::new();
+
+        println!("Storing a Curseforge API key...");
+        let auth_result = commands::auth::run(
+            &env,
+            "curseforge".to_string(),
+            Some("test-api-key".to_string()),
+            true,
+        )
+        .await;
+        assert!(
+            auth_result.is_ok(),
+            "Auth command failed: {:?}",
+            auth_result
+        );
+
+        let config_path = utils::get_user_config_path(&env)?;
+        assert!(config_path.exists(), "User config file was not created");
+        let user_config = utils::load_user_config(&env)?;
+        assert_eq!(
+            user_config.curseforge.credentials.api_key.as_deref(),
+            Some("test-api-key")
+        );
+        assert!(user_config.modrinth.api_key.is_none());
+
+        // The file holds secrets and must only be readable by the user
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&config_path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "Unexpected permissions: {:o}", mode);
+        }
+
+        // Unknown services are rejected
+        let unknown_result =
+            commands::auth::run(&env, "github".to_string(), Some("key".to_string()), false).await;
+        assert!(unknown_result.is_err(), "Unknown service was accepted");
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_modpack_api() -> Result<()> {
+        use minepack::commands::add::{AddOptions, AddOutcome};
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::modpack::Modpack;
+
+        // Set up isolated test environment
+        let env = MockEnv::new();
+
+        // Opening a directory without a modpack fails
+        assert!(Modpack::open(&env).is_err());
+
+        let init_result = commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await;
+        assert!(
+            init_result.is_ok(),
+            "Init command failed: {:?}",
+            init_result
+        );
+
+        let modpack = Modpack::open(&env)?;
+        assert_eq!(modpack.config().name, "Test Modpack");
+
+        println!("Adding a mod through the library API...");
+        let outcome = modpack
+            .add(
+                "https://www.curseforge.com/minecraft/mc-mods/oritech/files/6332315",
+                true,
+                AddOptions::default(),
+            )
+            .await?;
+        let AddOutcome::Added(added) = outcome else {
+            panic!("Mod was not added: {:?}", outcome);
+        };
+        assert_eq!(added[0].reference.name, "Oritech");
+        assert!(!added[0].dependency);
+        assert!(added[0].path.exists());
+        assert_eq!(modpack.references()?.len(), added.len());
+
+        println!("Building through the library API...");
+        let build_result = modpack
+            .build(ExportFormat::CurseForge, BuildOptions::default())
+            .await?;
+        assert_eq!(build_result.format, ExportFormat::CurseForge);
+        assert_eq!(build_result.entry_count, added.len());
+        assert!(
+            build_result.output_path.exists(),
+            "Build output not found: {}",
+            build_result.output_path.display()
+        );
+
+        println!("Removing the mod through the library API...");
+        let removed = modpack.remove("oritech")?;
+        assert_eq!(removed.reference.name, "Oritech");
+        assert!(!removed.path.exists(), "Reference file was not removed");
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify the interactive code paths using scripted answers to the prompts
+    #[tokio::test]
+    async fn test_interactive_prompts() -> Result<()> {
+        use minepack::ui::Answer;
+
+        let env = MockEnv::new().with_answers([
+            // init
+            Answer::Text("Scripted Modpack".to_string()),
+            Answer::Default,
+            Answer::Text("Test Author".to_string()),
+            Answer::Text(String::new()),
+            Answer::Select(1),
+            Answer::Text("0.15.1".to_string()),
+            Answer::Text("1.21.1".to_string()),
+            // add
+            Answer::Text(
+                "https://www.curseforge.com/minecraft/mc-mods/oritech/files/6332315".to_string(),
+            ),
+            // build
+            Answer::Select(1),
+            // remove, declined first
+            Answer::Confirm(false),
+            Answer::Confirm(true),
+        ]);
+
+        commands::init::run(&env, None, None, None, None, None, None, None).await?;
+        let config = utils::load_config(&env)?;
+        assert_eq!(config.name, "Scripted Modpack");
+        assert_eq!(config.version, "1.0.0");
+        assert_eq!(config.description, None);
+        assert_eq!(config.minecraft.version, "1.21.1");
+        assert_eq!(config.minecraft.mod_loaders[0].id, "fabric");
+        assert_eq!(config.minecraft.mod_loaders[0].version, "0.15.1");
+
+        commands::add::run(&env, None, true, commands::add::AddOptions::default()).await?;
+        let references = utils::read_references(&env)?;
+        assert!(!references.is_empty(), "No references were added");
+
+        commands::build::run(&env, None, commands::build::BuildOptions::default()).await?;
+        let build_dir = env.current_dir()?.join("build");
+        let has_curseforge_zip = fs::read_dir(&build_dir)?
+            .filter_map(|entry| entry.ok())
+            .any(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with("-CurseForge.zip")
+            });
+        assert!(has_curseforge_zip, "No CurseForge pack was built");
+
+        let (path, _) = utils::find_reference(&env, "oritech")?;
+        commands::remove::run(&env, "oritech".to_string(), false).await?;
+        assert!(path.exists(), "Reference was removed without confirmation");
+        commands::remove::run(&env, "oritech".to_string(), false).await?;
+        assert!(
+            !path.exists(),
+            "Reference was not removed after confirmation"
+        );
+
+        assert_eq!(env.prompter.remaining(), 0, "Not all answers were used");
+
+        // Prompts without a scripted answer fail instead of blocking
+        assert!(
+            commands::build::run(&env, None, commands::build::BuildOptions::default())
+                .await
+                .is_err()
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that builds take downloaded files from the cache supplied by the environment
+    #[tokio::test]
+    async fn test_cache_dir_from_env() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+
+        let shared_cache = tempfile::tempdir()?;
+        let env = MockEnv::new().with_cache_dir(shared_cache.path());
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        fs::write(
+            utils::get_content_dir(&env, ContentType::Mod)?.join("cached.ex.json"),
+            r#"{
+                "name": "Cached",
+                "filename": "cached.jar",
+                "side": "both",
+                "link": { "type": "curseforge", "project_id": 123456, "file_id": 7890123 }
+            }"#,
+        )?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        assert_eq!(mod_cache_dir, shared_cache.path().join("mods"));
+        fs::create_dir_all(&mod_cache_dir)?;
+        fs::write(mod_cache_dir.join("cached.jar"), "shared cache")?;
+
+        let result =
+            commands::build::build(&env, ExportFormat::MultiMC, &BuildOptions::default()).await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&result.output_path)?)?;
+        let mut content = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name("Test Modpack/.minecraft/mods/cached.jar")?,
+            &mut content,
+        )?;
+        assert_eq!(content, "shared cache");
+        assert!(!env.current_dir()?.join(".minepack").join("cache").exists());
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify the Curseforge API and CDN settings of the project and user configuration
+    #[tokio::test]
+    async fn test_curseforge_mirror_settings() -> Result<()> {
+        use minepack::models::config::Curseforge;
+        use std::io::Read;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        // The user's API mirror is unreachable, the project's settings take precedence
+        let mut user_config = utils::load_user_config(&env)?;
+        user_config.curseforge.endpoints.api_url = Some("http://127.0.0.1:9/v1".to_string());
+        utils::save_user_config(&env, &user_config)?;
+
+        let add_result = commands::add::run(
+            &env,
+            Some("https://www.curseforge.com/minecraft/mc-mods/oritech/files/6332315".to_string()),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await;
+        assert!(add_result.is_err(), "The user's API URL was not used");
+
+        let mut config = utils::load_config(&env)?;
+        config.curseforge = Some(Curseforge {
+            api_url: Some(env.curseforge_api_url()),
+            cdn_hosts: vec!["https://cache.example.com/forgecdn".to_string()],
+        });
+        utils::save_config(&env, &config)?;
+
+        commands::add::run(
+            &env,
+            Some("https://www.curseforge.com/minecraft/mc-mods/oritech/files/6332315".to_string()),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await?;
+
+        // Download URLs in the Modrinth index point to the configured CDN host. The index
+        // lists the hashes of the files, which are already cached
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        for entry in fs::read_dir(utils::get_content_dir(&env, ContentType::Mod)?)? {
+            let reference: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(entry?.path())?)?;
+            let filename = reference["filename"].as_str().unwrap();
+            fs::write(mod_cache_dir.join(filename), filename)?;
+        }
+        commands::build::run(
+            &env,
+            Some("modrinth".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await?;
+        let mrpack_path = env
+            .current_dir()?
+            .join("build")
+            .join("Test Modpack-1.0.0.mrpack");
+        let mut archive = zip::ZipArchive::new(fs::File::open(&mrpack_path)?)?;
+        let mut index = String::new();
+        archive
+            .by_name("modrinth.index.json")?
+            .read_to_string(&mut index)?;
+        assert!(
+            index.contains("https://cache.example.com/forgecdn/files/6332/315/"),
+            "CDN host not used in the index: {}",
+            index
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that building the same inputs twice produces identical archives
+    #[tokio::test]
+    async fn test_reproducible_build() -> Result<()> {
+        let env = MockEnv::new().with_var("SOURCE_DATE_EPOCH", "1709214330");
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+        commands::add::run(
+            &env,
+            Some("https://www.curseforge.com/minecraft/mc-mods/oritech/files/6332315".to_string()),
+            true,
+            commands::add::AddOptions::default(),
+        )
+        .await?;
+
+        // Some user content that ends up in the overrides
+        let config_dir = env.current_dir()?.join("config");
+        fs::create_dir_all(config_dir.join("oritech"))?;
+        fs::write(config_dir.join("zeta.toml"), "enabled = true\n")?;
+        fs::write(config_dir.join("oritech").join("alpha.json"), "{}\n")?;
+
+        let output_path = env
+            .current_dir()?
+            .join("build")
+            .join("Test Modpack-1.0.0-CurseForge.zip");
+        commands::build::run(
+            &env,
+            Some("curseforge".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await?;
+        let first_build = fs::read(&output_path)?;
+
+        // Newer modification times of the inputs don't change the archive
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        fs::write(config_dir.join("zeta.toml"), "enabled = true\n")?;
+        commands::build::run(
+            &env,
+            Some("curseforge".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await?;
+        let second_build = fs::read(&output_path)?;
+        assert!(
+            first_build == second_build,
+            "Building twice produced different archives"
+        );
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(second_build))?;
+        let names = (0..archive.len())
+            .map(|index| Ok(archive.by_index(index)?.name().to_string()))
+            .collect::<Result<Vec<String>>>()?;
+        let mut sorted_names = names.clone();
+        sorted_names.sort();
+        assert_eq!(names, sorted_names, "Archive entries are not sorted");
+
+        let entry = archive.by_name("overrides/config/zeta.toml")?;
+        let modified = entry.last_modified();
+        assert_eq!(
+            (modified.year(), modified.month(), modified.day()),
+            (2024, 2, 29)
+        );
+        assert_eq!(entry.unix_mode().map(|mode| mode & 0o777), Some(0o644));
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that .minepackignore and overrides.include select the shipped files
+    #[tokio::test]
+    async fn test_override_ignore_and_include_rules() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::models::config::Overrides;
+        use std::path::PathBuf;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let project_dir = env.current_dir()?;
+        for (path, content) in [
+            ("README.md", "# Test Modpack\n"),
+            (".github/workflows/build.yml", "on: push\n"),
+            ("config/sodium.json", "{}\n"),
+            ("config/debug.log", "log\n"),
+            ("options.txt", "fov:90\n"),
+            (".minepackignore", "README.md\n.github/\n*.log\n"),
+        ] {
+            let path = project_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+
+        let result =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+        assert_eq!(
+            result.overrides,
+            [
+                PathBuf::from("config/sodium.json"),
+                PathBuf::from("options.txt")
+            ]
+        );
+        assert_eq!(result.skipped_overrides, 3);
+
+        let zipfile = fs::File::open(&result.output_path)?;
+        let mut archive = zip::ZipArchive::new(zipfile)?;
+        assert!(archive.by_name("overrides/config/sodium.json").is_ok());
+        assert!(archive.by_name("overrides/README.md").is_err());
+        assert!(archive.by_name("overrides/.minepackignore").is_err());
+
+        // With an include list, only matching files are shipped
+        let mut config = utils::load_config(&env)?;
+        config.overrides = Some(Overrides {
+            include: vec!["config/".to_string()],
+        });
+        utils::save_config(&env, &config)?;
+
+        let result =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+        assert_eq!(result.overrides, [PathBuf::from("config/sodium.json")]);
+        assert_eq!(result.skipped_overrides, 4);
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that client and server overrides end up on the matching side
+    #[tokio::test]
+    async fn test_side_specific_overrides() -> Result<()> {
+        use std::io::Read;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        // One mod for each side, already in the cache
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        for (index, side) in ["both", "client", "server"].into_iter().enumerate() {
+            fs::write(
+                mods_dir.join(format!("{}-mod.ex.json", side)),
+                format!(
+                    r#"{{
+                        "name": "{side} mod",
+                        "filename": "{side}-mod.jar",
+                        "side": "{side}",
+                        "link": {{ "type": "curseforge", "project_id": {id}, "file_id": {id} }}
+                    }}"#,
+                    side = side,
+                    id = 100000 + index
+                ),
+            )?;
+            fs::write(mod_cache_dir.join(format!("{}-mod.jar", side)), side)?;
+        }
+
+        let project_dir = env.current_dir()?;
+        for (path, content) in [
+            ("config/root.toml", "root"),
+            ("overrides/config/shared.toml", "common"),
+            ("client-overrides/config/shared.toml", "client"),
+            ("client-overrides/options.txt", "fov:90"),
+            ("server-overrides/server.properties", "motd=Test"),
+        ] {
+            let path = project_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+
+        let read_entry = |archive: &mut zip::ZipArchive<fs::File>, name: &str| -> Result<String> {
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .with_context(|| format!("{} is missing", name))?
+                .read_to_string(&mut content)?;
+            Ok(content)
+        };
+        let build_dir = project_dir.join("build");
+
+        // Modrinth packs have a folder for each side
+        commands::build::run(
+            &env,
+            Some("modrinth".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await?;
+        let mut archive =
+            zip::ZipArchive::new(fs::File::open(build_dir.join("Test Modpack-1.0.0.mrpack"))?)?;
+        assert_eq!(
+            read_entry(&mut archive, "overrides/config/root.toml")?,
+            "root"
+        );
+        assert_eq!(
+            read_entry(&mut archive, "overrides/config/shared.toml")?,
+            "common"
+        );
+        assert_eq!(
+            read_entry(&mut archive, "client-overrides/config/shared.toml")?,
+            "client"
+        );
+        assert_eq!(
+            read_entry(&mut archive, "server-overrides/server.properties")?,
+            "motd=Test"
+        );
+
+        // Server packs get the common and server files and no client-only mods
+        commands::build::run(
+            &env,
+            Some("server".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(
+            build_dir.join("Test Modpack-1.0.0-Server.zip"),
+        )?)?;
+        assert_eq!(read_entry(&mut archive, "config/shared.toml")?, "common");
+        assert_eq!(read_entry(&mut archive, "server.properties")?, "motd=Test");
+        assert_eq!(read_entry(&mut archive, "mods/both-mod.jar")?, "both");
+        assert_eq!(read_entry(&mut archive, "mods/server-mod.jar")?, "server");
+        assert!(archive.by_name("mods/client-mod.jar").is_err());
+        assert!(archive.by_name("options.txt").is_err());
+
+        // MultiMC instances are clients, client files replace the common ones
+        commands::build::run(
+            &env,
+            Some("multimc".to_string()),
+            commands::build::BuildOptions::default(),
+        )
+        .await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(
+            build_dir.join("Test Modpack-1.0.0-MultiMC.zip"),
+        )?)?;
+        let minecraft_dir = "Test Modpack/.minecraft";
+        assert_eq!(
+            read_entry(
+                &mut archive,
+                &format!("{}/config/shared.toml", minecraft_dir)
+            )?,
+            "client"
+        );
+        assert_eq!(
+            read_entry(&mut archive, &format!("{}/options.txt", minecraft_dir))?,
+            "fov:90"
+        );
+        assert_eq!(
+            read_entry(
+                &mut archive,
+                &format!("{}/mods/client-mod.jar", minecraft_dir)
+            )?,
+            "client"
+        );
+        assert!(archive
+            .by_name(&format!("{}/mods/server-mod.jar", minecraft_dir))
+            .is_err());
+        assert!(archive
+            .by_name(&format!("{}/server.properties", minecraft_dir))
+            .is_err());
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that build profiles select references by tag and side
+    #[tokio::test]
+    async fn test_build_profiles() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::models::config::{Profile, Side};
+        use std::path::PathBuf;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        for (index, (slug, side, tags)) in [
+            ("sodium", "client", r#"["performance"]"#),
+            ("terralith", "both", r#"["worldgen"]"#),
+            (
+                "shader-helper",
+                "client",
+                r#"["optional-visual", "performance"]"#,
+            ),
+            ("spark", "server", "[]"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            fs::write(
+                mods_dir.join(format!("{}.ex.json", slug)),
+                format!(
+                    r#"{{
+                        "name": "{slug}",
+                        "filename": "{slug}.jar",
+                        "side": "{side}",
+                        "link": {{ "type": "curseforge", "project_id": {id}, "file_id": {id} }},
+                        "tags": {tags}
+                    }}"#,
+                    slug = slug,
+                    side = side,
+                    tags = tags,
+                    id = 200000 + index
+                ),
+            )?;
+        }
+
+        let project_dir = env.current_dir()?;
+        for path in ["config/sodium.json", "config/terralith/biomes.json"] {
+            let path = project_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, "{}")?;
+        }
+
+        let mut config = utils::load_config(&env)?;
+        config.profiles.insert(
+            "lite".to_string(),
+            Profile {
+                exclude_tags: vec!["worldgen".to_string(), "optional-visual".to_string()],
+                sides: vec![Side::Client],
+                exclude_overrides: vec!["config/terralith/".to_string()],
+                ..Default::default()
+            },
+        );
+        config.profiles.insert(
+            "performance".to_string(),
+            Profile {
+                include_tags: vec!["performance".to_string()],
+                ..Default::default()
+            },
+        );
+        utils::save_config(&env, &config)?;
+
+        let full = commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+            .await?;
+        assert_eq!(full.entry_count, 4);
+        assert_eq!(full.overrides.len(), 2);
+
+        let lite = commands::build::build(
+            &env,
+            ExportFormat::CurseForge,
+            &BuildOptions {
+                profile: Some("lite".to_string()),
+            },
+        )
+        .await?;
+        assert_eq!(lite.profile.as_deref(), Some("lite"));
+        assert_eq!(
+            lite.entry_count, 1,
+            "Only sodium belongs to the lite profile"
+        );
+        assert_eq!(lite.overrides, [PathBuf::from("config/sodium.json")]);
+        assert!(lite
+            .output_path
+            .ends_with("Test Modpack-1.0.0-lite-CurseForge.zip"));
+
+        let zipfile = fs::File::open(&lite.output_path)?;
+        let mut archive = zip::ZipArchive::new(zipfile)?;
+        let manifest: minepack::api::curseforge::schema::Manifest =
+            serde_json::from_reader(archive.by_name("manifest.json")?)?;
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].project_id, 200000);
+
+        // Both references tagged "performance" are included
+        let performance = commands::build::build(
+            &env,
+            ExportFormat::CurseForge,
+            &BuildOptions {
+                profile: Some("performance".to_string()),
+            },
+        )
+        .await?;
+        assert_eq!(performance.entry_count, 2);
+
+        // References for both sides belong to every side
+        config.profiles.insert(
+            "client".to_string(),
+            Profile {
+                sides: vec![Side::Client],
+                ..Default::default()
+            },
+        );
+        utils::save_config(&env, &config)?;
+        let client = commands::build::build(
+            &env,
+            ExportFormat::CurseForge,
+            &BuildOptions {
+                profile: Some("client".to_string()),
+            },
+        )
+        .await?;
+        assert_eq!(client.entry_count, 3, "Only spark is left out");
+        assert_eq!(client.overrides.len(), 2);
+
+        // The full modpack is still there
+        assert!(full.output_path.exists());
+
+        let unknown = commands::build::run(
+            &env,
+            Some("curseforge".to_string()),
+            BuildOptions {
+                profile: Some("missing".to_string()),
+            },
+        )
+        .await;
+        assert!(unknown.is_err(), "Unknown profile was accepted");
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that optional mods are marked as such in every export format
+    #[tokio::test]
+    async fn test_optional_mods() -> Result<()> {
+        use minepack::api::modrinth::schema::{EnvSupport, Index};
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::models::config::Credits;
+        use std::io::Read;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        for (index, (slug, side, optional)) in [
+            ("core", "both", false),
+            ("minimap", "client", true),
+            ("backups", "server", true),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            fs::write(
+                mods_dir.join(format!("{}.ex.json", slug)),
+                format!(
+                    r#"{{
+                        "name": "{slug}",
+                        "filename": "{slug}.jar",
+                        "side": "{side}",
+                        "link": {{ "type": "curseforge", "project_id": {id}, "file_id": {id} }},
+                        "optional": {optional},
+                        "description": "The {slug} mod"
+                    }}"#,
+                    slug = slug,
+                    side = side,
+                    optional = optional,
+                    id = 300000 + index
+                ),
+            )?;
+            fs::write(mod_cache_dir.join(format!("{}.jar", slug)), slug)?;
+        }
+
+        let curseforge =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&curseforge.output_path)?)?;
+        let manifest: minepack::api::curseforge::schema::Manifest =
+            serde_json::from_reader(archive.by_name("manifest.json")?)?;
+        let required: Vec<(u32, bool)> = manifest
+            .files
+            .iter()
+            .map(|file| (file.project_id, file.required))
+            .collect();
+        assert_eq!(required.len(), 3);
+        assert!(required.contains(&(300000, true)));
+        assert!(required.contains(&(300001, false)));
+        assert!(required.contains(&(300002, false)));
+
+        // The mod list tells players what optional mods add
+        let mut modlist = String::new();
+        archive
+            .by_name("modlist.html")?
+            .read_to_string(&mut modlist)?;
+        assert!(
+            modlist.contains("<li>core: The core mod</li>"),
+            "{}",
+            modlist
+        );
+        assert!(modlist.contains("<li>minimap (optional): The minimap mod</li>"));
+
+        let modrinth =
+            commands::build::build(&env, ExportFormat::Modrinth, &BuildOptions::default()).await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&modrinth.output_path)?)?;
+        let index: Index = serde_json::from_reader(archive.by_name("modrinth.index.json")?)?;
+        let env_of = |path: &str| {
+            index
+                .files
+                .iter()
+                .find(|file| file.path == path)
+                .and_then(|file| file.env.clone())
+                .map(|env| (env.client, env.server))
+        };
+        assert_eq!(
+            env_of("mods/core.jar"),
+            Some((EnvSupport::Required, EnvSupport::Required))
+        );
+        assert_eq!(
+            env_of("mods/minimap.jar"),
+            Some((EnvSupport::Optional, EnvSupport::Unsupported))
+        );
+        assert_eq!(
+            env_of("mods/backups.jar"),
+            Some((EnvSupport::Unsupported, EnvSupport::Optional))
+        );
+
+        // MultiMC instances ship optional mods disabled
+        let multimc =
+            commands::build::build(&env, ExportFormat::MultiMC, &BuildOptions::default()).await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&multimc.output_path)?)?;
+        let mods = "Test Modpack/.minecraft/mods";
+        assert!(archive.by_name(&format!("{}/core.jar", mods)).is_ok());
+        assert!(archive
+            .by_name(&format!("{}/minimap.jar.disabled", mods))
+            .is_ok());
+        assert!(archive.by_name(&format!("{}/minimap.jar", mods)).is_err());
+
+        let mut config = utils::load_config(&env)?;
+        config.credits = Some(Credits {
+            markdown: true,
+            html: false,
+        });
+        utils::save_config(&env, &config)?;
+        let multimc =
+            commands::build::build(&env, ExportFormat::MultiMC, &BuildOptions::default()).await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&multimc.output_path)?)?;
+        let mut credits = String::new();
+        archive
+            .by_name("Test Modpack/.minecraft/CREDITS.md")?
+            .read_to_string(&mut credits)?;
+        assert!(
+            credits.contains("| minimap (optional): The minimap mod |  | minimap.jar |"),
+            "{}",
+            credits
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that changelogs compare git revisions and built modpacks
+    #[tokio::test]
+    async fn test_changelog() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::commands::changelog::{self, ChangelogOptions};
+        use std::process::Command;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let write_reference = |slug: &str, project_id: u32, file_id: u32| {
+            fs::write(
+                mods_dir.join(format!("{}.ex.json", slug)),
+                format!(
+                    r#"{{
+                        "name": "{slug}",
+                        "filename": "{slug}-{file_id}.jar",
+                        "side": "both",
+                        "link": {{ "type": "curseforge", "project_id": {project_id}, "file_id": {file_id} }}
+                    }}"#,
+                    slug = slug,
+                    project_id = project_id,
+                    file_id = file_id
+                ),
+            )
+        };
+        write_reference("oritech", 1030830, 6000000)?;
+        write_reference("unchanged", 400001, 400001)?;
+        write_reference("dropped", 400002, 400002)?;
+        write_reference("rolled-back", 400003, 400010)?;
+
+        let project_dir = env.current_dir()?;
+        let git = |args: &[&str]| -> Result<()> {
+            let status = Command::new("git")
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&project_dir)
+                .output()?
+                .status;
+            anyhow::ensure!(status.success(), "git {:?} failed", args);
+            Ok(())
+        };
+        git(&["init", "--quiet"])?;
+        git(&["add", "."])?;
+        git(&["commit", "--quiet", "-m", "Release 1.0.0"])?;
+        git(&["tag", "v1.0.0"])?;
+        let built =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+
+        write_reference("oritech", 1030830, 6332315)?;
+        fs::remove_file(mods_dir.join("dropped.ex.json"))?;
+        write_reference("rolled-back", 400003, 400005)?;
+        write_reference("new-mod", 400004, 400004)?;
+
+        let project_ids = |changes: &[changelog::ModChange]| -> Vec<u32> {
+            changes.iter().map(|change| change.project_id).collect()
+        };
+        let from_git = changelog::changelog(
+            &env,
+            &ChangelogOptions {
+                from: "v1.0.0".to_string(),
+                upstream: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(project_ids(&from_git.added), [400004]);
+        assert_eq!(project_ids(&from_git.removed), [400002]);
+        assert_eq!(project_ids(&from_git.updated), [1030830]);
+        assert_eq!(project_ids(&from_git.downgraded), [400003]);
+
+        let oritech = &from_git.updated[0];
+        assert_eq!(oritech.name, "oritech");
+        assert_eq!(
+            oritech
+                .old_file
+                .as_ref()
+                .map(|file| file.file_name.as_str()),
+            Some("oritech-6000000.jar")
+        );
+        assert_eq!(
+            oritech.new_file.as_ref().map(|file| file.file_id),
+            Some(6332315)
+        );
+        assert!(oritech
+            .upstream_changelog
+            .as_deref()
+            .is_some_and(|upstream| upstream.contains("Fixed a crash")));
+
+        let markdown = changelog::render_markdown(&from_git);
+        assert!(markdown.contains("## Added"));
+        assert!(markdown.contains("- **dropped** (dropped-400002.jar)"));
+        assert!(
+            markdown.contains("- **rolled-back**: rolled-back-400010.jar → rolled-back-400005.jar")
+        );
+
+        // A built modpack gives the same result, names come from the current references
+        let from_pack = changelog::changelog(
+            &env,
+            &ChangelogOptions {
+                from: built.output_path.to_string_lossy().to_string(),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(project_ids(&from_pack.added), [400004]);
+        assert_eq!(project_ids(&from_pack.removed), [400002]);
+        assert_eq!(project_ids(&from_pack.updated), [1030830]);
+        assert_eq!(project_ids(&from_pack.downgraded), [400003]);
+        assert_eq!(from_pack.updated[0].name, "oritech");
+
+        // JSON output written to a file
+        commands::changelog::run(
+            &env,
+            ChangelogOptions {
+                from: "v1.0.0".to_string(),
+                format: Some("json".to_string()),
+                output: Some("CHANGELOG.json".into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(project_dir.join("CHANGELOG.json"))?)?;
+        assert_eq!(json["from"], "v1.0.0");
+        assert_eq!(json["updated"][0]["new_file"]["file_id"], 6332315);
+
+        let unknown = changelog::changelog(
+            &env,
+            &ChangelogOptions {
+                from: "no-such-tag".to_string(),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(unknown.is_err(), "Unknown revision was accepted");
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that builds list the mods and credit their authors
+    #[tokio::test]
+    async fn test_mod_list_and_credits() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::models::config::Credits;
+        use std::io::Read;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        fs::write(
+            mods_dir.join("oritech.ex.json"),
+            r#"{
+                "name": "oritech",
+                "filename": "oritech-fabric-0.14.5.jar",
+                "side": "both",
+                "link": { "type": "curseforge", "project_id": 1030830, "file_id": 6332315 }
+            }"#,
+        )?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        fs::write(mod_cache_dir.join("oritech-fabric-0.14.5.jar"), "mock jar")?;
+
+        let read_entry = |path: &Path, name: &str| -> Result<String> {
+            let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .with_context(|| format!("{} is missing", name))?
+                .read_to_string(&mut content)?;
+            Ok(content)
+        };
+
+        // CurseForge packs always have a mod list, other files need to be enabled
+        let curseforge =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+        let modlist = read_entry(&curseforge.output_path, "modlist.html")?;
+        assert!(
+            modlist.contains(r#"<li><a href="https://example.com">Oritech (by rearth)</a></li>"#)
+        );
+        assert!(read_entry(&curseforge.output_path, "overrides/CREDITS.md").is_err());
+
+        let mut config = utils::load_config(&env)?;
+        config.credits = Some(Credits {
+            markdown: true,
+            html: true,
+        });
+        utils::save_config(&env, &config)?;
+
+        let server =
+            commands::build::build(&env, ExportFormat::Server, &BuildOptions::default()).await?;
+        let credits = read_entry(&server.output_path, "CREDITS.md")?;
+        assert!(credits.contains("Test Modpack 1.0.0 by Test Author"));
+        assert!(credits
+            .contains("| [Oritech](https://example.com) | rearth | oritech-fabric-0.14.5.jar |"));
+        assert!(read_entry(&server.output_path, "modlist.html")?.contains("Oritech (by rearth)"));
+
+        let curseforge =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+        assert!(read_entry(&curseforge.output_path, "overrides/CREDITS.md")?.contains("rearth"));
+
+        env.close()?;
+        Ok(())
+    }
+
+    /// Test to verify that releases bump the version, build and upload the CurseForge zip
+    #[tokio::test]
+    async fn test_release() -> Result<()> {
+        use minepack::commands::build::ExportFormat;
+        use minepack::commands::release::{self, ReleaseOptions, VersionBump};
+        use minepack::models::config::Release;
+
+        let env = MockEnv::new().with_var("CURSEFORGE_UPLOAD_TOKEN", "mock-upload-token");
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        // Releases can't be uploaded without a project
+        let no_project =
+            release::release(&env, VersionBump::Patch, &ReleaseOptions::default()).await;
+        assert!(
+            no_project.is_err(),
+            "Release without a project was uploaded"
+        );
+        assert_eq!(utils::load_config(&env)?.version, "1.0.0");
+
+        let mut config = utils::load_config(&env)?;
+        config.release = Some(Release {
+            curseforge_project_id: Some(123456),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let project_dir = env.current_dir()?;
+        fs::write(project_dir.join("CHANGES.md"), "- Added more machines")?;
+
+        let result = release::release(
+            &env,
+            VersionBump::Minor,
+            &ReleaseOptions {
+                formats: vec!["modrinth".to_string()],
+                release_type: Some("beta".to_string()),
+                changelog: Some("CHANGES.md".into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(result.previous_version, "1.0.0");
+        assert_eq!(result.version, "1.1.0");
+        assert_eq!(result.uploaded_file_id, Some(7000001));
+        let formats: Vec<ExportFormat> = result.builds.iter().map(|build| build.format).collect();
+        assert_eq!(formats, [ExportFormat::Modrinth, ExportFormat::CurseForge]);
+        assert!(project_dir
+            .join("build")
+            .join("Test Modpack-1.1.0-CurseForge.zip")
+            .exists());
+        assert_eq!(utils::load_config(&env)?.version, "1.1.0");
+
+        // A failed upload sets the version back
+        let mut config = utils::load_config(&env)?;
+        config.release = Some(Release {
+            curseforge_project_id: Some(123456),
+            upload_url: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let failed = release::release(&env, VersionBump::Major, &ReleaseOptions::default()).await;
+        assert!(failed.is_err(), "Upload to an unreachable host succeeded");
+        assert_eq!(utils::load_config(&env)?.version, "1.1.0");
+
+        // Without uploading, only the version is bumped and the modpack built
+        let local = release::release(
+            &env,
+            VersionBump::Patch,
+            &ReleaseOptions {
+                no_upload: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(local.version, "1.1.1");
+        assert_eq!(local.uploaded_file_id, None);
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_release_modrinth() -> Result<()> {
+        use minepack::api::modrinth::schema::Index;
+        use minepack::commands::build::ExportFormat;
+        use minepack::commands::release::{self, ReleaseOptions, VersionBump};
+        use minepack::models::config::{Curseforge, Release};
+
+        let env = MockEnv::new()
+            .with_var("CURSEFORGE_UPLOAD_TOKEN", "mock-upload-token")
+            .with_var("MODRINTH_TOKEN", "mock-modrinth-token");
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        // Only Modrinth: the .mrpack is built and published
+        let mut config = utils::load_config(&env)?;
+        config.release = Some(Release {
+            modrinth_project_id: Some("test-modpack".to_string()),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let result = release::release(&env, VersionBump::Patch, &ReleaseOptions::default()).await?;
+        assert_eq!(result.version, "1.0.1");
+        assert_eq!(result.uploaded_file_id, None);
+        assert_eq!(result.modrinth_version_id.as_deref(), Some("MOCKVER1"));
+        let formats: Vec<ExportFormat> = result.builds.iter().map(|build| build.format).collect();
+        assert_eq!(formats, [ExportFormat::Modrinth]);
+
+        // Both platforms from one release, the project can also be given by its ID
+        let mut config = utils::load_config(&env)?;
+        config.release = Some(Release {
+            curseforge_project_id: Some(123456),
+            modrinth_project_id: Some("TsTmPk01".to_string()),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let result = release::release(&env, VersionBump::Minor, &ReleaseOptions::default()).await?;
+        assert_eq!(result.version, "1.1.0");
+        assert_eq!(result.uploaded_file_id, Some(7000001));
+        assert_eq!(result.modrinth_version_id.as_deref(), Some("MOCKVER1"));
+
+        // A failed Modrinth upload after a Curseforge upload keeps the version
+        let mut config = utils::load_config(&env)?;
+        config.release = Some(Release {
+            curseforge_project_id: Some(123456),
+            modrinth_project_id: Some("locked-modpack".to_string()),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let failed = release::release(&env, VersionBump::Patch, &ReleaseOptions::default()).await;
+        assert!(failed.is_err(), "Upload to a forbidden project succeeded");
+        assert_eq!(utils::load_config(&env)?.version, "1.1.1");
+
+        // An unknown project or an unreachable API fail before anything is built or uploaded
+        for (project_id, api_url) in [
+            ("no-such-modpack", None),
+            ("test-modpack", Some("http://127.0.0.1:9".to_string())),
+        ] {
+            let mut config = utils::load_config(&env)?;
+            config.release = Some(Release {
+                curseforge_project_id: Some(123456),
+                modrinth_project_id: Some(project_id.to_string()),
+                modrinth_api_url: api_url,
+                ..Default::default()
+            });
+            utils::save_config(&env, &config)?;
+            let failed =
+                release::release(&env, VersionBump::Patch, &ReleaseOptions::default()).await;
+            assert!(failed.is_err(), "Release to {} succeeded", project_id);
+            assert_eq!(utils::load_config(&env)?.version, "1.1.1");
+        }
+        let error = release::release(&env, VersionBump::Patch, &ReleaseOptions::default())
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Failed to look up Modrinth project"),
+            "{}",
+            error
+        );
+
+        // The index lists the hashes and size of every file, downloaded from a host Modrinth
+        // accepts
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mods_dir)?;
+        fs::write(
+            mods_dir.join("core.ex.json"),
+            r#"{
+                "name": "core",
+                "filename": "core.jar",
+                "side": "both",
+                "link": { "type": "curseforge", "project_id": 300000, "file_id": 300000 }
+            }"#,
+        )?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        fs::write(mod_cache_dir.join("core.jar"), "core")?;
+
+        let mut config = utils::load_config(&env)?;
+        config.release = Some(Release {
+            modrinth_project_id: Some("test-modpack".to_string()),
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let error = release::release(&env, VersionBump::Patch, &ReleaseOptions::default())
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("downloaded from other hosts: mods/core.jar"),
+            "{}",
+            error
+        );
+        assert_eq!(utils::load_config(&env)?.version, "1.1.1");
+
+        let mut config = utils::load_config(&env)?;
+        config.curseforge = Some(Curseforge {
+            cdn_hosts: vec!["https://github.com/example/mirror/releases/download".to_string()],
+            ..Default::default()
+        });
+        utils::save_config(&env, &config)?;
+        let result = release::release(&env, VersionBump::Patch, &ReleaseOptions::default()).await?;
+        assert_eq!(result.modrinth_version_id.as_deref(), Some("MOCKVER1"));
+        let mut archive = zip::ZipArchive::new(fs::File::open(&result.builds[0].output_path)?)?;
+        let index: Index = serde_json::from_reader(archive.by_name("modrinth.index.json")?)?;
+        assert_eq!(index.files.len(), 1);
+        let file = &index.files[0];
+        assert_eq!(file.path, "mods/core.jar");
+        assert_eq!(
+            file.hashes["sha1"],
+            "94a0426e8d3203da5468ccf0c624f93cb37601e2"
+        );
+        assert_eq!(file.hashes["sha512"].len(), 128);
+        assert_eq!(file.file_size, 4);
+        assert!(file.downloads[0].starts_with("https://github.com/example/mirror/"));
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_instance() -> Result<()> {
+        use minepack::commands::sync::{self, SyncOptions, MANIFEST_FILENAME};
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let project_dir = env.current_dir()?;
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        for (index, (slug, side, optional)) in [
+            ("core", "both", false),
+            ("minimap", "client", true),
+            ("backups", "server", false),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            fs::write(
+                mods_dir.join(format!("{}.ex.json", slug)),
+                format!(
+                    r#"{{
+                        "name": "{slug}",
+                        "filename": "{slug}.jar",
+                        "side": "{side}",
+                        "link": {{ "type": "curseforge", "project_id": {id}, "file_id": {id} }},
+                        "optional": {optional}
+                    }}"#,
+                    slug = slug,
+                    side = side,
+                    optional = optional,
+                    id = 310000 + index
+                ),
+            )?;
+            fs::write(mod_cache_dir.join(format!("{}.jar", slug)), slug)?;
+        }
+        fs::create_dir_all(project_dir.join("config"))?;
+        fs::write(project_dir.join("config").join("core.toml"), "speed = 1")?;
+
+        // A Prism Launcher instance with a mod and options of the player
+        let instance = project_dir.join("instances").join("Test");
+        let game_dir = instance.join(".minecraft");
+        fs::create_dir_all(game_dir.join("mods"))?;
+        fs::write(instance.join("instance.cfg"), "InstanceType=OneSix\n")?;
+        fs::write(game_dir.join("mods").join("own.jar"), "own")?;
+        fs::write(game_dir.join("options.txt"), "fov:90")?;
+        fs::write(project_dir.join(".minepackignore"), "instances/\n")?;
+
+        let options = SyncOptions {
+            instance: Path::new("instances").join("Test"),
+            ..Default::default()
+        };
+        let dry_run = sync::sync(
+            &env,
+            &SyncOptions {
+                dry_run: true,
+                ..options.clone()
+            },
+        )
+        .await?;
+        assert_eq!(dry_run.added.len(), 3);
+        assert!(!game_dir.join("mods").join("core.jar").exists());
+        assert!(!game_dir.join(MANIFEST_FILENAME).exists());
+
+        let first = sync::sync(&env, &options).await?;
+        assert_eq!(first.game_dir, game_dir);
+        let mut added: Vec<String> = first
+            .added
+            .iter()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        added.sort();
+        assert_eq!(
+            added,
+            [
+                "config/core.toml",
+                "mods/core.jar",
+                "mods/minimap.jar.disabled"
+            ]
+        );
+        assert!(!game_dir.join("mods").join("backups.jar").exists());
+        assert!(game_dir.join(MANIFEST_FILENAME).exists());
+
+        // Nothing to do on the next sync
+        let again = sync::sync(&env, &options).await?;
+        assert!(again.added.is_empty() && again.updated.is_empty() && again.removed.is_empty());
+        assert_eq!(again.unchanged, 3);
+
+        // The player enables the optional mod, the project changes
+        fs::rename(
+            game_dir.join("mods").join("minimap.jar.disabled"),
+            game_dir.join("mods").join("minimap.jar"),
+        )?;
+        fs::write(project_dir.join("config").join("core.toml"), "speed = 2")?;
+        fs::remove_file(mods_dir.join("core.ex.json"))?;
+
+        let second = sync::sync(&env, &options).await?;
+        assert_eq!(second.updated, [Path::new("config/core.toml")]);
+        assert_eq!(second.removed, [Path::new("mods/core.jar")]);
+        assert!(second.added.is_empty());
+        assert_eq!(
+            fs::read_to_string(game_dir.join("config").join("core.toml"))?,
+            "speed = 2"
+        );
+        assert!(!game_dir.join("mods").join("core.jar").exists());
+        assert!(game_dir.join("mods").join("minimap.jar").exists());
+        assert!(!game_dir.join("mods").join("minimap.jar.disabled").exists());
+
+        // Files the player changed since the last sync are neither replaced nor removed
+        fs::write(game_dir.join("config").join("core.toml"), "speed = 5")?;
+        fs::write(project_dir.join("config").join("core.toml"), "speed = 3")?;
+        fs::write(game_dir.join("mods").join("minimap.jar"), "patched")?;
+        fs::remove_file(mods_dir.join("minimap.ex.json"))?;
+
+        let third = sync::sync(&env, &options).await?;
+        assert_eq!(
+            third.modified,
+            [Path::new("config/core.toml"), Path::new("mods/minimap.jar")]
+        );
+        assert!(third.updated.is_empty() && third.removed.is_empty());
+        assert_eq!(
+            fs::read_to_string(game_dir.join("config").join("core.toml"))?,
+            "speed = 5"
+        );
+        assert_eq!(
+            fs::read_to_string(game_dir.join("mods").join("minimap.jar"))?,
+            "patched"
+        );
+
+        // The removed mod now belongs to the player, the config is updated once it's restored
+        let fourth = sync::sync(&env, &options).await?;
+        assert_eq!(fourth.modified, [Path::new("config/core.toml")]);
+        fs::write(game_dir.join("config").join("core.toml"), "speed = 2")?;
+        let fifth = sync::sync(&env, &options).await?;
+        assert!(fifth.modified.is_empty());
+        assert_eq!(fifth.updated, [Path::new("config/core.toml")]);
+        assert_eq!(
+            fs::read_to_string(game_dir.join("config").join("core.toml"))?,
+            "speed = 3"
+        );
+
+        // Files of the player are left alone
+        assert!(game_dir.join("mods").join("own.jar").exists());
+        assert_eq!(fs::read_to_string(game_dir.join("options.txt"))?, "fov:90");
+
+        let missing = sync::sync(
+            &env,
+            &SyncOptions {
+                instance: "missing".into(),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(missing.is_err(), "Synced into a missing instance");
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pull_instance() -> Result<()> {
+        use minepack::commands::pull::{self, FileChange, PullOptions};
+        use minepack::commands::sync::{self, SyncOptions};
+        use minepack::ui::Answer;
+
+        let env = MockEnv::new().with_answers([
+            Answer::Confirm(true),
+            Answer::Confirm(false),
+            Answer::Confirm(true),
+        ]);
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let project_dir = env.current_dir()?;
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        for (index, slug) in ["core", "minimap"].into_iter().enumerate() {
+            fs::write(
+                mods_dir.join(format!("{}.ex.json", slug)),
+                format!(
+                    r#"{{
+                        "name": "{slug}",
+                        "filename": "{slug}.jar",
+                        "side": "both",
+                        "link": {{ "type": "curseforge", "project_id": {id}, "file_id": {id} }}
+                    }}"#,
+                    slug = slug,
+                    id = 320000 + index
+                ),
+            )?;
+            fs::write(mod_cache_dir.join(format!("{}.jar", slug)), slug)?;
+        }
+        let config_dir = project_dir.join("config");
+        fs::create_dir_all(&config_dir)?;
+        fs::write(config_dir.join("a.toml"), "a = 1\n")?;
+        fs::write(config_dir.join("b.toml"), "b = 1\n")?;
+        fs::write(project_dir.join(".minepackignore"), "instance/\n")?;
+
+        let game_dir = project_dir.join("instance");
+        fs::create_dir_all(&game_dir)?;
+        sync::sync(
+            &env,
+            &SyncOptions {
+                instance: "instance".into(),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        // Play-testing changes a config, adds a script, deletes a config and swaps a mod
+        fs::write(game_dir.join("config").join("a.toml"), "a = 2\n")?;
+        fs::remove_file(game_dir.join("config").join("b.toml"))?;
+        let scripts_dir = game_dir.join("kubejs").join("startup_scripts");
+        fs::create_dir_all(&scripts_dir)?;
+        fs::write(scripts_dir.join("items.js"), "// items")?;
+        fs::remove_file(game_dir.join("mods").join("core.jar"))?;
+        fs::write(game_dir.join("mods").join("own.jar"), "own")?;
+        fs::write(game_dir.join("options.txt"), "fov:90")?;
+
+        let options = PullOptions {
+            instance: "instance".into(),
+            ..Default::default()
+        };
+        let dry_run = pull::pull(
+            &env,
+            &PullOptions {
+                dry_run: true,
+                ..options.clone()
+            },
+        )?;
+        assert!(dry_run.applied.is_empty());
+        let changes: Vec<(FileChange, String)> = dry_run
+            .skipped
+            .iter()
+            .map(|file| {
+                (
+                    file.change,
+                    file.project_path.to_string_lossy().replace('\\', "/"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (FileChange::Modified, "config/a.toml".to_string()),
+                (
+                    FileChange::Added,
+                    "kubejs/startup_scripts/items.js".to_string()
+                ),
+                (FileChange::Removed, "config/b.toml".to_string()),
+            ]
+        );
+        assert_eq!(dry_run.untracked_jars, ["own.jar"]);
+        assert_eq!(dry_run.missing_jars, ["core.jar"]);
+        assert_eq!(fs::read_to_string(config_dir.join("a.toml"))?, "a = 1\n");
+
+        // The script is declined
+        let result = pull::pull(&env, &options)?;
+        assert_eq!(result.applied.len(), 2);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(fs::read_to_string(config_dir.join("a.toml"))?, "a = 2\n");
+        assert!(!config_dir.join("b.toml").exists());
+        assert!(!project_dir.join("kubejs").exists());
+        assert!(!project_dir.join("options.txt").exists());
+
+        let accept_all = pull::pull(
+            &env,
+            &PullOptions {
+                yes: true,
+                ..options
+            },
+        )?;
+        assert_eq!(accept_all.applied.len(), 1);
+        assert!(project_dir
+            .join("kubejs")
+            .join("startup_scripts")
+            .join("items.js")
+            .exists());
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_manual_downloads() -> Result<()> {
+        use minepack::api::curseforge::fingerprint::fingerprint;
+        use minepack::commands::build::manual::MANUAL_DOWNLOADS_FILENAME;
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::commands::fetch_manual::{self, FetchManualOptions};
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        fs::write(
+            mods_dir.join("core.ex.json"),
+            r#"{
+                "name": "Core",
+                "filename": "core.jar",
+                "side": "both",
+                "link": { "type": "curseforge", "project_id": 330000, "file_id": 330001 }
+            }"#,
+        )?;
+        fs::write(mod_cache_dir.join("core.jar"), "core")?;
+
+        // Marked when it was added
+        let restricted_content = b"restricted mod content";
+        fs::write(
+            mods_dir.join("restricted.ex.json"),
+            format!(
+                r#"{{
+                    "name": "Restricted",
+                    "filename": "restricted.jar",
+                    "side": "both",
+                    "link": {{ "type": "curseforge", "project_id": 330002, "file_id": 330003 }},
+                    "manual_download": {{
+                        "page_url": "https://www.curseforge.com/minecraft/mc-mods/restricted/files/330003",
+                        "fingerprint": {}
+                    }}
+                }}"#,
+                fingerprint(restricted_content)
+            ),
+        )?;
+        // Added before its author disallowed distribution, the API has no download URL
+        fs::write(
+            mods_dir.join("unmarked.ex.json"),
+            r#"{
+                "name": "Unmarked",
+                "filename": "unmarked.jar",
+                "side": "both",
+                "link": { "type": "curseforge", "project_id": 990001, "file_id": 990002 }
+            }"#,
+        )?;
+
+        let multimc =
+            commands::build::build(&env, ExportFormat::MultiMC, &BuildOptions::default()).await?;
+        let names: Vec<&str> = multimc
+            .manual_downloads
+            .iter()
+            .map(|file| file.file_name.as_str())
+            .collect();
+        assert_eq!(names, ["restricted.jar", "unmarked.jar"]);
+        assert_eq!(
+            multimc.manual_downloads[1].page_url,
+            "https://www.curseforge.com/projects/990001"
+        );
+        let mut archive = zip::ZipArchive::new(fs::File::open(&multimc.output_path)?)?;
+        let mut list = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name(&format!(
+                "Test Modpack/.minecraft/{}",
+                MANUAL_DOWNLOADS_FILENAME
+            ))?,
+            &mut list,
+        )?;
+        assert!(
+            list.contains("https://www.curseforge.com/minecraft/mc-mods/restricted/files/330003")
+        );
+        assert!(archive
+            .by_name("Test Modpack/.minecraft/mods/core.jar")
+            .is_ok());
+        assert!(archive
+            .by_name("Test Modpack/.minecraft/mods/restricted.jar")
+            .is_err());
+
+        // The build recorded the fingerprint of the unmarked file, so it can be fetched
+        let unmarked = utils::read_references(&env)?
+            .into_iter()
+            .find(|(_, reference)| reference.file_id() == 990002)
+            .and_then(|(_, reference)| reference.manual_download)
+            .context("The unmarked reference wasn't marked")?;
+        let unmarked_content = b"unmarked mod content";
+        assert_eq!(unmarked.fingerprint, fingerprint(unmarked_content) as u64);
+        assert_eq!(
+            unmarked.page_url,
+            "https://www.curseforge.com/minecraft/mc-mods/unmarked/files/990002"
+        );
+
+        // The CurseForge app downloads them itself
+        let curseforge =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await?;
+        assert!(curseforge.manual_downloads.is_empty());
+
+        // The browser renamed the download, it's found by its fingerprint
+        let downloads_dir = env.current_dir()?.join("downloads");
+        fs::create_dir_all(&downloads_dir)?;
+        fs::write(downloads_dir.join("other.jar"), "something else")?;
+        fs::write(downloads_dir.join("restricted (1).jar"), restricted_content)?;
+        fs::write(downloads_dir.join("unmarked.jar"), unmarked_content)?;
+        let listed = fetch_manual::fetch_manual(&env, &FetchManualOptions::default()).await?;
+        assert_eq!(listed.missing.len(), 2);
+        let fetched = fetch_manual::fetch_manual(
+            &env,
+            &FetchManualOptions {
+                dir: Some("downloads".into()),
+                watch: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(
+            fetched.fetched,
+            [
+                mod_cache_dir.join("restricted.jar"),
+                mod_cache_dir.join("unmarked.jar")
+            ]
+        );
+        assert!(fetched.missing.is_empty());
+        assert_eq!(
+            fs::read(mod_cache_dir.join("restricted.jar"))?,
+            restricted_content
+        );
+
+        let multimc =
+            commands::build::build(&env, ExportFormat::MultiMC, &BuildOptions::default()).await?;
+        assert!(multimc.manual_downloads.is_empty());
+        let mut archive = zip::ZipArchive::new(fs::File::open(&multimc.output_path)?)?;
+        assert!(archive
+            .by_name("Test Modpack/.minecraft/mods/restricted.jar")
+            .is_ok());
+        assert!(archive
+            .by_name("Test Modpack/.minecraft/mods/unmarked.jar")
+            .is_ok());
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reference_store() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::utils::reference_store::ReferenceStore;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let resourcepacks_dir = utils::get_content_dir(&env, ContentType::ResourcePack)?;
+        fs::create_dir_all(&resourcepacks_dir)?;
+        let reference = |name: &str, project_id: &str, file_id: u32| {
+            format!(
+                r#"{{
+                    "name": "{name}",
+                    "filename": "{name}.jar",
+                    "side": "both",
+                    "link": {{ "type": "curseforge", "project_id": {project_id}, "file_id": {file_id} }}
+                }}"#
+            )
+        };
+        fs::write(
+            mods_dir.join("sodium.ex.json"),
+            reference("Sodium", "394468", 5000001),
+        )?;
+        fs::write(
+            resourcepacks_dir.join("faithful.ex.json"),
+            reference("Faithful", "237645", 4750000),
+        )?;
+
+        let mut store = ReferenceStore::load(&env)?;
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store
+                .get_by_slug("faithful")
+                .unwrap()
+                .reference
+                .content_type,
+            ContentType::ResourcePack
+        );
+        assert_eq!(store.get_by_project_id(394468).unwrap().slug, "sodium");
+        assert_eq!(store.get_by_file_id(4750000).unwrap().slug, "faithful");
+        assert_eq!(store.find("Sodium")?.slug, "sodium");
+        assert!(store.find("lithium").is_err());
+
+        // Saving a new file of a project replaces its reference, keeping the file name
+        let mut updated = store.get_by_slug("sodium").unwrap().reference.clone();
+        updated.filename = "sodium-new.jar".to_string();
+        updated.link = minepack::models::config::Link::CurseForge {
+            project_id: 394468,
+            file_id: 5000002,
+            download_url: None,
+        };
+        let path = store.save(&env, "sodium-renamed", updated)?.path.clone();
+        assert_eq!(path, mods_dir.join("sodium.ex.json"));
+        assert!(!mods_dir.join("sodium-renamed.ex.json").exists());
+        assert_eq!(store.len(), 2);
+        assert!(store.get_by_file_id(5000001).is_none());
+        let store = ReferenceStore::load(&env)?;
+        assert_eq!(
+            store.get_by_project_id(394468).unwrap().reference.filename,
+            "sodium-new.jar"
+        );
+
+        // Malformed files are reported with their path instead of being misread
+        let broken_path = mods_dir.join("broken.ex.json");
+        fs::write(&broken_path, reference("Broken", "\"oops\"", 1))?;
+        let error =
+            commands::build::build(&env, ExportFormat::CurseForge, &BuildOptions::default())
+                .await
+                .unwrap_err();
+        assert!(
+            format!("{:#}", error)
+                .contains(&format!("Invalid reference file {}", broken_path.display())),
+            "{:#}",
+            error
+        );
+        fs::write(&broken_path, reference("Broken", "0", 1))?;
+        let error = ReferenceStore::load(&env).unwrap_err().to_string();
+        assert!(
+            error.ends_with("link.project_id must not be 0"),
+            "{}",
+            error
+        );
+
+        // Two references to the same project
+        fs::write(&broken_path, reference("Sodium copy", "394468", 5000003))?;
+        let error = ReferenceStore::load(&env).unwrap_err().to_string();
+        assert!(
+            error.contains("both reference Curseforge project 394468"),
+            "{}",
+            error
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reference_slug_collision() -> Result<()> {
+        use minepack::models::config::{Link, Reference, Side};
+        use minepack::utils::reference_store::ReferenceStore;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let reference = |name: &str, project_id: u32, content_type: ContentType| Reference {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            name: name.to_string(),
+            filename: format!("{}.jar", project_id),
+            side: Side::Both,
+            link: Link::CurseForge {
+                project_id,
+                file_id: project_id + 1,
+                download_url: None,
+            },
+            content_type,
+            pinned: false,
+            pin_reason: None,
+            optional: false,
+            description: None,
+            tags: Vec::new(),
+            manual_download: None,
+        };
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mut store = ReferenceStore::load(&env)?;
+        let path = store
+            .save(
+                &env,
+                "Storage Drawers",
+                reference("Drawers", 100, ContentType::Mod),
+            )?
+            .path
+            .clone();
+        assert_eq!(path, mods_dir.join("storage-drawers.ex.json"));
+
+        // Another project with the same slug doesn't replace the first one
+        let path = store
+            .save(
+                &env,
+                "storage-drawers",
+                reference("Drawers fork", 200, ContentType::Mod),
+            )?
+            .path
+            .clone();
+        assert_eq!(path, mods_dir.join("storage-drawers-200.ex.json"));
+        // Slugs are unique across content directories
+        let path = store
+            .save(
+                &env,
+                "storage-drawers",
+                reference("Drawers textures", 300, ContentType::ResourcePack),
+            )?
+            .path
+            .clone();
+        assert_eq!(
+            path,
+            utils::get_content_dir(&env, ContentType::ResourcePack)?
+                .join("storage-drawers-300.ex.json")
+        );
+        // Files the store doesn't know aren't overwritten either
+        fs::write(mods_dir.join("notes.ex.json"), "not a reference")?;
+        let path = store
+            .save(&env, "notes", reference("Notes", 400, ContentType::Mod))?
+            .path
+            .clone();
+        assert_eq!(path, mods_dir.join("notes-400.ex.json"));
+        assert_eq!(
+            fs::read_to_string(mods_dir.join("notes.ex.json"))?,
+            "not a reference"
+        );
+        fs::remove_file(mods_dir.join("notes.ex.json"))?;
+
+        let store = ReferenceStore::load(&env)?;
+        assert_eq!(store.len(), 4);
+        assert_eq!(
+            store.get_by_slug("storage-drawers").unwrap().reference.name,
+            "Drawers"
+        );
+        assert_eq!(
+            store
+                .get_by_slug("storage-drawers-200")
+                .unwrap()
+                .reference
+                .name,
+            "Drawers fork"
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nested_reference_folders() -> Result<()> {
+        use minepack::commands::build::{BuildOptions, ExportFormat};
+        use minepack::models::config::{Profile, References};
+        use minepack::utils::reference_store::ReferenceStore;
+
+        let env = MockEnv::new();
+        commands::init::run(
+            &env,
+            Some("Test Modpack".to_string()),
+            Some("1.0.0".to_string()),
+            Some("Test Author".to_string()),
+            Some("A test modpack".to_string()),
+            Some("fabric".to_string()),
+            Some("1.21.1".to_string()),
+            Some("0.15.1".to_string()),
+        )
+        .await?;
+
+        let mut config = utils::load_config(&env)?;
+        config.references = Some(References { folder_tags: true });
+        config.profiles.insert(
+            "lite".to_string(),
+            Profile {
+                exclude_tags: vec!["worldgen".to_string()],
+                ..Default::default()
+            },
+        );
+        utils::save_config(&env, &config)?;
+
+        let mods_dir = utils::get_content_dir(&env, ContentType::Mod)?;
+        let mod_cache_dir = utils::get_minepack_cache_content_dir(&env, ContentType::Mod)?;
+        fs::create_dir_all(&mod_cache_dir)?;
+        for (index, relative_path) in ["sodium", "library/jei", "worldgen/biomes/terra"]
+            .into_iter()
+            .enumerate()
+        {
+            let path = mods_dir.join(format!("{}.ex.json", relative_path));
+            fs::create_dir_all(path.parent().unwrap())?;
+            let slug = relative_path.rsplit('/').next().unwrap();
+            fs::write(
+                &path,
+                format!(
+                    r#"{{
+                        "name": "{slug}",
+                        "filename": "{slug}.jar",
+                        "side": "both",
+                        "link": {{ "type": "curseforge", "project_id": {id}, "file_id": {id} }}
+                    }}"#,
+                    id = 500000 + index
+                ),
+            )?;
+            fs::write(mod_cache_dir.join(format!("{}.jar", slug)), slug)?;
+        }
+
+        let store = ReferenceStore::load(&env)?;
+        assert_eq!(store.len(), 3);
+        assert_eq!(
+            store.get_by_slug("terra").unwrap().folders,
+            ["worldgen", "biomes"]
+        );
+
+        // Subdirectories are flattened into the mods folder of the instance
+        let result =
+            commands::build::build(&env, ExportFormat::MultiMC, &BuildOptions::default()).await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&result.output_path)?)?;
+        for name in ["sodium.jar", "jei.jar", "terra.jar"] {
+            assert!(
+                archive
+                    .by_name(&format!("Test Modpack/.minecraft/mods/{}", name))
+                    .is_ok(),
+                "{} is missing",
+                name
+            );
+        }
+        assert!(!archive
+            .file_names()
+            .any(|name| name.contains("library") || name.ends_with(".ex.json")));
+
+        // Folder names are tags that profiles select references by
+        let result = commands::build::build(
+            &env,
+            ExportFormat::MultiMC,
+            &BuildOptions {
+                profile: Some("lite".to_string()),
+            },
+        )
+        .await?;
+        let mut archive = zip::ZipArchive::new(fs::File::open(&result.output_path)?)?;
+        assert!(archive
+            .by_name("Test Modpack/.minecraft/mods/jei.jar")
+            .is_ok());
+        assert!(archive
+            .by_name("Test Modpack/.minecraft/mods/terra.jar")
+            .is_err());
+
+        // Changed references stay in their folder
+        commands::pin::run(&env, Some("jei".to_string()), None)?;
+        let store = ReferenceStore::load(&env)?;
+        let jei = store.get_by_slug("jei").unwrap();
+        assert_eq!(jei.path, mods_dir.join("library").join("jei.ex.json"));
+        assert!(jei.reference.pinned);
+        assert!(!mods_dir.join("jei.ex.json").exists());
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_matches_api_results_by_id() -> Result<()> {
+        use minepack::utils::reference_store::ReferenceStore;
+
+        let env = MockEnv::new();
+        let mock_modpack_dir =
+            tempfile::tempdir().context("Failed to create mock modpack directory")?;
+        // The API returns the projects sorted by ID, not in the order of the manifest, and
+        // leaves out the deleted project
+        let manifest = r#"{
+            "minecraft": {
+                "version": "1.21.1",
+                "modLoaders": [{ "id": "fabric-0.16.10", "primary": true }]
+            },
+            "manifestType": "minecraftModpack",
+            "manifestVersion": 1,
+            "name": "Test Import Modpack",
+            "version": "1.0.0",
+            "author": "Test Author",
+            "files": [
+                { "projectID": 1030830, "fileID": 6332315, "required": true },
+                { "projectID": 999999, "fileID": 999998, "required": true },
+                { "projectID": 306612, "fileID": 4090886, "required": true }
+            ],
+            "overrides": "overrides"
+        }"#;
+        fs::write(mock_modpack_dir.path().join("manifest.json"), manifest)?;
+        let zip_path = env.current_dir()?.join("test-modpack.zip");
+        create_zip_from_dir(mock_modpack_dir.path(), &zip_path)?;
+
+        let result = commands::import::import(&env, &zip_path).await?;
+        assert_eq!(result.references.len(), 2);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].project_id, 999999);
+        assert_eq!(result.skipped[0].file_id, 999998);
+
+        let store = ReferenceStore::load(&env)?;
+        let oritech = store.get_by_project_id(1030830).unwrap();
+        assert_eq!(oritech.slug, "oritech");
+        assert_eq!(oritech.reference.name, "Oritech");
+        assert_eq!(oritech.reference.file_id(), 6332315);
+        let fabric_api = store.get_by_project_id(306612).unwrap();
+        assert_eq!(fabric_api.slug, "fabric-api");
+        assert_eq!(fabric_api.reference.name, "Fabric API");
+        assert_eq!(
+            fabric_api.reference.filename,
+            "fabric-api-0.66.3+1.19.3.jar"
+        );
+
+        env.close()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_unsafe_archive() -> Result<()> {
+        use std::io::Write;
+
+        let env = MockEnv::new();
+        let zip_path = env.current_dir()?.join("evil-modpack.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path)?);
+        let options = zip::write::FileOptions::default();
+        zip.start_file("manifest.json", options)?;
+        zip.write_all(br#"{ "minecraft": { "version": "1.21.1", "modLoaders": [] }, "manifestType": "minecraftModpack", "manifestVersion": 1, "name": "Evil", "version": "1.0.0", "author": "Evil", "files": [], "overrides": "overrides" }"#)?;
+        zip.start_file("overrides/../../escaped.txt", options)?;
+        zip.write_all(b"evil")?;
+        zip.finish()?;
+
+        let error = commands::import::import(&env, &zip_path).await.unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("points outside the archive"),
+            "{:#}",
+            error
+        );
+        assert!(!env.current_dir()?.join("escaped.txt").exists());
+        assert!(!env
+            .current_dir()?
+            .parent()
+            .unwrap()
+            .join("escaped.txt")
+            .exists());
+        assert!(!utils::modpack_exists(&env));
+
+        // Overrides can't replace the project's configuration or git setup
+        let zip_path = env.current_dir()?.join("sneaky-modpack.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path)?);
+        zip.start_file("manifest.json", options)?;
+        zip.write_all(br#"{ "minecraft": { "version": "1.21.1", "modLoaders": [] }, "manifestType": "minecraftModpack", "manifestVersion": 1, "name": "Sneaky", "version": "1.0.0", "author": "Sneaky", "files": [], "overrides": "overrides" }"#)?;
+        zip.start_file("overrides/minepack.json", options)?;
+        zip.write_all(br#"{ "name": "Replaced" }"#)?;
+        zip.start_file("overrides/.git/hooks/x", options)?;
+        zip.write_all(b"#!/bin/sh")?;
+        zip.start_file("overrides/config/kept.toml", options)?;
+        zip.write_all(b"kept = true")?;
+        zip.finish()?;
+
+        commands::import::import(&env, &zip_path).await?;
+        let config = utils::load_config(&env)?;
+        assert_eq!(config.name, "Sneaky");
+        assert!(!env.current_dir()?.join(".git/hooks/x").exists());
+        assert!(env.current_dir()?.join("config/kept.toml").exists());
+
+        env.close()?;
+        Ok(())
+    }
 }